@@ -0,0 +1,763 @@
+use crate::{
+    buffer::CacheWriteExt,
+    definitions::{
+        codec::{opcodes, Codec, Set, U8List, I32, U16, U8},
+        Definition, DefinitionError,
+    },
+    model::Model,
+    revision::Revision,
+    trig::{cosine, sine},
+};
+use osrs_bytes::{ReadExt, WriteExt};
+use std::io;
+use thiserror::Error;
+
+/// The archive holding animation frames, one group per frame set and one
+/// file per frame.
+pub const FRAMES_ARCHIVE: u8 = 0;
+/// The archive holding frame maps (skeletons), one group per frame map.
+pub const FRAME_MAPS_ARCHIVE: u8 = 1;
+
+/// Sets the origin of later rotations and scales to the centre of the labels.
+pub const TRANSFORM_ORIGIN: u8 = 0;
+pub const TRANSFORM_TRANSLATE: u8 = 1;
+pub const TRANSFORM_ROTATE: u8 = 2;
+pub const TRANSFORM_SCALE: u8 = 3;
+/// Changes the transparency of the faces with the labels.
+pub const TRANSFORM_ALPHA: u8 = 5;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum AnimError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("frame uses frame map {actual} but frame map {expected} was given")]
+    FrameMapMismatch { expected: u16, actual: u16 },
+    #[error("frame has {0} transforms but its frame map has fewer")]
+    TooManyTransforms(usize),
+}
+
+/// A transform of a frame map: its type and the labels of the vertices or
+/// faces it moves.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameMapTransform {
+    pub transform_type: u8,
+    pub labels: Vec<u8>,
+}
+
+/// A frame map (skeleton) from the frame maps archive, listing the
+/// transforms its frames can apply.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameMap {
+    pub id: u16,
+    pub transforms: Vec<FrameMapTransform>,
+}
+
+impl FrameMap {
+    /// Decode a frame map
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the frame map, which is its group in the frame maps archive
+    /// * `buf` - The contents of file 0 of the group
+    pub fn decode(id: u16, mut buf: &[u8]) -> Result<FrameMap, AnimError> {
+        let len = buf.read_u8()? as usize;
+        let mut transforms = vec![FrameMapTransform::default(); len];
+        for transform in transforms.iter_mut() {
+            transform.transform_type = buf.read_u8()?;
+        }
+        for transform in transforms.iter_mut() {
+            transform.labels = vec![0; buf.read_u8()? as usize];
+        }
+        for transform in transforms.iter_mut() {
+            for label in transform.labels.iter_mut() {
+                *label = buf.read_u8()?;
+            }
+        }
+
+        Ok(FrameMap { id, transforms })
+    }
+}
+
+/// A transform applied by a frame, with the frame map transform it uses.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameTransform {
+    /// The index of the transform in the frame map
+    pub index: u8,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// A frame of an animation from the frames archive.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Frame {
+    pub frame_map: FrameMap,
+    pub transforms: Vec<FrameTransform>,
+    /// Whether the frame changes the transparency of any faces
+    pub alpha: bool,
+}
+
+impl Frame {
+    /// Read the id of the frame map a frame uses
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The contents of the frame's file
+    pub fn frame_map_id(mut buf: &[u8]) -> Result<u16, AnimError> {
+        Ok(buf.read_u16()?)
+    }
+
+    /// Decode a frame. As in the client, an origin transform is inserted
+    /// before a transform that isn't preceded by one.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The contents of the frame's file
+    /// * `frame_map` - The frame map named by [`Frame::frame_map_id`]
+    pub fn decode(buf: &[u8], frame_map: &FrameMap) -> Result<Frame, AnimError> {
+        let mut flags = buf;
+        let frame_map_id = flags.read_u16()?;
+        if frame_map_id != frame_map.id {
+            return Err(AnimError::FrameMapMismatch {
+                expected: frame_map.id,
+                actual: frame_map_id,
+            });
+        }
+        let len = flags.read_u8()? as usize;
+        if len > frame_map.transforms.len() {
+            return Err(AnimError::TooManyTransforms(len));
+        }
+        let mut values = flags.get(len..).unwrap_or_default();
+
+        let types: Vec<u8> = frame_map
+            .transforms
+            .iter()
+            .map(|transform| transform.transform_type)
+            .collect();
+        let mut transforms = Vec::new();
+        let mut alpha = false;
+        let mut last = None;
+        for i in 0..len {
+            let mask = flags.read_u8()?;
+            if mask == 0 {
+                continue;
+            }
+
+            if types[i] != TRANSFORM_ORIGIN {
+                let start = last.map_or(0, |last| last + 1);
+                if let Some(origin) = (start..i).rev().find(|j| types[*j] == TRANSFORM_ORIGIN) {
+                    transforms.push(FrameTransform {
+                        index: origin as u8,
+                        ..Default::default()
+                    });
+                }
+            }
+
+            let default = if types[i] == TRANSFORM_SCALE { 128 } else { 0 };
+            let mut read = |bit: u8| -> io::Result<i32> {
+                if mask & bit != 0 {
+                    Ok(values.read_i16_smart()? as i32)
+                } else {
+                    Ok(default)
+                }
+            };
+            transforms.push(FrameTransform {
+                index: i as u8,
+                x: read(1)?,
+                y: read(2)?,
+                z: read(4)?,
+            });
+
+            last = Some(i);
+            alpha |= types[i] == TRANSFORM_ALPHA;
+        }
+
+        Ok(Frame {
+            frame_map: frame_map.clone(),
+            transforms,
+            alpha,
+        })
+    }
+
+    /// Apply the frame to a model, moving the vertices and changing the
+    /// transparency of the faces grouped by the frame map's labels. Models
+    /// without vertex skins are left untouched, as in the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The model to transform
+    pub fn apply(&self, model: &mut Model) {
+        let Some(vertex_skins) = &model.vertex_skins else {
+            return;
+        };
+        let vertex_groups = groups(vertex_skins.iter().map(|skin| Some(*skin)));
+        let face_groups = groups(model.faces.iter().map(|face| face.skin));
+
+        let mut origin = [0; 3];
+        for transform in &self.transforms {
+            let Some(map) = self.frame_map.transforms.get(transform.index as usize) else {
+                continue;
+            };
+            let vertices = map
+                .labels
+                .iter()
+                .filter_map(|label| vertex_groups.get(*label as usize))
+                .flatten()
+                .copied();
+            let (x, y, z) = (transform.x, transform.y, transform.z);
+
+            match map.transform_type {
+                TRANSFORM_ORIGIN => {
+                    let mut sum = [0; 3];
+                    let mut count = 0;
+                    for vertex in vertices {
+                        for (sum, value) in sum.iter_mut().zip(model.vertices[vertex]) {
+                            *sum += value;
+                        }
+                        count += 1;
+                    }
+                    origin = if count > 0 {
+                        [x + sum[0] / count, y + sum[1] / count, z + sum[2] / count]
+                    } else {
+                        [x, y, z]
+                    };
+                }
+                TRANSFORM_TRANSLATE => {
+                    for vertex in vertices {
+                        let vertex = &mut model.vertices[vertex];
+                        vertex[0] += x;
+                        vertex[1] += y;
+                        vertex[2] += z;
+                    }
+                }
+                TRANSFORM_ROTATE => {
+                    let (pitch, yaw, roll) = ((x & 0xff) * 8, (y & 0xff) * 8, (z & 0xff) * 8);
+                    for vertex in vertices {
+                        let [mut vx, mut vy, mut vz] = model.vertices[vertex];
+                        vx -= origin[0];
+                        vy -= origin[1];
+                        vz -= origin[2];
+                        if roll != 0 {
+                            (vx, vy) = rotate(roll as usize, vx, vy);
+                        }
+                        if pitch != 0 {
+                            (vz, vy) = rotate(pitch as usize, vz, vy);
+                        }
+                        if yaw != 0 {
+                            (vx, vz) = rotate(yaw as usize, vx, vz);
+                        }
+                        model.vertices[vertex] = [vx + origin[0], vy + origin[1], vz + origin[2]];
+                    }
+                }
+                TRANSFORM_SCALE => {
+                    for vertex in vertices {
+                        let vertex = &mut model.vertices[vertex];
+                        for ((value, origin), scale) in vertex.iter_mut().zip(origin).zip([x, y, z])
+                        {
+                            *value = (*value - origin) * scale / 128 + origin;
+                        }
+                    }
+                }
+                TRANSFORM_ALPHA => {
+                    let faces = map
+                        .labels
+                        .iter()
+                        .filter_map(|label| face_groups.get(*label as usize))
+                        .flatten();
+                    for face in faces {
+                        let face = &mut model.faces[*face];
+                        face.alpha = (face.alpha as i32 + x * 8).clamp(0, 255) as u8;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A frame of a sequence and how long it is shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SequenceFrame {
+    /// The id of the frame, `(group << 16) | file` in the frames archive
+    pub id: u32,
+    /// The number of client ticks (20ms) the frame is shown for
+    pub duration: u16,
+}
+
+impl SequenceFrame {
+    /// Get the group of the frame in the frames archive
+    pub fn group(&self) -> u32 {
+        self.id >> 16
+    }
+
+    /// Get the file of the frame within its group
+    pub fn file(&self) -> u16 {
+        self.id as u16
+    }
+}
+
+/// A sound effect played by a sequence.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameSound {
+    /// The id of the sound effect in the synths archive
+    pub id: u16,
+    /// The number of times the sound is played
+    pub loops: u8,
+    pub location: u8,
+    /// Whether the sound keeps playing once out of range, only held from
+    /// [`Revision::SEQUENCE_SOUND_FIELDS`]
+    pub retain: u8,
+}
+
+impl FrameSound {
+    /// Read a sound, packed into 24 bits before
+    /// [`Revision::SEQUENCE_SOUND_FIELDS`]
+    fn read(buf: &mut &[u8], packed: bool) -> io::Result<FrameSound> {
+        if packed {
+            let sound = buf.read_u24()?;
+            Ok(FrameSound {
+                id: (sound >> 8) as u16,
+                loops: (sound >> 4 & 0x7) as u8,
+                location: (sound & 0xf) as u8,
+                retain: 0,
+            })
+        } else {
+            Ok(FrameSound {
+                id: buf.read_u16()?,
+                loops: buf.read_u8()?,
+                location: buf.read_u8()?,
+                retain: buf.read_u8()?,
+            })
+        }
+    }
+
+    fn write(&self, buf: &mut Vec<u8>, packed: bool) -> io::Result<()> {
+        if packed {
+            buf.write_u24((self.id as u32) << 8 | (self.loops as u32) << 4 | self.location as u32)
+        } else {
+            buf.write_u16(self.id)?;
+            buf.write_u8(self.loops)?;
+            buf.write_u8(self.location)?;
+            buf.write_u8(self.retain)
+        }
+    }
+
+    /// Check whether the sound fits in 24 bits
+    fn is_packable(&self) -> bool {
+        self.loops <= 0x7 && self.location <= 0xf && self.retain == 0
+    }
+}
+
+/// An animation sequence from the seq group of the config archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Sequence {
+    pub id: u32,
+    pub frames: Vec<SequenceFrame>,
+    /// The number of frames to go back when the sequence loops, or `None` to
+    /// start again from the first
+    pub frame_step: Option<u16>,
+    /// The frame map transforms this sequence animates when it is interleaved
+    /// with a movement sequence, which animates the rest
+    pub interleave_order: Option<Vec<u8>>,
+    pub stretches: bool,
+    pub forced_priority: u8,
+    /// The item shown in the left hand, offset by 512, with 0 hiding it
+    pub left_hand_item: Option<u16>,
+    /// The item shown in the right hand, offset by 512, with 0 hiding it
+    pub right_hand_item: Option<u16>,
+    pub max_loops: u8,
+    pub precedence_animating: Option<u8>,
+    pub priority: Option<u8>,
+    pub reply_mode: u8,
+    /// The frames of the chat head animation
+    pub chat_frames: Vec<u32>,
+    /// The sound played on each frame
+    pub sounds: Vec<FrameSound>,
+    /// The id of the skeletal (animaya) animation replacing the frames
+    pub skeletal_id: Option<i32>,
+    /// The sounds of the skeletal animation, as `(frame, sound)` pairs
+    pub skeletal_sounds: Vec<(u16, FrameSound)>,
+    /// The first and last frames of the skeletal animation
+    pub skeletal_range: Option<(u16, u16)>,
+    /// The skeletal groups the animation moves
+    pub skeletal_masks: Option<Vec<u8>>,
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Sequence {
+            id: 0,
+            frames: Vec::new(),
+            frame_step: None,
+            interleave_order: None,
+            stretches: false,
+            forced_priority: 5,
+            left_hand_item: None,
+            right_hand_item: None,
+            max_loops: 99,
+            precedence_animating: None,
+            priority: None,
+            reply_mode: 2,
+            chat_frames: Vec::new(),
+            sounds: Vec::new(),
+            skeletal_id: None,
+            skeletal_sounds: Vec::new(),
+            skeletal_range: None,
+            skeletal_masks: None,
+        }
+    }
+}
+
+impl Sequence {
+    /// Get the number of client ticks the sequence lasts
+    pub fn duration(&self) -> u32 {
+        self.frames.iter().map(|frame| frame.duration as u32).sum()
+    }
+}
+
+/// The frames of a sequence: a short count, then every duration, then the
+/// low and high halves of every frame id.
+struct SequenceFrames;
+
+impl Codec<Vec<SequenceFrame>> for SequenceFrames {
+    fn read(buf: &mut &[u8]) -> Result<Vec<SequenceFrame>, DefinitionError> {
+        let len = buf.read_u16()? as usize;
+        let mut frames = vec![SequenceFrame::default(); len];
+        for frame in frames.iter_mut() {
+            frame.duration = buf.read_u16()?;
+        }
+        for frame in frames.iter_mut() {
+            frame.id = buf.read_u16()? as u32;
+        }
+        for frame in frames.iter_mut() {
+            frame.id |= (buf.read_u16()? as u32) << 16;
+        }
+        Ok(frames)
+    }
+
+    fn write(value: &Vec<SequenceFrame>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        buf.write_u16(value.len() as u16)?;
+        for frame in value {
+            buf.write_u16(frame.duration)?;
+        }
+        for frame in value {
+            buf.write_u16(frame.id as u16)?;
+        }
+        for frame in value {
+            buf.write_u16((frame.id >> 16) as u16)?;
+        }
+        Ok(())
+    }
+}
+
+/// The chat head frames of a sequence: a byte count, then the low and high
+/// halves of every frame id.
+struct ChatFrames;
+
+impl Codec<Vec<u32>> for ChatFrames {
+    fn read(buf: &mut &[u8]) -> Result<Vec<u32>, DefinitionError> {
+        let len = buf.read_u8()? as usize;
+        let mut frames = vec![0; len];
+        for frame in frames.iter_mut() {
+            *frame = buf.read_u16()? as u32;
+        }
+        for frame in frames.iter_mut() {
+            *frame |= (buf.read_u16()? as u32) << 16;
+        }
+        Ok(frames)
+    }
+
+    fn write(value: &Vec<u32>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        buf.write_u8(value.len() as u8)?;
+        for frame in value {
+            buf.write_u16(*frame as u16)?;
+        }
+        for frame in value {
+            buf.write_u16((*frame >> 16) as u16)?;
+        }
+        Ok(())
+    }
+}
+
+/// The sounds of a sequence's frames: a byte count, then every sound, packed
+/// when `PACKED` is set.
+struct FrameSounds<const PACKED: bool>;
+
+impl<const PACKED: bool> Codec<Vec<FrameSound>> for FrameSounds<PACKED> {
+    fn read(buf: &mut &[u8]) -> Result<Vec<FrameSound>, DefinitionError> {
+        let len = buf.read_u8()?;
+        Ok((0..len)
+            .map(|_| FrameSound::read(buf, PACKED))
+            .collect::<io::Result<_>>()?)
+    }
+
+    fn write(value: &Vec<FrameSound>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        buf.write_u8(value.len() as u8)?;
+        for sound in value {
+            sound.write(buf, PACKED)?;
+        }
+        Ok(())
+    }
+
+    fn is_encodable(value: &Vec<FrameSound>) -> bool {
+        !PACKED || value.iter().all(FrameSound::is_packable)
+    }
+}
+
+/// The sounds of a skeletal animation: a short count, then the frame and
+/// sound of each, packed when `PACKED` is set.
+struct SkeletalSounds<const PACKED: bool>;
+
+impl<const PACKED: bool> Codec<Vec<(u16, FrameSound)>> for SkeletalSounds<PACKED> {
+    fn read(buf: &mut &[u8]) -> Result<Vec<(u16, FrameSound)>, DefinitionError> {
+        let len = buf.read_u16()?;
+        Ok((0..len)
+            .map(|_| Ok((buf.read_u16()?, FrameSound::read(buf, PACKED)?)))
+            .collect::<io::Result<_>>()?)
+    }
+
+    fn write(value: &Vec<(u16, FrameSound)>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        buf.write_u16(value.len() as u16)?;
+        for (frame, sound) in value {
+            buf.write_u16(*frame)?;
+            sound.write(buf, PACKED)?;
+        }
+        Ok(())
+    }
+
+    fn is_encodable(value: &Vec<(u16, FrameSound)>) -> bool {
+        !PACKED || value.iter().all(|(_, sound)| sound.is_packable())
+    }
+}
+
+opcodes!(Sequence {
+    1 => frames: SequenceFrames,
+    2 => frame_step: Option<U16>,
+    3 => interleave_order: Option<U8List>,
+    4 => stretches: Set<true>,
+    5 => forced_priority: U8,
+    6 => left_hand_item: Option<U16>,
+    7 => right_hand_item: Option<U16>,
+    8 => max_loops: U8,
+    9 => precedence_animating: Option<U8>,
+    10 => priority: Option<U8>,
+    11 => reply_mode: U8,
+    12 => chat_frames: ChatFrames,
+    13 @ ..Revision::SEQUENCE_SOUND_FIELDS => sounds: FrameSounds<true>,
+    13 @ Revision::SEQUENCE_SOUND_FIELDS.. => sounds: FrameSounds<false>,
+    14 => skeletal_id: Option<I32>,
+    15 @ ..Revision::SEQUENCE_SOUND_FIELDS => skeletal_sounds: SkeletalSounds<true>,
+    15 @ Revision::SEQUENCE_SOUND_FIELDS.. => skeletal_sounds: SkeletalSounds<false>,
+    16 => skeletal_range: Option<(U16, U16)>,
+    17 => skeletal_masks: Option<U8List>,
+});
+
+impl Definition for Sequence {
+    const GROUP: u32 = 12;
+
+    fn with_id(id: u32) -> Self {
+        Sequence {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+/// Rotate a point the way the client does, returning
+/// `((sin * b + cos * a) >> 16, (cos * b - sin * a) >> 16)`.
+fn rotate(angle: usize, a: i32, b: i32) -> (i32, i32) {
+    let (sin, cos) = (sine()[angle] as i64, cosine()[angle] as i64);
+    let (a, b) = (a as i64, b as i64);
+    (
+        ((sin * b + cos * a) >> 16) as i32,
+        ((cos * b - sin * a) >> 16) as i32,
+    )
+}
+
+/// Group indices by their label.
+fn groups(labels: impl Iterator<Item = Option<u8>>) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, label) in labels.enumerate() {
+        if let Some(label) = label {
+            let label = label as usize;
+            if label >= groups.len() {
+                groups.resize(label + 1, Vec::new());
+            }
+            groups[label].push(i);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        definitions::assert_round_trip_revision,
+        model::{Face, ModelFormat},
+        Cache,
+    };
+
+    /// The fixture packs its frame sounds, as caches before
+    /// [`Revision::SEQUENCE_SOUND_FIELDS`] do
+    const FIXTURE_REVISION: Revision = Revision(219);
+
+    fn model() -> Model {
+        Model {
+            format: ModelFormat::Old,
+            vertices: vec![[0, 0, 0], [100, 0, 0], [0, 100, 0]],
+            vertex_skins: Some(vec![0, 1, 1]),
+            faces: vec![Face {
+                vertices: [0, 1, 2],
+                alpha: 10,
+                skin: Some(2),
+                ..Default::default()
+            }],
+            priority: 0,
+            texture_triangles: Vec::new(),
+            texture_mappings: Vec::new(),
+            animaya_groups: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_eq!(
+            2,
+            assert_round_trip_revision::<Sequence>("tests/data/cache/cache-anim", FIXTURE_REVISION)
+        );
+
+        let mut cache = Cache::open("tests/data/cache/cache-anim").unwrap();
+        cache.set_revision(FIXTURE_REVISION);
+        let sequence = cache.sequence(1).unwrap();
+        assert_eq!(Some(vec![1, 2]), sequence.interleave_order);
+        assert_eq!(
+            (Some(1554), Some(0)),
+            (sequence.left_hand_item, sequence.right_hand_item)
+        );
+        assert_eq!(vec![0x10000], sequence.chat_frames);
+        assert_eq!(
+            vec![(
+                1,
+                FrameSound {
+                    id: 0x0123,
+                    loops: 4,
+                    location: 5,
+                    retain: 0
+                }
+            )],
+            sequence.skeletal_sounds
+        );
+    }
+
+    #[test]
+    fn test_frame_sound_fields() {
+        let sound = FrameSound {
+            id: 2400,
+            loops: 1,
+            location: 10,
+            retain: 1,
+        };
+        let sequence = Sequence {
+            sounds: vec![sound],
+            ..Default::default()
+        };
+
+        let buf = sequence.encode().unwrap();
+        assert_eq!(vec![13, 1, 0x09, 0x60, 1, 10, 1, 0], buf);
+        assert_eq!(sequence, Sequence::decode(0, &buf).unwrap());
+
+        // Packed sounds can't hold whether they are retained
+        assert!(matches!(
+            sequence.encode_revision(FIXTURE_REVISION),
+            Err(DefinitionError::Unencodable { opcode: 13, .. })
+        ));
+        let sequence = Sequence {
+            sounds: vec![FrameSound { retain: 0, ..sound }],
+            ..Default::default()
+        };
+        let buf = sequence.encode_revision(FIXTURE_REVISION).unwrap();
+        assert_eq!(vec![13, 1, 0x09, 0x60, 0x1a, 0], buf);
+        assert_eq!(
+            sequence,
+            Sequence::decode_revision(0, &buf, FIXTURE_REVISION).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sequence_frames() {
+        let mut cache = Cache::open("tests/data/cache/cache-anim").unwrap();
+        cache.set_revision(FIXTURE_REVISION);
+        let sequence = cache.sequence(0).unwrap();
+        assert_eq!(1, sequence.frames[1].group());
+        assert_eq!(8, sequence.duration());
+        assert_eq!(
+            (8, 3, 2),
+            (
+                sequence.forced_priority,
+                sequence.max_loops,
+                sequence.reply_mode
+            )
+        );
+        assert_eq!(
+            vec![FrameSound {
+                id: 2400,
+                loops: 1,
+                location: 2,
+                retain: 0
+            }],
+            sequence.sounds
+        );
+
+        let frames = cache.sequence_frames(&sequence).unwrap();
+        assert_eq!(cache.frame(0x10000).unwrap(), frames[1]);
+        assert_eq!(5, frames[0].frame_map.transforms.len());
+        assert!(frames[0].alpha && !frames[1].alpha);
+
+        // The origin transform is inserted before the translation and scale
+        let indices: Vec<u8> = frames[0].transforms.iter().map(|t| t.index).collect();
+        assert_eq!(vec![0, 1, 2, 4], indices);
+        assert_eq!(
+            FrameTransform {
+                index: 3,
+                x: 256,
+                y: 128,
+                z: 128
+            },
+            frames[1].transforms[1]
+        );
+
+        let mut translated = model();
+        frames[0].apply(&mut translated);
+        assert_eq!(
+            vec![[0, 0, 0], [-20, -110, 300], [79, -10, 300]],
+            translated.vertices
+        );
+        assert_eq!(26, translated.faces[0].alpha);
+
+        let mut scaled = model();
+        frames[1].apply(&mut scaled);
+        assert_eq!(vec![[0, 0, 0], [200, 0, 0], [0, 100, 0]], scaled.vertices);
+    }
+
+    #[test]
+    fn test_frame_map_mismatch() {
+        let frame_map = FrameMap {
+            id: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Frame::decode(&[0, 0, 0], &frame_map),
+            Err(AnimError::FrameMapMismatch {
+                expected: 1,
+                actual: 0
+            })
+        ));
+        assert!(matches!(
+            Frame::decode(&[0, 1, 1, 0], &frame_map),
+            Err(AnimError::TooManyTransforms(1))
+        ));
+    }
+}
@@ -12,6 +12,7 @@ pub mod cache_archive;
 
 // TODO: Move a lot of these to the CacheArchive error and then propagate back to ArchiveError as a "CacheArchiveError" as this is really bad right now
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum ArchiveError {
     #[error("Js5CompressionError: {0}")]
     Js5CompressionError(#[from] Js5CompressionError),
@@ -38,11 +39,18 @@ pub trait Archive {
     ) -> Result<Vec<u8>, ArchiveError>;
     fn read_named_group(
         &mut self,
-        group: u32,
+        group_name_hash: u32,
         file: u16,
         xtea_keys: Option<[u32; 4]>,
         store: &dyn Store,
     ) -> Result<Vec<u8>, ArchiveError>;
+    fn read_named(
+        &mut self,
+        group_name_hash: u32,
+        file_name_hash: u32,
+        xtea_keys: Option<[u32; 4]>,
+        store: &dyn Store,
+    ) -> Result<Vec<u8>, ArchiveError>;
     fn get_unpacked(
         &mut self,
         entry_id: u32,
@@ -55,6 +63,7 @@ pub trait Archive {
 }
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum UnpackedError {
     #[error("failed getting file {0} from unpacked cache")]
     FileNotFound(u32),
@@ -52,6 +52,27 @@ impl Archive for CacheArchive {
         Ok(unpacked.read(file as u32)?)
     }
 
+    fn read_named(
+        &mut self,
+        group_name_hash: u32,
+        file_name_hash: u32,
+        key: Option<[u32; 4]>,
+        store: &dyn Store,
+    ) -> Result<Vec<u8>, ArchiveError> {
+        let entry_id = self.index.get_named(group_name_hash)?;
+        let file = self
+            .index
+            .groups
+            .get(&entry_id)
+            .ok_or(ArchiveError::GroupNotFound(entry_id))?
+            .get_named(file_name_hash)?;
+        let unpacked = match self.unpacked_cache.get(&entry_id) {
+            Some(unpacked) => unpacked,
+            None => self.get_unpacked(entry_id, key, store)?,
+        };
+        Ok(unpacked.read(file)?)
+    }
+
     fn get_unpacked(
         &mut self,
         entry_id: u32,
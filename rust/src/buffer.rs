@@ -0,0 +1,164 @@
+use osrs_bytes::{ReadExt, WriteExt};
+use std::io::{self, Read, Write};
+
+/// The characters of the Windows-1252 code page between 0x80 and 0x9F. The
+/// undefined code points map to the replacement character.
+const CP1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{FFFD}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{FFFD}', '\u{017D}', '\u{FFFD}',
+    '\u{FFFD}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{FFFD}', '\u{017E}', '\u{0178}',
+];
+
+/// Decode a single Windows-1252 byte.
+pub(crate) fn cp1252_to_char(b: u8) -> char {
+    match b {
+        0x80..=0x9F => CP1252_HIGH[(b - 0x80) as usize],
+        _ => b as char,
+    }
+}
+
+/// Encode a single character as Windows-1252, using `?` for unmappable characters.
+pub(crate) fn char_to_cp1252(c: char) -> u8 {
+    match c as u32 {
+        0x00..=0x7F | 0xA0..=0xFF => c as u8,
+        _ => CP1252_HIGH
+            .iter()
+            .position(|high| *high == c && c != '\u{FFFD}')
+            .map_or(b'?', |i| 0x80 + i as u8),
+    }
+}
+
+/// Reads the encodings used throughout the cache that `osrs_bytes` lacks.
+pub(crate) trait CacheReadExt: Read {
+    /// Reads a null terminated Windows-1252 string
+    fn read_cp1252_string(&mut self) -> io::Result<String> {
+        let mut string = String::new();
+        loop {
+            match self.read_u8()? {
+                0 => return Ok(string),
+                b => string.push(cp1252_to_char(b)),
+            }
+        }
+    }
+
+    /// Reads an id that is a short if it fits in 15 bits, or an int with the
+    /// high bit set otherwise. A short of 32767 means -1.
+    fn read_big_smart(&mut self) -> io::Result<i32> {
+        let peek = self.read_u8()?;
+        if peek & 0x80 == 0 {
+            let value = ((peek as i32) << 8) | self.read_u8()? as i32;
+            Ok(if value == 32767 { -1 } else { value })
+        } else {
+            let rest = self.read_u24()? as i32;
+            Ok((((peek as i32) << 24) | rest) & 0x7FFFFFFF)
+        }
+    }
+
+    /// Reads a smart that is a byte if below 128 and a short otherwise, minus one
+    fn read_u16_smart_minus_one(&mut self) -> io::Result<i32> {
+        Ok(self.read_u16_smart()? as i32 - 1)
+    }
+
+    /// Reads a value as a sum of smarts, each smart of 32767 adding to the
+    /// value and continuing to the next
+    fn read_extended_smart(&mut self) -> io::Result<u32> {
+        let mut value = 0;
+        loop {
+            match self.read_u16_smart()? {
+                32767 => value += 32767,
+                smart => return Ok(value + smart as u32),
+            }
+        }
+    }
+}
+
+impl<R: Read + ?Sized> CacheReadExt for R {}
+
+/// Writes the encodings used throughout the cache that `osrs_bytes` lacks.
+pub(crate) trait CacheWriteExt: Write {
+    /// Writes a null terminated Windows-1252 string
+    fn write_cp1252_string(&mut self, string: &str) -> io::Result<()> {
+        for c in string.chars() {
+            self.write_u8(char_to_cp1252(c))?;
+        }
+        self.write_u8(0)
+    }
+
+    /// Writes the low 24 bits of a value
+    fn write_u24(&mut self, value: u32) -> io::Result<()> {
+        self.write_u8((value >> 16) as u8)?;
+        self.write_u16(value as u16)
+    }
+
+    /// Writes an id as a short if it fits in 15 bits, or an int with the high bit set otherwise
+    fn write_big_smart(&mut self, value: i32) -> io::Result<()> {
+        match value {
+            -1 => self.write_u16(32767),
+            0..=32766 => self.write_u16(value as u16),
+            32767.. => self.write_u32(value as u32 | 0x80000000),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("value out of range for a big smart: {value}"),
+            )),
+        }
+    }
+
+    /// Writes a smart that is a byte if below 128 and a short otherwise, plus one
+    fn write_u16_smart_minus_one(&mut self, value: i32) -> io::Result<()> {
+        let value = u16::try_from(value + 1).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("value out of range for a smart: {value}"),
+            )
+        })?;
+        self.write_u16_smart(value)
+    }
+
+    /// Writes a value as a sum of smarts, each smart of 32767 adding to the
+    /// value and continuing to the next
+    fn write_extended_smart(&mut self, mut value: u32) -> io::Result<()> {
+        while value >= 32767 {
+            self.write_u16_smart(32767)?;
+            value -= 32767;
+        }
+        self.write_u16_smart(value as u16)
+    }
+}
+
+impl<W: Write + ?Sized> CacheWriteExt for W {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cp1252_string_round_trip() {
+        let mut buf = Vec::new();
+        buf.write_cp1252_string("Caf\u{e9} \u{20AC}5").unwrap();
+
+        assert_eq!(b"Caf\xe9 \x805\0".to_vec(), buf);
+        assert_eq!(
+            "Caf\u{e9} \u{20AC}5",
+            buf.as_slice().read_cp1252_string().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_big_smart_round_trip() {
+        for value in [-1, 0, 32766, 32767, 100000] {
+            let mut buf = Vec::new();
+            buf.write_big_smart(value).unwrap();
+            assert_eq!(value, buf.as_slice().read_big_smart().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_extended_smart_round_trip() {
+        for value in [0, 127, 32766, 32767, 40000, 100000] {
+            let mut buf = Vec::new();
+            buf.write_extended_smart(value).unwrap();
+            assert_eq!(value, buf.as_slice().read_extended_smart().unwrap());
+        }
+    }
+}
@@ -1,18 +1,43 @@
 use crate::{
+    anim::{AnimError, Frame, FrameMap, Sequence, FRAMES_ARCHIVE, FRAME_MAPS_ARCHIVE},
     archive::{cache_archive::CacheArchive, Archive, ArchiveError},
+    clientscript::{Script, ScriptError, CLIENTSCRIPTS_ARCHIVE},
+    definitions::{
+        Definition, DefinitionError, EnumDefinition, HealthBarDefinition, HitsplatDefinition,
+        IdkDefinition, InvDefinition, ItemDefinition, NpcDefinition, ObjectDefinition,
+        OverlayDefinition, ParamDefinition, ParamValue, Params, SpotAnimDefinition,
+        StructDefinition, UnderlayDefinition, VarbitDefinition, VarpDefinition, CONFIG_ARCHIVE,
+    },
     djb2::djb2_hash,
+    font::{Font, FontError, FontMetrics, FONTS_ARCHIVE},
+    huffman::{Huffman, HuffmanError, BINARY_ARCHIVE, HUFFMAN_GROUP},
+    interface::{Component, Interface, InterfaceError, INTERFACES_ARCHIVE},
     js5_compression::{Js5Compression, Js5CompressionError},
     js5_index::{Js5Index, Js5IndexError},
+    key_store::KeyStore,
+    map::{self, MapError, MapSquare, Terrain, MAPS_ARCHIVE},
+    model::{Model, ModelError, MODELS_ARCHIVE},
+    music::{MusicError, Track},
+    name_dictionary::{NameCoverage, NameDictionary},
+    revision::Revision,
+    sound::{SoundEffect, SoundError, SOUND_EFFECTS_ARCHIVE},
+    sprite::{SpriteError, SpriteSheet, SPRITES_ARCHIVE},
     store::{store_open, Store, StoreError},
+    texture::{Texture, TextureError, TEXTURES_ARCHIVE, TEXTURES_GROUP},
+    vars::VarpState,
     Cache,
 };
-use std::{collections::HashMap, io};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io,
+};
 use thiserror::Error;
 
 const ARCHIVESET: usize = (1 << 24) - 1;
 const UNPACKED_CACHE_SIZE_DEFAULT: usize = 1024;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum CacheError {
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
@@ -28,6 +53,32 @@ pub enum CacheError {
     ArchiveNotFound(u8),
     #[error("failed reading CacheArchive {0} from cache")]
     ArchiveRead(u8),
+    #[error("id {0} doesn't fit in a file id")]
+    FileIdOutOfRange(u32),
+    #[error("definition error: {0}")]
+    Definition(#[from] DefinitionError),
+    #[error("map error: {0}")]
+    Map(#[from] MapError),
+    #[error("sprite error: {0}")]
+    Sprite(#[from] SpriteError),
+    #[error("model error: {0}")]
+    Model(#[from] ModelError),
+    #[error("huffman error: {0}")]
+    Huffman(#[from] HuffmanError),
+    #[error("font error: {0}")]
+    Font(#[from] FontError),
+    #[error("interface error: {0}")]
+    Interface(#[from] InterfaceError),
+    #[error("script error: {0}")]
+    Script(#[from] ScriptError),
+    #[error("anim error: {0}")]
+    Anim(#[from] AnimError),
+    #[error("texture error: {0}")]
+    Texture(#[from] TextureError),
+    #[error("music error: {0}")]
+    Music(#[from] MusicError),
+    #[error("sound error: {0}")]
+    Sound(#[from] SoundError),
 }
 
 impl Cache {
@@ -49,6 +100,8 @@ impl Cache {
         let mut cache = Self {
             store,
             archives: HashMap::new(),
+            keys: KeyStore::new(),
+            revision: Revision::LATEST,
             _unpacked_cache_size: UNPACKED_CACHE_SIZE_DEFAULT,
         };
         cache.init()?;
@@ -75,9 +128,69 @@ impl Cache {
             self.archives.insert(archive as u8, cache_archive);
         }
 
+        if let Some(config) = self.archives.get(&CONFIG_ARCHIVE) {
+            self.revision = Revision::detect(&config.index);
+        }
+
         Ok(())
     }
 
+    /// Get the XTEA keys used when reading map squares without explicit keys
+    pub fn keys(&self) -> &KeyStore {
+        &self.keys
+    }
+
+    /// Set the XTEA keys used when reading map squares without explicit keys
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The key store to use
+    pub fn set_keys(&mut self, keys: KeyStore) {
+        self.keys = keys;
+    }
+
+    /// Get the revision used when decoding configs and maps, detected when
+    /// the cache is opened
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    /// Set the revision used when decoding configs and maps
+    ///
+    /// # Arguments
+    ///
+    /// * `revision` - The revision of the cache
+    pub fn set_revision(&mut self, revision: Revision) {
+        self.revision = revision;
+    }
+
+    /// Get the index of an archive
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The archive to get the index of
+    pub fn index(&self, archive: u8) -> Result<&Js5Index, CacheError> {
+        Ok(&self
+            .archives
+            .get(&archive)
+            .ok_or(CacheError::ArchiveNotFound(archive))?
+            .index)
+    }
+
+    /// Count how many groups and files in an archive can be named by a dictionary
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The archive to count
+    /// * `dictionary` - The dictionary to look names up in
+    pub fn name_coverage(
+        &self,
+        archive: u8,
+        dictionary: &NameDictionary,
+    ) -> Result<NameCoverage, CacheError> {
+        Ok(dictionary.coverage(self.index(archive)?))
+    }
+
     /// Read a file from the cache
     ///
     /// # Arguments
@@ -85,7 +198,8 @@ impl Cache {
     /// * `archive` - The archive to read from
     /// * `group` - The group to read from
     /// * `file` - The file to read
-    /// * `xtea_keys` - The XTEA keys to use for decryption. If None, the file will not be decrypted
+    /// * `xtea_keys` - The XTEA keys to use for decryption. If None, the key store is consulted for
+    ///   map squares and otherwise the file will not be decrypted
     pub fn read(
         &mut self,
         archive: u8,
@@ -93,6 +207,8 @@ impl Cache {
         file: u16,
         xtea_keys: Option<[u32; 4]>,
     ) -> Result<Vec<u8>, CacheError> {
+        let xtea_keys = xtea_keys.or_else(|| self.stored_key(archive, group));
+
         Ok(self
             .archives
             .get_mut(&archive)
@@ -107,7 +223,8 @@ impl Cache {
     /// * `archive` - The archive to read from
     /// * `group` - The group to read from
     /// * `file` - The file to read
-    /// * `xtea_keys` - The XTEA keys to use for decryption. If None, the file will not be decrypted
+    /// * `xtea_keys` - The XTEA keys to use for decryption. If None, the key store is consulted for
+    ///   map squares and otherwise the file will not be decrypted
     pub fn read_named_group(
         &mut self,
         archive: u8,
@@ -115,10 +232,511 @@ impl Cache {
         file: u16,
         xtea_keys: Option<[u32; 4]>,
     ) -> Result<Vec<u8>, CacheError> {
+        let xtea_keys = xtea_keys.or_else(|| self.stored_named_key(archive, group));
+
         Ok(self
             .archives
             .get_mut(&archive)
             .ok_or(CacheError::ArchiveNotFound(archive))?
             .read_named_group(djb2_hash(group), file, xtea_keys, self.store.as_ref())?)
     }
+
+    /// Read a named file from a named group in the cache
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The archive to read from
+    /// * `group` - The name of the group to read from
+    /// * `file` - The name of the file to read
+    /// * `xtea_keys` - The XTEA keys to use for decryption. If None, the key store is consulted for
+    ///   map squares and otherwise the file will not be decrypted
+    pub fn read_named(
+        &mut self,
+        archive: u8,
+        group: &str,
+        file: &str,
+        xtea_keys: Option<[u32; 4]>,
+    ) -> Result<Vec<u8>, CacheError> {
+        let xtea_keys = xtea_keys.or_else(|| self.stored_named_key(archive, group));
+
+        Ok(self
+            .archives
+            .get_mut(&archive)
+            .ok_or(CacheError::ArchiveNotFound(archive))?
+            .read_named(
+                djb2_hash(group),
+                djb2_hash(file),
+                xtea_keys,
+                self.store.as_ref(),
+            )?)
+    }
+
+    /// Read and decode a definition from the config archive with the opcodes of
+    /// the cache's revision
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the definition
+    pub fn definition<T: Definition>(&mut self, id: u32) -> Result<T, CacheError> {
+        let file = u16::try_from(id).map_err(|_| CacheError::FileIdOutOfRange(id))?;
+        let buf = self.read(CONFIG_ARCHIVE, T::GROUP, file, None)?;
+        Ok(T::decode_revision(id, &buf, self.revision)?)
+    }
+
+    /// Read and decode every definition of a config type, in id order
+    pub fn definitions<T: Definition>(&mut self) -> Result<Vec<T>, CacheError> {
+        let ids: Vec<u32> = self
+            .index(CONFIG_ARCHIVE)?
+            .groups
+            .get(&T::GROUP)
+            .ok_or(ArchiveError::GroupNotFound(T::GROUP))?
+            .files
+            .keys()
+            .copied()
+            .collect();
+
+        ids.into_iter().map(|id| self.definition(id)).collect()
+    }
+
+    /// Read and decode an item definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the item
+    pub fn item(&mut self, id: u32) -> Result<ItemDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode an npc definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the npc
+    pub fn npc(&mut self, id: u32) -> Result<NpcDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode an object (loc) definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the object
+    pub fn object(&mut self, id: u32) -> Result<ObjectDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode an enum definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the enum
+    pub fn enum_definition(&mut self, id: u32) -> Result<EnumDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Look up a key in an enum, falling back to the enum's default
+    ///
+    /// # Arguments
+    ///
+    /// * `enum_id` - The id of the enum
+    /// * `key` - The key to look up
+    pub fn enum_value(&mut self, enum_id: u32, key: i32) -> Result<ParamValue, CacheError> {
+        Ok(self.enum_definition(enum_id)?.value(key))
+    }
+
+    /// Read and decode a struct definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the struct
+    pub fn struct_definition(&mut self, id: u32) -> Result<StructDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode a param definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the param
+    pub fn param(&mut self, id: u32) -> Result<ParamDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Get the value of a param of an item, npc, loc or struct, falling back
+    /// to the param's default
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The params of the config
+    /// * `param_id` - The id of the param
+    pub fn param_value(
+        &mut self,
+        params: &Params,
+        param_id: u32,
+    ) -> Result<ParamValue, CacheError> {
+        match params.get(param_id) {
+            Some(value) => Ok(value.clone()),
+            None => Ok(self.param(param_id)?.default_value()),
+        }
+    }
+
+    /// Read and decode a varbit definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the varbit
+    pub fn varbit(&mut self, id: u32) -> Result<VarbitDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode a varp definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the varp
+    pub fn varp(&mut self, id: u32) -> Result<VarpDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Create a varp state with room for every varp in the cache
+    pub fn varp_state(&mut self) -> Result<VarpState, CacheError> {
+        let len = self
+            .index(CONFIG_ARCHIVE)?
+            .groups
+            .get(&VarpDefinition::GROUP)
+            .ok_or(ArchiveError::GroupNotFound(VarpDefinition::GROUP))?
+            .files
+            .keys()
+            .next_back()
+            .map_or(0, |id| *id as usize + 1);
+        Ok(VarpState::new(len))
+    }
+
+    /// Read and decode an inventory definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the inventory
+    pub fn inv(&mut self, id: u32) -> Result<InvDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode an identity kit definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the identity kit
+    pub fn idk(&mut self, id: u32) -> Result<IdkDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode a spot animation definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the spot animation
+    pub fn spotanim(&mut self, id: u32) -> Result<SpotAnimDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode a hitsplat definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the hitsplat
+    pub fn hitsplat(&mut self, id: u32) -> Result<HitsplatDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode a health bar definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the health bar
+    pub fn health_bar(&mut self, id: u32) -> Result<HealthBarDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode an underlay definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the underlay
+    pub fn underlay(&mut self, id: u32) -> Result<UnderlayDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode an overlay definition
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the overlay
+    pub fn overlay(&mut self, id: u32) -> Result<OverlayDefinition, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode the terrain and locations of a map square. A map
+    /// square without a locations group has no locations.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x coordinate of the map square
+    /// * `y` - The y coordinate of the map square
+    /// * `xtea_keys` - The XTEA keys of the locations. If None, the key store is consulted
+    pub fn map_square(
+        &mut self,
+        x: u8,
+        y: u8,
+        xtea_keys: Option<[u32; 4]>,
+    ) -> Result<MapSquare, CacheError> {
+        let buf = self.read_named_group(MAPS_ARCHIVE, &map::terrain_name(x, y), 0, None)?;
+        let terrain = Terrain::decode_revision(&buf, self.revision)?;
+
+        let locations_name = map::locations_name(x, y);
+        let locations = if self
+            .index(MAPS_ARCHIVE)?
+            .get_named(djb2_hash(&locations_name))
+            .is_ok()
+        {
+            let buf = self.read_named_group(MAPS_ARCHIVE, &locations_name, 0, xtea_keys)?;
+            map::decode_locations(&buf)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(MapSquare {
+            x,
+            y,
+            terrain,
+            locations,
+        })
+    }
+
+    /// Read and decode a sprite sheet
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The group of the sprites archive
+    pub fn sprite(&mut self, group: u32) -> Result<SpriteSheet, CacheError> {
+        let buf = self.read(SPRITES_ARCHIVE, group, 0, None)?;
+        Ok(SpriteSheet::decode(&buf)?)
+    }
+
+    /// Read and decode a texture
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the texture, which is its file in the textures group
+    pub fn texture(&mut self, id: u16) -> Result<Texture, CacheError> {
+        let buf = self.read(TEXTURES_ARCHIVE, TEXTURES_GROUP, id, None)?;
+        Ok(Texture::decode(id, &buf)?)
+    }
+
+    /// Read and decode a song or jingle
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The archive holding the track, such as [`SONGS_ARCHIVE`](crate::music::SONGS_ARCHIVE)
+    /// * `group` - The group of the track within the archive
+    pub fn track(&mut self, archive: u8, group: u32) -> Result<Track, CacheError> {
+        let buf = self.read(archive, group, 0, None)?;
+        Ok(Track::decode(&buf)?)
+    }
+
+    /// Read and decode a sound effect
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the sound effect, which is its group in the sound effects archive
+    pub fn sound_effect(&mut self, id: u32) -> Result<SoundEffect, CacheError> {
+        let buf = self.read(SOUND_EFFECTS_ARCHIVE, id, 0, None)?;
+        Ok(SoundEffect::decode(&buf)?)
+    }
+
+    /// Read and decode a model
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the model, which is its group in the models archive
+    pub fn model(&mut self, id: u32) -> Result<Model, CacheError> {
+        let buf = self.read(MODELS_ARCHIVE, id, 0, None)?;
+        Ok(Model::decode(&buf)?)
+    }
+
+    /// Read and decode an animation sequence
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the sequence
+    pub fn sequence(&mut self, id: u32) -> Result<Sequence, CacheError> {
+        self.definition(id)
+    }
+
+    /// Read and decode a frame map
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the frame map, which is its group in the frame maps archive
+    pub fn frame_map(&mut self, id: u16) -> Result<FrameMap, CacheError> {
+        let buf = self.read(FRAME_MAPS_ARCHIVE, id as u32, 0, None)?;
+        Ok(FrameMap::decode(id, &buf)?)
+    }
+
+    /// Read and decode an animation frame along with its frame map
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the frame, `(group << 16) | file` in the frames archive
+    pub fn frame(&mut self, id: u32) -> Result<Frame, CacheError> {
+        let buf = self.read(FRAMES_ARCHIVE, id >> 16, id as u16, None)?;
+        let frame_map = self.frame_map(Frame::frame_map_id(&buf)?)?;
+        Ok(Frame::decode(&buf, &frame_map)?)
+    }
+
+    /// Read and decode every frame of a sequence, in order. Frame maps shared
+    /// by several frames are only read once.
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - The sequence to read the frames of
+    pub fn sequence_frames(&mut self, sequence: &Sequence) -> Result<Vec<Frame>, CacheError> {
+        let mut frame_maps = HashMap::new();
+        let mut frames = Vec::with_capacity(sequence.frames.len());
+        for frame in &sequence.frames {
+            let buf = self.read(FRAMES_ARCHIVE, frame.group(), frame.file(), None)?;
+            let frame_map_id = Frame::frame_map_id(&buf)?;
+            let frame_map = match frame_maps.entry(frame_map_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(self.frame_map(frame_map_id)?),
+            };
+            frames.push(Frame::decode(&buf, frame_map)?);
+        }
+        Ok(frames)
+    }
+
+    /// Read and decode every component of an interface
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the interface, which is its group in the interfaces archive
+    pub fn interface(&mut self, id: u16) -> Result<Interface, CacheError> {
+        let files: Vec<u32> = self
+            .index(INTERFACES_ARCHIVE)?
+            .groups
+            .get(&(id as u32))
+            .ok_or(ArchiveError::GroupNotFound(id as u32))?
+            .files
+            .keys()
+            .copied()
+            .collect();
+
+        let mut interface = Interface {
+            id,
+            ..Default::default()
+        };
+        for file in files {
+            let buf = self.read(INTERFACES_ARCHIVE, id as u32, file as u16, None)?;
+            let component = Component::decode(((id as u32) << 16) | file, &buf)?;
+            interface.components.insert(file as u16, component);
+        }
+        Ok(interface)
+    }
+
+    /// Read and decode a clientscript
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the script, which is its group in the clientscripts archive
+    pub fn script(&mut self, id: u32) -> Result<Script, CacheError> {
+        let buf = self.read(CLIENTSCRIPTS_ARCHIVE, id, 0, None)?;
+        Ok(Script::decode(&buf)?)
+    }
+
+    /// Read and decode a font
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the font, such as [`crate::font::FONT_PLAIN_12`],
+    ///   which names both its sprites group and its metrics group
+    pub fn font(&mut self, name: &str) -> Result<Font, CacheError> {
+        let glyphs =
+            SpriteSheet::decode(&self.read_named_group(SPRITES_ARCHIVE, name, 0, None)?)?;
+        let metrics = FontMetrics::decode(&self.read_named_group(FONTS_ARCHIVE, name, 0, None)?)?;
+        Ok(Font::new(glyphs, metrics)?)
+    }
+
+    /// Read the Huffman table and build the chat message code
+    pub fn huffman(&mut self) -> Result<Huffman, CacheError> {
+        let buf = self.read_named_group(BINARY_ARCHIVE, HUFFMAN_GROUP, 0, None)?;
+        Ok(Huffman::new(&buf)?)
+    }
+
+    fn stored_key(&self, archive: u8, group: u32) -> Option<[u32; 4]> {
+        if archive != MAPS_ARCHIVE {
+            return None;
+        }
+
+        let entry = self.archives.get(&archive)?.index.groups.get(&group)?;
+        self.keys.get_by_name_hash(entry.name_hash as u32)
+    }
+
+    fn stored_named_key(&self, archive: u8, group: &str) -> Option<[u32; 4]> {
+        if archive != MAPS_ARCHIVE {
+            return None;
+        }
+
+        self.keys.get_named(group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_named_group_with_stored_key() {
+        let mut cache = open_with_keys("tests/data/cache/cache-read-map-keys");
+
+        assert_eq!(
+            "OpenRS2".as_bytes(),
+            cache.read_named_group(5, "l40_55", 0, None).unwrap()
+        );
+        assert_eq!(
+            "OpenRS2".as_bytes(),
+            cache.read_named_group(5, "m40_55", 0, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_read_named_with_stored_key() {
+        let mut cache = open_with_keys("tests/data/cache/cache-read-named-map-keys");
+
+        assert_eq!(
+            "OpenRS2".as_bytes(),
+            cache.read_named(5, "l40_55", "world", None).unwrap()
+        );
+        assert_eq!(
+            "Hello".as_bytes(),
+            cache.read_named(5, "l40_55", "hello", Some(KEY)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_read_with_stored_key() {
+        let mut cache = open_with_keys("tests/data/cache/cache-read-map-keys");
+
+        assert_eq!("OpenRS2".as_bytes(), cache.read(5, 1, 0, None).unwrap());
+    }
+
+    fn open_with_keys(path: &str) -> Cache {
+        let mut cache = Cache::open(path).unwrap();
+
+        let mut keys = KeyStore::new();
+        keys.insert(10295, KEY);
+        cache.set_keys(keys);
+
+        cache
+    }
+
+    const KEY: [u32; 4] = [0x00112233, 0x44556677, 0x8899AABB, 0xCCDDEEFF];
 }
@@ -31,7 +31,7 @@ impl ChecksumTable {
         Ok(())
     }
 
-    pub fn create(store: &Box<dyn Store>) -> Result<ChecksumTable, ChecksumTableError> {
+    pub fn create(store: &dyn Store) -> Result<ChecksumTable, ChecksumTableError> {
         let mut entries = Vec::new();
         let mut next_archive = 0;
 
@@ -0,0 +1,477 @@
+use crate::buffer::{CacheReadExt, CacheWriteExt};
+use osrs_bytes::{ReadExt, WriteExt};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+use thiserror::Error;
+
+/// The archive holding clientscripts, one group per script.
+pub const CLIENTSCRIPTS_ARCHIVE: u8 = 12;
+
+pub const ICONST: u16 = 0;
+pub const SCONST: u16 = 3;
+pub const JUMP: u16 = 6;
+pub const IF_ICMPNE: u16 = 7;
+pub const IF_ICMPEQ: u16 = 8;
+pub const IF_ICMPLT: u16 = 9;
+pub const IF_ICMPGT: u16 = 10;
+pub const RETURN: u16 = 21;
+pub const IF_ICMPLE: u16 = 31;
+pub const IF_ICMPGE: u16 = 32;
+pub const POP_INT: u16 = 38;
+pub const POP_STRING: u16 = 39;
+pub const SWITCH: u16 = 60;
+
+/// The names of the core opcodes, whose numbers have not changed since
+/// clientscripts were introduced.
+const CORE_OPCODES: [(u16, &str); 32] = [
+    (ICONST, "iconst"),
+    (1, "get_varp"),
+    (2, "set_varp"),
+    (SCONST, "sconst"),
+    (JUMP, "jump"),
+    (IF_ICMPNE, "if_icmpne"),
+    (IF_ICMPEQ, "if_icmpeq"),
+    (IF_ICMPLT, "if_icmplt"),
+    (IF_ICMPGT, "if_icmpgt"),
+    (RETURN, "return"),
+    (25, "get_varbit"),
+    (27, "set_varbit"),
+    (IF_ICMPLE, "if_icmple"),
+    (IF_ICMPGE, "if_icmpge"),
+    (33, "iload"),
+    (34, "istore"),
+    (35, "sload"),
+    (36, "sstore"),
+    (37, "join_string"),
+    (POP_INT, "pop_int"),
+    (POP_STRING, "pop_string"),
+    (40, "invoke"),
+    (42, "get_varc_int"),
+    (43, "set_varc_int"),
+    (44, "define_array"),
+    (45, "get_array_int"),
+    (46, "set_array_int"),
+    (47, "get_varc_string_old"),
+    (48, "set_varc_string_old"),
+    (49, "get_varc_string"),
+    (50, "set_varc_string"),
+    (SWITCH, "switch"),
+];
+
+/// The length of the trailer before the switch tables: the instruction
+/// count and the four local and argument counts.
+const TRAILER_LEN: usize = 12;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ScriptError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("script trailer out of bounds")]
+    TrailerOutOfBounds,
+    #[error("operand of opcode {0} has the wrong type")]
+    InvalidOperand(u16),
+    #[error("too many switch tables: {0}")]
+    TooManySwitches(usize),
+    #[error("invalid opcode mapping on line {0}")]
+    InvalidMapping(usize),
+}
+
+/// The operand of an instruction. Opcodes below 100 take an int, except
+/// `sconst` which takes a string and `return`, `pop_int` and `pop_string`
+/// which take a byte like every other opcode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operand {
+    Int(i32),
+    Byte(u8),
+    String(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: u16,
+    pub operand: Operand,
+}
+
+impl Instruction {
+    /// Get whether the instruction's operand is an offset to jump by
+    pub fn is_branch(&self) -> bool {
+        matches!(
+            self.opcode,
+            JUMP | IF_ICMPNE | IF_ICMPEQ | IF_ICMPLT | IF_ICMPGT | IF_ICMPLE | IF_ICMPGE
+        )
+    }
+}
+
+/// A compiled clientscript (cs2).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Script {
+    pub name: Option<String>,
+    pub instructions: Vec<Instruction>,
+    pub local_int_count: u16,
+    pub local_string_count: u16,
+    pub int_arg_count: u16,
+    pub string_arg_count: u16,
+    /// The switch tables, as `(value, offset)` pairs. Offsets are relative
+    /// to the instruction after the `switch`.
+    pub switches: Vec<Vec<(i32, i32)>>,
+}
+
+impl Script {
+    /// Decode a script
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The contents of file 0 of a clientscripts group
+    pub fn decode(buf: &[u8]) -> Result<Script, ScriptError> {
+        let trailer = |len: usize| {
+            buf.len()
+                .checked_sub(len)
+                .map(|start| &buf[start..])
+                .ok_or(ScriptError::TrailerOutOfBounds)
+        };
+
+        let switches_len = trailer(2)?.read_u16()? as usize;
+        let code_len = buf
+            .len()
+            .checked_sub(2 + switches_len + TRAILER_LEN)
+            .ok_or(ScriptError::TrailerOutOfBounds)?;
+
+        let mut header = &buf[code_len..];
+        let instruction_count = header.read_u32()? as usize;
+        let local_int_count = header.read_u16()?;
+        let local_string_count = header.read_u16()?;
+        let int_arg_count = header.read_u16()?;
+        let string_arg_count = header.read_u16()?;
+
+        let switch_count = header.read_u8()?;
+        let mut switches = Vec::with_capacity(switch_count as usize);
+        for _ in 0..switch_count {
+            let len = header.read_u16()?;
+            let mut cases = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                cases.push((header.read_i32()?, header.read_i32()?));
+            }
+            switches.push(cases);
+        }
+
+        let mut code = &buf[..code_len];
+        let name = match code.first() {
+            Some(0) | None => {
+                code = code.get(1..).unwrap_or_default();
+                None
+            }
+            Some(_) => Some(code.read_cp1252_string()?),
+        };
+
+        let mut instructions = Vec::with_capacity(instruction_count.min(code.len() / 2));
+        while !code.is_empty() {
+            let opcode = code.read_u16()?;
+            let operand = match opcode {
+                SCONST => Operand::String(code.read_cp1252_string()?),
+                RETURN | POP_INT | POP_STRING => Operand::Byte(code.read_u8()?),
+                0..=99 => Operand::Int(code.read_i32()?),
+                _ => Operand::Byte(code.read_u8()?),
+            };
+            instructions.push(Instruction { opcode, operand });
+        }
+
+        Ok(Script {
+            name,
+            instructions,
+            local_int_count,
+            local_string_count,
+            int_arg_count,
+            string_arg_count,
+            switches,
+        })
+    }
+
+    /// Encode the script
+    pub fn encode(&self) -> Result<Vec<u8>, ScriptError> {
+        let mut buf = Vec::new();
+        match &self.name {
+            Some(name) => buf.write_cp1252_string(name)?,
+            None => buf.write_u8(0)?,
+        }
+
+        for instruction in &self.instructions {
+            buf.write_u16(instruction.opcode)?;
+            match (instruction.opcode, &instruction.operand) {
+                (SCONST, Operand::String(value)) => buf.write_cp1252_string(value)?,
+                (RETURN | POP_INT | POP_STRING, Operand::Byte(value)) => buf.write_u8(*value)?,
+                (SCONST | RETURN | POP_INT | POP_STRING, _) => {
+                    return Err(ScriptError::InvalidOperand(instruction.opcode))
+                }
+                (0..=99, Operand::Int(value)) => buf.write_i32(*value)?,
+                (100.., Operand::Byte(value)) => buf.write_u8(*value)?,
+                _ => return Err(ScriptError::InvalidOperand(instruction.opcode)),
+            }
+        }
+
+        buf.write_u32(self.instructions.len() as u32)?;
+        buf.write_u16(self.local_int_count)?;
+        buf.write_u16(self.local_string_count)?;
+        buf.write_u16(self.int_arg_count)?;
+        buf.write_u16(self.string_arg_count)?;
+
+        let switches_start = buf.len();
+        let switch_count = u8::try_from(self.switches.len())
+            .map_err(|_| ScriptError::TooManySwitches(self.switches.len()))?;
+        buf.write_u8(switch_count)?;
+        for cases in &self.switches {
+            buf.write_u16(cases.len() as u16)?;
+            for (value, offset) in cases {
+                buf.write_i32(*value)?;
+                buf.write_i32(*offset)?;
+            }
+        }
+        buf.write_u16((buf.len() - switches_start) as u16)?;
+
+        Ok(buf)
+    }
+
+    /// Disassemble the script into one instruction per line. Jump and switch
+    /// targets are replaced with labels and opcodes without a name are shown
+    /// as `op_{opcode}`.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The names of the opcodes
+    pub fn disassemble(&self, names: &OpcodeNames) -> String {
+        let target = |pc: usize, offset: i32| pc as i64 + offset as i64 + 1;
+
+        let mut labels = BTreeSet::new();
+        for (pc, instruction) in self.instructions.iter().enumerate() {
+            match (&instruction.operand, instruction.opcode) {
+                (Operand::Int(offset), _) if instruction.is_branch() => {
+                    labels.insert(target(pc, *offset));
+                }
+                (Operand::Int(table), SWITCH) => {
+                    let cases = self.switches.get(*table as usize).into_iter().flatten();
+                    labels.extend(cases.map(|(_, offset)| target(pc, *offset)));
+                }
+                _ => {}
+            }
+        }
+
+        let mut out = String::new();
+        if let Some(name) = &self.name {
+            writeln!(out, "; {name}").unwrap();
+        }
+        writeln!(
+            out,
+            "; int args {}, string args {}, int locals {}, string locals {}",
+            self.int_arg_count,
+            self.string_arg_count,
+            self.local_int_count,
+            self.local_string_count
+        )
+        .unwrap();
+
+        for (pc, instruction) in self.instructions.iter().enumerate() {
+            if labels.contains(&(pc as i64)) {
+                writeln!(out, "label_{pc}:").unwrap();
+            }
+
+            let name = match names.get(instruction.opcode) {
+                Some(name) => name.to_string(),
+                None => format!("op_{}", instruction.opcode),
+            };
+            let operand = match &instruction.operand {
+                Operand::Int(offset) if instruction.is_branch() => {
+                    format!("label_{}", target(pc, *offset))
+                }
+                Operand::Int(value) => value.to_string(),
+                Operand::Byte(value) => value.to_string(),
+                Operand::String(value) => format!("{value:?}"),
+            };
+            writeln!(out, "    {name} {operand}").unwrap();
+
+            if let (SWITCH, Operand::Int(table)) = (instruction.opcode, &instruction.operand) {
+                for (value, offset) in self.switches.get(*table as usize).into_iter().flatten() {
+                    writeln!(out, "        case {value}: label_{}", target(pc, *offset)).unwrap();
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Names for clientscript opcodes, which are reassigned between revisions.
+#[derive(Clone, Debug, Default)]
+pub struct OpcodeNames {
+    names: HashMap<u16, String>,
+}
+
+impl OpcodeNames {
+    /// Create an empty set of names
+    pub fn new() -> OpcodeNames {
+        Self::default()
+    }
+
+    /// Load names from a mapping file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the mapping file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<OpcodeNames, ScriptError> {
+        let mut names = Self::new();
+        names.read(BufReader::new(File::open(path)?))?;
+        Ok(names)
+    }
+
+    /// Add the names from a reader with an opcode and its name on each line,
+    /// separated by whitespace. Blank lines and lines starting with `#` are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader to read the mapping from
+    pub fn read<R: BufRead>(&mut self, reader: R) -> Result<(), ScriptError> {
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            match (parts.next().map(str::parse), parts.next(), parts.next()) {
+                (Some(Ok(opcode)), Some(name), None) => self.insert(opcode, name),
+                _ => return Err(ScriptError::InvalidMapping(i + 1)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the names of the core opcodes, such as `iconst` and `switch`,
+    /// without replacing names already present
+    pub fn with_core_opcodes(mut self) -> Self {
+        for (opcode, name) in CORE_OPCODES {
+            self.names.entry(opcode).or_insert_with(|| name.to_string());
+        }
+        self
+    }
+
+    /// Add or replace the name of an opcode
+    pub fn insert<T: Into<String>>(&mut self, opcode: u16, name: T) {
+        self.names.insert(opcode, name.into());
+    }
+
+    /// Get the name of an opcode
+    pub fn get(&self, opcode: u16) -> Option<&str> {
+        self.names.get(&opcode).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    #[test]
+    fn test_decode() {
+        let mut cache = Cache::open("tests/data/cache/cache-scripts").unwrap();
+        let script = cache.script(0).unwrap();
+
+        assert_eq!(None, script.name);
+        assert_eq!(
+            (1, 1, 1, 0),
+            (
+                script.int_arg_count,
+                script.string_arg_count,
+                script.local_int_count,
+                script.local_string_count
+            )
+        );
+        assert_eq!(9, script.instructions.len());
+        assert_eq!(
+            Instruction {
+                opcode: SCONST,
+                operand: Operand::String("Hello".to_string())
+            },
+            script.instructions[3]
+        );
+        assert_eq!(
+            Instruction {
+                opcode: 3100,
+                operand: Operand::Byte(0)
+            },
+            script.instructions[4]
+        );
+        assert_eq!(vec![vec![(1, 2), (5, 4)]], script.switches);
+
+        let script = cache.script(1).unwrap();
+        assert_eq!(Some("[proc,test]".to_string()), script.name);
+        assert!(script.switches.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut cache = Cache::open("tests/data/cache/cache-scripts").unwrap();
+        for id in 0..2 {
+            let buf = cache.read(CLIENTSCRIPTS_ARCHIVE, id, 0, None).unwrap();
+            assert_eq!(buf, Script::decode(&buf).unwrap().encode().unwrap());
+        }
+
+        let script = Script {
+            instructions: vec![Instruction {
+                opcode: RETURN,
+                operand: Operand::Int(0),
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            script.encode(),
+            Err(ScriptError::InvalidOperand(RETURN))
+        ));
+        assert!(matches!(
+            Script::decode(&[0, 1]),
+            Err(ScriptError::TrailerOutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let mut cache = Cache::open("tests/data/cache/cache-scripts").unwrap();
+        let script = cache.script(0).unwrap();
+
+        let mut names = OpcodeNames::open("tests/data/clientscript/opcodes.txt").unwrap();
+        names = names.with_core_opcodes();
+        assert_eq!(Some("mes"), names.get(3100));
+        assert_eq!(Some("load_int"), names.get(33));
+
+        assert_eq!(
+            "; int args 1, string args 1, int locals 1, string locals 0
+    load_int 0
+    switch 0
+        case 1: label_4
+        case 5: label_6
+    jump label_8
+    sconst \"Hello\"
+label_4:
+    mes 0
+    jump label_8
+label_6:
+    op_3101 2
+    iconst -1
+label_8:
+    return 0
+",
+            script.disassemble(&names)
+        );
+
+        assert!(matches!(
+            names.read("1 a b".as_bytes()),
+            Err(ScriptError::InvalidMapping(1))
+        ));
+    }
+}
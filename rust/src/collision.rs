@@ -0,0 +1,571 @@
+use crate::{
+    cache::CacheError,
+    definitions::{object::INTERACT_TYPE_BLOCKING, ObjectDefinition},
+    djb2::djb2_hash,
+    map::{
+        self, Location, MapError, Terrain, MAPS_ARCHIVE, MAP_PLANES, MAP_SIZE, SETTING_BLOCKED,
+        SETTING_BRIDGE,
+    },
+    Cache,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use osrs_bytes::{ReadExt, WriteExt};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+use thiserror::Error;
+
+pub const BLOCK_NORTH_WEST: u32 = 0x1;
+pub const BLOCK_NORTH: u32 = 0x2;
+pub const BLOCK_NORTH_EAST: u32 = 0x4;
+pub const BLOCK_EAST: u32 = 0x8;
+pub const BLOCK_SOUTH_EAST: u32 = 0x10;
+pub const BLOCK_SOUTH: u32 = 0x20;
+pub const BLOCK_SOUTH_WEST: u32 = 0x40;
+pub const BLOCK_WEST: u32 = 0x80;
+/// Movement is blocked by a loc occupying the tile.
+pub const BLOCK_LOC: u32 = 0x100;
+/// Shifting a wall flag by this many bits gives the matching projectile flag.
+pub const PROJECTILE_SHIFT: u32 = 9;
+/// Projectiles are blocked by a loc occupying the tile.
+pub const BLOCK_PROJECTILE_LOC: u32 = 0x20000;
+/// Movement is blocked by a floor decoration.
+pub const BLOCK_FLOOR_DECORATION: u32 = 0x40000;
+/// Movement is blocked by the tile's settings.
+pub const BLOCK_FLOOR: u32 = 0x200000;
+
+/// Identifies the collision file format, followed by a version byte.
+const MAGIC: &[u8; 4] = b"COLL";
+const VERSION: u8 = 1;
+
+const SQUARE_TILES: usize = MAP_SIZE * MAP_SIZE;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum CollisionError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("cache error: {0}")]
+    Cache(#[from] CacheError),
+    #[error("map error: {0}")]
+    Map(#[from] MapError),
+    #[error("invalid collision file header")]
+    InvalidHeader,
+    #[error("unsupported collision file version: {0}")]
+    UnsupportedVersion(u8),
+}
+
+/// Collision flags of every tile, stored per map square and plane. Tiles of
+/// squares that were never touched have no flags.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CollisionMap {
+    /// Flags keyed by map square id, indexed by plane, then x, then y
+    squares: BTreeMap<u16, Vec<u32>>,
+    /// Map squares whose locations couldn't be read, for lack of a key
+    incomplete: Vec<u32>,
+}
+
+impl CollisionMap {
+    /// Create an empty collision map
+    pub fn new() -> CollisionMap {
+        Self::default()
+    }
+
+    /// Build the collision flags of a single map square. Locs at the edge of
+    /// the square may add flags to its neighbours.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The cache to read the map square and loc definitions from
+    /// * `x` - The x coordinate of the map square
+    /// * `y` - The y coordinate of the map square
+    /// * `xtea_keys` - The XTEA keys of the locations. If None, the key store is consulted
+    pub fn build_region(
+        cache: &mut Cache,
+        x: u8,
+        y: u8,
+        xtea_keys: Option<[u32; 4]>,
+    ) -> Result<CollisionMap, CollisionError> {
+        let square = cache.map_square(x, y, xtea_keys)?;
+
+        let mut collision = CollisionMap::new();
+        let mut objects = HashMap::new();
+        collision.add_terrain(x, y, &square.terrain);
+        for location in &square.locations {
+            let object = object(cache, &mut objects, location.id)?;
+            collision.add_location(x, y, &square.terrain, location, object);
+        }
+
+        Ok(collision)
+    }
+
+    /// Build the collision flags of every map square in the cache, using the
+    /// cache's key store. Squares without a key for their locations only get
+    /// their terrain flags and are listed by [`CollisionMap::incomplete_squares`].
+    /// Locations that fail to read or decode are an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The cache to read the map squares and loc definitions from
+    pub fn build_world(cache: &mut Cache) -> Result<CollisionMap, CollisionError> {
+        let mut collision = CollisionMap::new();
+        let mut objects = HashMap::new();
+
+        for x in 0..=u8::MAX {
+            for y in 0..=u8::MAX {
+                let index = cache.index(MAPS_ARCHIVE)?;
+                if index.get_named(djb2_hash(map::terrain_name(x, y))).is_err() {
+                    continue;
+                }
+                let locations_name = map::locations_name(x, y);
+                let has_locations = index.get_named(djb2_hash(&locations_name)).is_ok();
+
+                let buf =
+                    cache.read_named_group(MAPS_ARCHIVE, &map::terrain_name(x, y), 0, None)?;
+                let terrain = Terrain::decode_revision(&buf, cache.revision())?;
+                collision.add_terrain(x, y, &terrain);
+
+                if !has_locations {
+                    continue;
+                }
+                if cache.keys().get_named(&locations_name).is_none() {
+                    collision.incomplete.push(((x as u32) << 8) | y as u32);
+                    continue;
+                }
+                let buf = cache.read_named_group(MAPS_ARCHIVE, &locations_name, 0, None)?;
+                for location in &map::decode_locations(&buf)? {
+                    let object = object(cache, &mut objects, location.id)?;
+                    collision.add_location(x, y, &terrain, location, object);
+                }
+            }
+        }
+
+        Ok(collision)
+    }
+
+    /// Get the map squares whose locations couldn't be read while building
+    pub fn incomplete_squares(&self) -> &[u32] {
+        &self.incomplete
+    }
+
+    /// Get the collision flags of a tile
+    ///
+    /// # Arguments
+    ///
+    /// * `plane` - The plane of the tile, from 0 to 3
+    /// * `x` - The absolute x coordinate of the tile
+    /// * `y` - The absolute y coordinate of the tile
+    pub fn flags(&self, plane: u8, x: u32, y: u32) -> u32 {
+        match Self::locate(plane, x, y) {
+            Some((square, offset)) => self.squares.get(&square).map_or(0, |flags| flags[offset]),
+            None => 0,
+        }
+    }
+
+    /// Add collision flags to a tile
+    ///
+    /// # Arguments
+    ///
+    /// * `plane` - The plane of the tile, from 0 to 3
+    /// * `x` - The absolute x coordinate of the tile
+    /// * `y` - The absolute y coordinate of the tile
+    /// * `flags` - The flags to add
+    pub fn add_flags(&mut self, plane: u8, x: u32, y: u32, flags: u32) {
+        if let Some((square, offset)) = Self::locate(plane, x, y) {
+            self.squares
+                .entry(square)
+                .or_insert_with(|| vec![0; MAP_PLANES * SQUARE_TILES])[offset] |= flags;
+        }
+    }
+
+    /// Remove collision flags from a tile
+    ///
+    /// # Arguments
+    ///
+    /// * `plane` - The plane of the tile, from 0 to 3
+    /// * `x` - The absolute x coordinate of the tile
+    /// * `y` - The absolute y coordinate of the tile
+    /// * `flags` - The flags to remove
+    pub fn remove_flags(&mut self, plane: u8, x: u32, y: u32, flags: u32) {
+        if let Some((square, offset)) = Self::locate(plane, x, y) {
+            if let Some(tiles) = self.squares.get_mut(&square) {
+                tiles[offset] &= !flags;
+            }
+        }
+    }
+
+    /// Get the ids of the map squares that have flags
+    pub fn squares(&self) -> impl Iterator<Item = u32> + '_ {
+        self.squares.keys().map(|square| *square as u32)
+    }
+
+    /// Open a collision file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<CollisionMap, CollisionError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Save the collision map to a file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CollisionError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read a collision map in the format written by [`CollisionMap::write`]
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader to read from
+    pub fn read<R: Read>(mut reader: R) -> Result<CollisionMap, CollisionError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(CollisionError::InvalidHeader);
+        }
+        let version = reader.read_u8()?;
+        if version != VERSION {
+            return Err(CollisionError::UnsupportedVersion(version));
+        }
+
+        let mut reader = GzDecoder::new(reader);
+        let mut collision = CollisionMap::new();
+        let len = reader.read_u32()?;
+        for _ in 0..len {
+            let square = reader.read_u16()?;
+            let mut tiles = Vec::with_capacity(MAP_PLANES * SQUARE_TILES);
+            for _ in 0..MAP_PLANES * SQUARE_TILES {
+                tiles.push(reader.read_u32()?);
+            }
+            collision.squares.insert(square, tiles);
+        }
+
+        Ok(collision)
+    }
+
+    /// Write the collision map as a gzip compressed list of map squares and
+    /// their flags, prefixed by a magic number and version
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The writer to write to
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), CollisionError> {
+        writer.write_all(MAGIC)?;
+        writer.write_u8(VERSION)?;
+
+        let mut writer = GzEncoder::new(writer, Compression::default());
+        writer.write_u32(self.squares.len() as u32)?;
+        for (square, tiles) in &self.squares {
+            writer.write_u16(*square)?;
+            for flags in tiles {
+                writer.write_u32(*flags)?;
+            }
+        }
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Block the floor of every tile with the blocked setting, moving the
+    /// flag down a plane under bridges
+    fn add_terrain(&mut self, square_x: u8, square_y: u8, terrain: &Terrain) {
+        let (base_x, base_y) = base(square_x, square_y);
+        for plane in 0..MAP_PLANES {
+            for x in 0..MAP_SIZE {
+                for y in 0..MAP_SIZE {
+                    if terrain.tile(plane, x, y).settings & SETTING_BLOCKED == 0 {
+                        continue;
+                    }
+                    if let Some(plane) = visible_plane(terrain, plane, x, y) {
+                        self.add_flags(plane, base_x + x as u32, base_y + y as u32, BLOCK_FLOOR);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Add the flags of a loc the way the client does when loading a scene
+    fn add_location(
+        &mut self,
+        square_x: u8,
+        square_y: u8,
+        terrain: &Terrain,
+        location: &Location,
+        object: &ObjectDefinition,
+    ) {
+        let Some(plane) = visible_plane(
+            terrain,
+            location.plane as usize,
+            location.x as usize,
+            location.y as usize,
+        ) else {
+            return;
+        };
+        let (base_x, base_y) = base(square_x, square_y);
+        let x = base_x + location.x as u32;
+        let y = base_y + location.y as u32;
+
+        match location.shape {
+            // Floor decorations only block when they can be interacted with
+            22 if object.interact_type == INTERACT_TYPE_BLOCKING => {
+                self.add_flags(plane, x, y, BLOCK_FLOOR_DECORATION);
+            }
+            0..=3 if object.solid() => {
+                self.add_wall(
+                    plane,
+                    x,
+                    y,
+                    location.shape,
+                    location.rotation,
+                    object.blocks_projectile,
+                );
+            }
+            // Diagonal walls, scenery and roofs
+            9..=21 | 23.. if object.solid() => {
+                let (size_x, size_y) = object.rotated_size(location.rotation);
+                let mut flags = BLOCK_LOC;
+                if object.blocks_projectile {
+                    flags |= BLOCK_PROJECTILE_LOC;
+                }
+                for dx in 0..size_x as u32 {
+                    for dy in 0..size_y as u32 {
+                        self.add_flags(plane, x + dx, y + dy, flags);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Add the flags of a wall, on its own tile and on the tiles on the
+    /// other side of it
+    fn add_wall(&mut self, plane: u8, x: u32, y: u32, shape: u8, rotation: u8, projectile: bool) {
+        let rotation = rotation & 0x3;
+        let tiles: &[(i32, i32, u32)] = match shape {
+            // Straight walls along one side of the tile
+            0 => match rotation {
+                0 => &[(0, 0, BLOCK_WEST), (-1, 0, BLOCK_EAST)],
+                1 => &[(0, 0, BLOCK_NORTH), (0, 1, BLOCK_SOUTH)],
+                2 => &[(0, 0, BLOCK_EAST), (1, 0, BLOCK_WEST)],
+                _ => &[(0, 0, BLOCK_SOUTH), (0, -1, BLOCK_NORTH)],
+            },
+            // Diagonal and corner pieces, blocking a single corner
+            1 | 3 => match rotation {
+                0 => &[(0, 0, BLOCK_NORTH_WEST), (-1, 1, BLOCK_SOUTH_EAST)],
+                1 => &[(0, 0, BLOCK_NORTH_EAST), (1, 1, BLOCK_SOUTH_WEST)],
+                2 => &[(0, 0, BLOCK_SOUTH_EAST), (1, -1, BLOCK_NORTH_WEST)],
+                _ => &[(0, 0, BLOCK_SOUTH_WEST), (-1, -1, BLOCK_NORTH_EAST)],
+            },
+            // L shaped corners, blocking two sides
+            _ => match rotation {
+                0 => &[
+                    (0, 0, BLOCK_WEST | BLOCK_NORTH),
+                    (-1, 0, BLOCK_EAST),
+                    (0, 1, BLOCK_SOUTH),
+                ],
+                1 => &[
+                    (0, 0, BLOCK_NORTH | BLOCK_EAST),
+                    (0, 1, BLOCK_SOUTH),
+                    (1, 0, BLOCK_WEST),
+                ],
+                2 => &[
+                    (0, 0, BLOCK_EAST | BLOCK_SOUTH),
+                    (1, 0, BLOCK_WEST),
+                    (0, -1, BLOCK_NORTH),
+                ],
+                _ => &[
+                    (0, 0, BLOCK_SOUTH | BLOCK_WEST),
+                    (0, -1, BLOCK_NORTH),
+                    (-1, 0, BLOCK_EAST),
+                ],
+            },
+        };
+
+        for (dx, dy, flags) in tiles {
+            let (Some(x), Some(y)) = (x.checked_add_signed(*dx), y.checked_add_signed(*dy)) else {
+                continue;
+            };
+            let mut flags = *flags;
+            if projectile {
+                flags |= flags << PROJECTILE_SHIFT;
+            }
+            self.add_flags(plane, x, y, flags);
+        }
+    }
+
+    /// Find the map square and offset of a tile, `None` if out of bounds
+    fn locate(plane: u8, x: u32, y: u32) -> Option<(u16, usize)> {
+        let size = MAP_SIZE as u32;
+        if plane as usize >= MAP_PLANES || x >= size * 256 || y >= size * 256 {
+            return None;
+        }
+        let square = (((x / size) << 8) | (y / size)) as u16;
+        let offset =
+            (plane as usize * MAP_SIZE + (x % size) as usize) * MAP_SIZE + (y % size) as usize;
+        Some((square, offset))
+    }
+}
+
+fn base(square_x: u8, square_y: u8) -> (u32, u32) {
+    (
+        square_x as u32 * MAP_SIZE as u32,
+        square_y as u32 * MAP_SIZE as u32,
+    )
+}
+
+/// Get the plane a tile's contents end up on, moving down a plane when the
+/// tile above plane 0 is a bridge. `None` for contents below a bridge.
+fn visible_plane(terrain: &Terrain, plane: usize, x: usize, y: usize) -> Option<u8> {
+    if terrain.tile(1, x, y).settings & SETTING_BRIDGE != 0 {
+        plane.checked_sub(1).map(|plane| plane as u8)
+    } else {
+        Some(plane as u8)
+    }
+}
+
+fn object<'a>(
+    cache: &mut Cache,
+    objects: &'a mut HashMap<u32, ObjectDefinition>,
+    id: u32,
+) -> Result<&'a ObjectDefinition, CollisionError> {
+    Ok(match objects.entry(id) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(cache.object(id)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_store::KeyStore;
+
+    const KEY: [u32; 4] = [0x1234_5678, 0x9abc_def0, 0x0fed_cba9, 0x8765_4321];
+
+    fn build() -> CollisionMap {
+        let mut cache = Cache::open("tests/data/cache/cache-maps").unwrap();
+        CollisionMap::build_region(&mut cache, 51, 50, Some(KEY)).unwrap()
+    }
+
+    #[test]
+    fn test_terrain_flags() {
+        let collision = build();
+
+        assert_eq!(BLOCK_FLOOR, collision.flags(0, 3264, 3200));
+        // The blocked tile on plane 2 is moved down by the bridge on plane 1
+        assert_eq!(BLOCK_FLOOR, collision.flags(1, 3269, 3205));
+        assert_eq!(0, collision.flags(2, 3269, 3205));
+    }
+
+    #[test]
+    fn test_bridge_locations() {
+        let collision = build();
+
+        // The table on plane 1 moves down to plane 0, rotated to 3x1 and not
+        // blocking projectiles, while the loc below the bridge is dropped
+        for x in 3269..3272 {
+            assert_eq!(BLOCK_LOC, collision.flags(0, x, 3205));
+        }
+        assert_eq!(0, collision.flags(0, 3272, 3205));
+        assert_eq!(0, collision.flags(0, 3269, 3206));
+    }
+
+    #[test]
+    fn test_walls() {
+        let collision = build();
+
+        let shifted = |flags: u32| flags | (flags << PROJECTILE_SHIFT);
+
+        // Straight wall on the west side
+        assert_eq!(shifted(BLOCK_WEST), collision.flags(0, 3274, 3210));
+        assert_eq!(shifted(BLOCK_EAST), collision.flags(0, 3273, 3210));
+
+        // L shaped corner facing north and east
+        assert_eq!(
+            shifted(BLOCK_NORTH | BLOCK_EAST),
+            collision.flags(0, 3284, 3220)
+        );
+        assert_eq!(shifted(BLOCK_SOUTH), collision.flags(0, 3284, 3221));
+        assert_eq!(shifted(BLOCK_WEST), collision.flags(0, 3285, 3220));
+
+        // Corner piece in the south west
+        assert_eq!(shifted(BLOCK_SOUTH_WEST), collision.flags(0, 3294, 3230));
+        assert_eq!(shifted(BLOCK_NORTH_EAST), collision.flags(0, 3293, 3229));
+    }
+
+    #[test]
+    fn test_decorations_and_neighbours() {
+        let collision = build();
+
+        assert_eq!(0, collision.flags(0, 3304, 3240));
+        assert_eq!(BLOCK_FLOOR_DECORATION, collision.flags(0, 3305, 3241));
+
+        // The 2x2 tree in the north east corner spills into three neighbours
+        let tree = BLOCK_LOC | BLOCK_PROJECTILE_LOC;
+        for (x, y) in [(3327, 3263), (3328, 3263), (3327, 3264), (3328, 3264)] {
+            assert_eq!(tree, collision.flags(0, x, y));
+        }
+        assert_eq!(
+            vec![13106, 13107, 13362, 13363],
+            collision.squares().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_build_world() {
+        let mut cache = Cache::open("tests/data/cache/cache-maps").unwrap();
+        let mut keys = KeyStore::new();
+        keys.insert(12850, KEY);
+        cache.set_keys(keys);
+
+        let collision = CollisionMap::build_world(&mut cache).unwrap();
+
+        assert_eq!(&[13106], collision.incomplete_squares());
+        // Terrain of the incomplete square is still applied
+        assert_eq!(BLOCK_FLOOR, collision.flags(0, 3264, 3200));
+        assert_eq!(0, collision.flags(0, 3274, 3210));
+        // Locations of squares with keys are applied
+        assert_eq!(
+            BLOCK_LOC | BLOCK_PROJECTILE_LOC,
+            collision.flags(0, 3220, 3220)
+        );
+    }
+
+    #[test]
+    fn test_build_world_wrong_key() {
+        let mut cache = Cache::open("tests/data/cache/cache-maps").unwrap();
+        let mut keys = KeyStore::new();
+        keys.insert(12850, [1, 2, 3, 4]);
+        cache.set_keys(keys);
+
+        assert!(CollisionMap::build_world(&mut cache).is_err());
+    }
+
+    #[test]
+    fn test_write_read() {
+        let mut collision = build();
+        collision.remove_flags(0, 3264, 3200, BLOCK_FLOOR);
+        assert_eq!(0, collision.flags(0, 3264, 3200));
+
+        let mut buf = Vec::new();
+        collision.write(&mut buf).unwrap();
+        assert_eq!(b"COLL\x01", &buf[..5]);
+
+        let read = CollisionMap::read(buf.as_slice()).unwrap();
+        assert_eq!(collision, read);
+        assert!(matches!(
+            CollisionMap::read(&b"NOPE\x01"[..]),
+            Err(CollisionError::InvalidHeader)
+        ));
+    }
+}
@@ -0,0 +1,424 @@
+use crate::{
+    buffer::{CacheReadExt, CacheWriteExt},
+    revision::Revision,
+};
+use osrs_bytes::{ReadExt, WriteExt};
+use thiserror::Error;
+
+pub(crate) mod codec;
+pub mod enum_type;
+pub mod healthbar;
+pub mod hitsplat;
+pub mod idk;
+pub mod inv;
+pub mod item;
+pub mod npc;
+pub mod object;
+pub mod overlay;
+pub mod param;
+pub mod spotanim;
+pub mod struct_type;
+pub mod underlay;
+pub mod varbit;
+pub mod varp;
+
+pub use enum_type::EnumDefinition;
+pub use healthbar::HealthBarDefinition;
+pub use hitsplat::HitsplatDefinition;
+pub use idk::IdkDefinition;
+pub use inv::InvDefinition;
+pub use item::ItemDefinition;
+pub use npc::{HeadIcon, NpcDefinition};
+pub use object::ObjectDefinition;
+pub use overlay::OverlayDefinition;
+pub use param::ParamDefinition;
+pub use spotanim::SpotAnimDefinition;
+pub use struct_type::StructDefinition;
+pub use underlay::UnderlayDefinition;
+pub use varbit::VarbitDefinition;
+pub use varp::VarpDefinition;
+
+/// The archive holding the config groups.
+pub const CONFIG_ARCHIVE: u8 = 2;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum DefinitionError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("unknown opcode {opcode} at offset {offset}")]
+    UnknownOpcode { opcode: u8, offset: usize },
+    #[error("values of different types can't be encoded together")]
+    MixedValueTypes,
+    #[error("opcode {opcode} can't hold this value in revision {}", revision.0)]
+    Unencodable { opcode: u8, revision: Revision },
+}
+
+/// The opcodes of a config type and the fields each holds.
+///
+/// Most config types implement this with the `opcodes!` table, describing
+/// every opcode once for both directions.
+pub trait Opcodes {
+    /// Decode the fields held by an opcode, returning `false` if the opcode
+    /// isn't known in the revision
+    ///
+    /// # Arguments
+    ///
+    /// * `opcode` - The opcode, which is never 0
+    /// * `buf` - The encoded config, positioned after the opcode
+    /// * `revision` - The revision of the cache the config is from
+    fn decode_opcode(
+        &mut self,
+        opcode: u8,
+        buf: &mut &[u8],
+        revision: Revision,
+    ) -> Result<bool, DefinitionError>;
+
+    /// Encode the opcodes of the fields that differ from their defaults in
+    /// ascending order, without the terminating opcode 0
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The buffer to write to
+    /// * `revision` - The revision of the cache the config is for
+    fn encode_opcodes(&self, buf: &mut Vec<u8>, revision: Revision) -> Result<(), DefinitionError>;
+}
+
+/// A config type stored as one file per id in a group of the config archive.
+///
+/// Each file is a stream of opcodes followed by their fields, terminated by
+/// opcode 0. Encoding writes the fields that differ from their defaults in
+/// ascending opcode order.
+pub trait Definition: Opcodes + Sized {
+    /// The group of the config archive holding this config type
+    const GROUP: u32;
+
+    /// Create a definition with every field at its default
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the definition, which is the file id within the group
+    fn with_id(id: u32) -> Self;
+
+    /// Decode the definition with the given id, using the latest opcodes
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the definition, which is the file id within the group
+    /// * `buf` - The encoded definition
+    fn decode(id: u32, buf: &[u8]) -> Result<Self, DefinitionError> {
+        Self::decode_revision(id, buf, Revision::LATEST)
+    }
+
+    /// Decode the definition with the given id from a cache of a revision
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the definition, which is the file id within the group
+    /// * `buf` - The encoded definition
+    /// * `revision` - The revision of the cache
+    fn decode_revision(id: u32, buf: &[u8], revision: Revision) -> Result<Self, DefinitionError> {
+        let mut definition = Self::with_id(id);
+
+        let mut reader = buf;
+        loop {
+            let offset = buf.len() - reader.len();
+            let opcode = reader.read_u8()?;
+            if opcode == 0 {
+                return Ok(definition);
+            }
+            if !definition.decode_opcode(opcode, &mut reader, revision)? {
+                return Err(DefinitionError::UnknownOpcode { opcode, offset });
+            }
+        }
+    }
+
+    /// Encode the definition with the latest opcodes
+    fn encode(&self) -> Result<Vec<u8>, DefinitionError> {
+        self.encode_revision(Revision::LATEST)
+    }
+
+    /// Encode the definition for a cache of a revision.
+    ///
+    /// Fields at their default value aren't written, since the decoded
+    /// definition doesn't record which opcodes were present. Re-encoding a
+    /// config that explicitly stored a default value is therefore shorter than
+    /// the original, though it decodes to the same definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `revision` - The revision of the cache
+    fn encode_revision(&self, revision: Revision) -> Result<Vec<u8>, DefinitionError> {
+        let mut buf = Vec::new();
+        self.encode_opcodes(&mut buf, revision)?;
+        buf.write_u8(0)?;
+        Ok(buf)
+    }
+}
+
+/// A value of a param, either an integer or a string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParamValue {
+    Int(i32),
+    String(String),
+}
+
+/// Get whether values of a script type, such as `i` for ints or `o` for
+/// objs, are stored as strings. Only `s` is; every other type is an int.
+///
+/// # Arguments
+///
+/// * `type_char` - The type char
+pub fn is_string_type(type_char: char) -> bool {
+    type_char == 's'
+}
+
+/// The params of a config, keyed by param id. The order params were decoded
+/// in is kept so that encoding reproduces the original bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Params {
+    entries: Vec<(u32, ParamValue)>,
+}
+
+impl Params {
+    /// Create an empty set of params
+    pub fn new() -> Params {
+        Self::default()
+    }
+
+    /// Get the value of a param
+    pub fn get(&self, id: u32) -> Option<&ParamValue> {
+        self.entries
+            .iter()
+            .find(|(key, _)| *key == id)
+            .map(|(_, value)| value)
+    }
+
+    /// Get the value of an integer param
+    pub fn get_int(&self, id: u32) -> Option<i32> {
+        match self.get(id) {
+            Some(ParamValue::Int(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a string param
+    pub fn get_string(&self, id: u32) -> Option<&str> {
+        match self.get(id) {
+            Some(ParamValue::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Set the value of a param, replacing any previous value
+    pub fn insert(&mut self, id: u32, value: ParamValue) {
+        match self.entries.iter_mut().find(|(key, _)| *key == id) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((id, value)),
+        }
+    }
+
+    /// Iterate over the params in order
+    pub fn iter(&self) -> impl Iterator<Item = &(u32, ParamValue)> {
+        self.entries.iter()
+    }
+
+    /// Get the number of params
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether there are no params
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(crate) fn read(buf: &mut &[u8]) -> Result<Params, DefinitionError> {
+        let mut params = Params::new();
+
+        let len = buf.read_u8()?;
+        for _ in 0..len {
+            let is_string = buf.read_u8()? == 1;
+            let id = buf.read_u24()?;
+            let value = if is_string {
+                ParamValue::String(buf.read_cp1252_string()?)
+            } else {
+                ParamValue::Int(buf.read_i32()?)
+            };
+            params.insert(id, value);
+        }
+
+        Ok(params)
+    }
+
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        buf.write_u8(self.entries.len() as u8)?;
+        for (id, value) in &self.entries {
+            buf.write_u8(matches!(value, ParamValue::String(_)) as u8)?;
+            buf.write_u24(*id)?;
+            match value {
+                ParamValue::Int(value) => buf.write_i32(*value)?,
+                ParamValue::String(value) => buf.write_cp1252_string(value)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A transform of an npc or loc into one of several children depending on the
+/// value of a varbit or varp.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transform {
+    pub varbit: Option<u16>,
+    pub varp: Option<u16>,
+    /// The child for each value of the variable, `None` hiding the config
+    pub children: Vec<Option<u16>>,
+    /// The child used when the value is out of range, only held by the opcode
+    /// with a default child. `Some(None)` hides the config.
+    pub default_child: Option<Option<u16>>,
+}
+
+impl Transform {
+    pub(crate) fn read(buf: &mut &[u8], has_default: bool) -> Result<Transform, DefinitionError> {
+        let varbit = read_optional_u16(buf)?;
+        let varp = read_optional_u16(buf)?;
+        let default_child = if has_default {
+            Some(read_optional_u16(buf)?)
+        } else {
+            None
+        };
+
+        let len = buf.read_u8()? as usize + 1;
+        let mut children = Vec::with_capacity(len);
+        for _ in 0..len {
+            children.push(read_optional_u16(buf)?);
+        }
+
+        Ok(Transform {
+            varbit,
+            varp,
+            children,
+            default_child,
+        })
+    }
+
+    /// Check whether the children fit the count byte, which holds one less
+    /// than the number of children
+    pub(crate) fn has_encodable_children(&self) -> bool {
+        (1..=256).contains(&self.children.len())
+    }
+
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        buf.write_u16(self.varbit.unwrap_or(u16::MAX))?;
+        buf.write_u16(self.varp.unwrap_or(u16::MAX))?;
+        if let Some(default_child) = self.default_child {
+            buf.write_u16(default_child.unwrap_or(u16::MAX))?;
+        }
+        buf.write_u8((self.children.len() - 1) as u8)?;
+        for child in &self.children {
+            buf.write_u16(child.unwrap_or(u16::MAX))?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn read_optional_u16(buf: &mut &[u8]) -> Result<Option<u16>, DefinitionError> {
+    Ok(match buf.read_u16()? {
+        u16::MAX => None,
+        value => Some(value),
+    })
+}
+
+pub(crate) fn read_optional_big_smart(buf: &mut &[u8]) -> Result<Option<u32>, DefinitionError> {
+    Ok(match buf.read_big_smart()? {
+        -1 => None,
+        value => Some(value as u32),
+    })
+}
+
+pub(crate) fn read_u16_list(buf: &mut &[u8]) -> Result<Vec<u16>, DefinitionError> {
+    let len = buf.read_u8()?;
+    let mut values = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        values.push(buf.read_u16()?);
+    }
+    Ok(values)
+}
+
+pub(crate) fn write_u16_list(buf: &mut Vec<u8>, values: &[u16]) -> Result<(), DefinitionError> {
+    buf.write_u8(values.len() as u8)?;
+    for value in values {
+        buf.write_u16(*value)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_u16_pairs(buf: &mut &[u8]) -> Result<(Vec<u16>, Vec<u16>), DefinitionError> {
+    let len = buf.read_u8()?;
+    let mut find = Vec::with_capacity(len as usize);
+    let mut replace = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        find.push(buf.read_u16()?);
+        replace.push(buf.read_u16()?);
+    }
+    Ok((find, replace))
+}
+
+pub(crate) fn write_u16_pairs(
+    buf: &mut Vec<u8>,
+    find: &[u16],
+    replace: &[u16],
+) -> Result<(), DefinitionError> {
+    buf.write_u8(find.len() as u8)?;
+    for (find, replace) in find.iter().zip(replace) {
+        buf.write_u16(*find)?;
+        buf.write_u16(*replace)?;
+    }
+    Ok(())
+}
+
+/// Assert that every definition of a config type in a cache of the latest
+/// revision encodes back to the bytes it was decoded from, returning the number
+/// of definitions checked
+///
+/// # Arguments
+///
+/// * `path` - The path to the cache
+#[cfg(test)]
+pub(crate) fn assert_round_trip<T: Definition>(path: &str) -> usize {
+    assert_round_trip_revision::<T>(path, Revision::LATEST)
+}
+
+/// Assert that every definition of a config type in a cache of a revision
+/// encodes back to the bytes it was decoded from, returning the number of
+/// definitions checked
+///
+/// # Arguments
+///
+/// * `path` - The path to the cache
+/// * `revision` - The revision of the cache
+#[cfg(test)]
+pub(crate) fn assert_round_trip_revision<T: Definition>(path: &str, revision: Revision) -> usize {
+    let mut cache = crate::Cache::open(path).unwrap();
+    let ids: Vec<u32> = cache.index(CONFIG_ARCHIVE).unwrap().groups[&T::GROUP]
+        .files
+        .keys()
+        .copied()
+        .collect();
+    assert!(!ids.is_empty());
+
+    for &id in &ids {
+        let buf = cache
+            .read(CONFIG_ARCHIVE, T::GROUP, id as u16, None)
+            .unwrap();
+        let definition = T::decode_revision(id, &buf, revision).unwrap();
+        let name = std::any::type_name::<T>();
+        assert_eq!(
+            buf,
+            definition.encode_revision(revision).unwrap(),
+            "{name} {id}"
+        );
+    }
+    ids.len()
+}
@@ -0,0 +1,538 @@
+//! The field encodings used by the `opcodes!` table.
+
+use super::{
+    read_optional_big_smart, read_optional_u16, read_u16_list, read_u16_pairs, write_u16_list,
+    write_u16_pairs,
+};
+use super::{DefinitionError, Params, Transform};
+use crate::buffer::{char_to_cp1252, cp1252_to_char, CacheReadExt, CacheWriteExt};
+use osrs_bytes::{ReadExt, WriteExt};
+
+/// Reads and writes a value of type `T` held by an opcode.
+pub(crate) trait Codec<T> {
+    fn read(buf: &mut &[u8]) -> Result<T, DefinitionError>;
+
+    fn write(value: &T, buf: &mut Vec<u8>) -> Result<(), DefinitionError>;
+
+    /// Check whether the opcode holds a value that differs from the default,
+    /// letting several opcodes share a field
+    fn is_held(_value: &T) -> bool {
+        true
+    }
+
+    /// Check whether the opcode can hold a value it is given, such as an
+    /// `Option` being `Some`
+    fn is_encodable(_value: &T) -> bool {
+        true
+    }
+}
+
+macro_rules! primitive_codec {
+    ($($codec:ident($type:ty, $read:ident, $write:ident)),* $(,)?) => {
+        $(
+            pub(crate) struct $codec;
+
+            impl Codec<$type> for $codec {
+                fn read(buf: &mut &[u8]) -> Result<$type, DefinitionError> {
+                    Ok(buf.$read()?)
+                }
+
+                fn write(value: &$type, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+                    Ok(buf.$write(*value)?)
+                }
+            }
+        )*
+    };
+}
+
+primitive_codec! {
+    U8(u8, read_u8, write_u8),
+    I8(i8, read_i8, write_i8),
+    U16(u16, read_u16, write_u16),
+    I16(i16, read_i16, write_i16),
+    U24(u32, read_u24, write_u24),
+    I32(i32, read_i32, write_i32),
+}
+
+/// A null terminated Windows-1252 string.
+pub(crate) struct Cp1252;
+
+impl Codec<String> for Cp1252 {
+    fn read(buf: &mut &[u8]) -> Result<String, DefinitionError> {
+        Ok(buf.read_cp1252_string()?)
+    }
+
+    fn write(value: &String, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(buf.write_cp1252_string(value)?)
+    }
+}
+
+/// A Windows-1252 string after a byte the client skips, written as 0.
+pub(crate) struct PrefixedCp1252;
+
+impl Codec<String> for PrefixedCp1252 {
+    fn read(buf: &mut &[u8]) -> Result<String, DefinitionError> {
+        buf.read_u8()?;
+        Ok(buf.read_cp1252_string()?)
+    }
+
+    fn write(value: &String, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        buf.write_u8(0)?;
+        Ok(buf.write_cp1252_string(value)?)
+    }
+}
+
+/// A single Windows-1252 character, such as a script type char.
+pub(crate) struct Char;
+
+impl Codec<char> for Char {
+    fn read(buf: &mut &[u8]) -> Result<char, DefinitionError> {
+        Ok(cp1252_to_char(buf.read_u8()?))
+    }
+
+    fn write(value: &char, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(buf.write_u8(char_to_cp1252(*value))?)
+    }
+}
+
+/// A byte count followed by that many shorts.
+pub(crate) struct U16List;
+
+impl Codec<Vec<u16>> for U16List {
+    fn read(buf: &mut &[u8]) -> Result<Vec<u16>, DefinitionError> {
+        read_u16_list(buf)
+    }
+
+    fn write(value: &Vec<u16>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        write_u16_list(buf, value)
+    }
+}
+
+/// A byte count followed by that many bytes.
+pub(crate) struct U8List;
+
+impl Codec<Vec<u8>> for U8List {
+    fn read(buf: &mut &[u8]) -> Result<Vec<u8>, DefinitionError> {
+        let len = buf.read_u8()?;
+        Ok((0..len).map(|_| buf.read_u8()).collect::<Result<_, _>>()?)
+    }
+
+    fn write(value: &Vec<u8>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        buf.write_u8(value.len() as u8)?;
+        buf.extend(value);
+        Ok(())
+    }
+}
+
+/// A byte count followed by that many pairs of shorts, such as recolors.
+pub(crate) struct U16Pairs;
+
+impl Codec<(Vec<u16>, Vec<u16>)> for U16Pairs {
+    fn read(buf: &mut &[u8]) -> Result<(Vec<u16>, Vec<u16>), DefinitionError> {
+        read_u16_pairs(buf)
+    }
+
+    fn write(value: &(Vec<u16>, Vec<u16>), buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        write_u16_pairs(buf, &value.0, &value.1)
+    }
+}
+
+/// The params of a config.
+pub(crate) struct ParamList;
+
+impl Codec<Params> for ParamList {
+    fn read(buf: &mut &[u8]) -> Result<Params, DefinitionError> {
+        Params::read(buf)
+    }
+
+    fn write(value: &Params, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        value.write(buf)
+    }
+}
+
+/// An opcode without fields that sets a flag to `V`.
+pub(crate) struct Set<const V: bool>;
+
+impl<const V: bool> Codec<bool> for Set<V> {
+    fn read(_buf: &mut &[u8]) -> Result<bool, DefinitionError> {
+        Ok(V)
+    }
+
+    fn write(_value: &bool, _buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(())
+    }
+
+    fn is_held(value: &bool) -> bool {
+        *value == V
+    }
+}
+
+/// An opcode without fields that sets a number to `V`.
+pub(crate) struct Const<const V: i32>;
+
+impl<const V: i32> Codec<u8> for Const<V> {
+    fn read(_buf: &mut &[u8]) -> Result<u8, DefinitionError> {
+        Ok(V as u8)
+    }
+
+    fn write(_value: &u8, _buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(())
+    }
+
+    fn is_held(value: &u8) -> bool {
+        *value as i32 == V
+    }
+}
+
+impl<const V: i32> Codec<i32> for Const<V> {
+    fn read(_buf: &mut &[u8]) -> Result<i32, DefinitionError> {
+        Ok(V)
+    }
+
+    fn write(_value: &i32, _buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(())
+    }
+
+    fn is_held(value: &i32) -> bool {
+        *value == V
+    }
+}
+
+/// An opcode without fields that starts fading out immediately, holding a
+/// fade start of 0.
+pub(crate) struct FadeNow;
+
+impl Codec<Option<u16>> for FadeNow {
+    fn read(_buf: &mut &[u8]) -> Result<Option<u16>, DefinitionError> {
+        Ok(Some(0))
+    }
+
+    fn write(_value: &Option<u16>, _buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(())
+    }
+
+    fn is_held(value: &Option<u16>) -> bool {
+        *value == Some(0)
+    }
+}
+
+/// A short that is kept as stored, with `None` written as 65535. Used for
+/// fields that share an opcode with fields that are set.
+pub(crate) struct NullableU16;
+
+impl Codec<Option<u16>> for NullableU16 {
+    fn read(buf: &mut &[u8]) -> Result<Option<u16>, DefinitionError> {
+        Ok(Some(buf.read_u16()?))
+    }
+
+    fn write(value: &Option<u16>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(buf.write_u16(value.unwrap_or(u16::MAX))?)
+    }
+}
+
+/// A short where 65535 is `None`, such as an id the client treats as absent.
+pub(crate) struct OptionalU16;
+
+impl Codec<Option<u16>> for OptionalU16 {
+    fn read(buf: &mut &[u8]) -> Result<Option<u16>, DefinitionError> {
+        read_optional_u16(buf)
+    }
+
+    fn write(value: &Option<u16>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(buf.write_u16(value.unwrap_or(u16::MAX))?)
+    }
+}
+
+/// A big smart where -1 is `None`, such as a sprite group.
+pub(crate) struct OptionalBigSmart;
+
+impl Codec<Option<u32>> for OptionalBigSmart {
+    fn read(buf: &mut &[u8]) -> Result<Option<u32>, DefinitionError> {
+        read_optional_big_smart(buf)
+    }
+
+    fn write(value: &Option<u32>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(buf.write_big_smart(value.map_or(-1, |value| value as i32))?)
+    }
+}
+
+/// A transform, held by the opcode with a default child if `DEFAULT_CHILD`
+/// is set and by the one without otherwise.
+pub(crate) struct Transforms<const DEFAULT_CHILD: bool>;
+
+impl<const DEFAULT_CHILD: bool> Codec<Option<Transform>> for Transforms<DEFAULT_CHILD> {
+    fn read(buf: &mut &[u8]) -> Result<Option<Transform>, DefinitionError> {
+        Ok(Some(Transform::read(buf, DEFAULT_CHILD)?))
+    }
+
+    fn write(value: &Option<Transform>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        match value {
+            Some(transform) => transform.write(buf),
+            None => Ok(()),
+        }
+    }
+
+    fn is_held(value: &Option<Transform>) -> bool {
+        value
+            .as_ref()
+            .is_some_and(|transform| transform.default_child.is_some() == DEFAULT_CHILD)
+    }
+
+    fn is_encodable(value: &Option<Transform>) -> bool {
+        value.as_ref().is_none_or(Transform::has_encodable_children)
+    }
+}
+
+/// A field that is `None` unless its opcode is present.
+impl<T, C: Codec<T>> Codec<Option<T>> for Option<C> {
+    fn read(buf: &mut &[u8]) -> Result<Option<T>, DefinitionError> {
+        Ok(Some(C::read(buf)?))
+    }
+
+    fn write(value: &Option<T>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        match value {
+            Some(value) => C::write(value, buf),
+            None => Ok(()),
+        }
+    }
+
+    fn is_held(value: &Option<T>) -> bool {
+        value.as_ref().is_none_or(C::is_held)
+    }
+
+    fn is_encodable(value: &Option<T>) -> bool {
+        value.as_ref().is_some_and(C::is_encodable)
+    }
+}
+
+/// Implement [`Codec`] for tuples of codecs, holding fields one after the other.
+macro_rules! tuple_codec {
+    ($(($($codec:ident: $type:ident . $index:tt),+)),* $(,)?) => {
+        $(
+            impl<$($codec: Codec<$type>, $type),+> Codec<($($type),+)> for ($($codec),+) {
+                fn read(buf: &mut &[u8]) -> Result<($($type),+), DefinitionError> {
+                    Ok(($($codec::read(buf)?),+))
+                }
+
+                fn write(value: &($($type),+), buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+                    $($codec::write(&value.$index, buf)?;)+
+                    Ok(())
+                }
+
+                fn is_held(value: &($($type),+)) -> bool {
+                    $($codec::is_held(&value.$index))&&+
+                }
+
+                fn is_encodable(value: &($($type),+)) -> bool {
+                    $($codec::is_encodable(&value.$index))&&+
+                }
+            }
+        )*
+    };
+}
+
+tuple_codec! {
+    (A: TA.0, B: TB.1),
+    (A: TA.0, B: TB.1, C: TC.2),
+    (A: TA.0, B: TB.1, C: TC.2, D: TD.3),
+    (A: TA.0, B: TB.1, C: TC.2, D: TD.3, E: TE.4),
+}
+
+/// Check the guard of an `opcodes!` entry, letting a closure infer the type
+/// of the config it is given.
+pub(crate) fn guard<T>(guard: impl Fn(&T) -> bool, config: &T) -> bool {
+    guard(config)
+}
+
+/// Implement [`Opcodes`](super::Opcodes) for a config type from a table of its
+/// opcodes, describing each opcode once for both decoding and encoding.
+///
+/// Each entry is `opcode => field: Codec`, where the field is either a field
+/// name, an element of an array field such as `(actions[0])`, or a tuple of
+/// fields such as `(recolor_find, recolor_replace)` held by the same opcode.
+/// An entry can be limited to a range of cache revisions with
+/// `opcode @ range`, letting an opcode hold different fields in different
+/// revisions. Fields are encoded in table order when they differ from the
+/// type's default and the codec holds them, and an entry ending in
+/// `where guard` is only encoded when the closure `guard` accepts the config.
+/// A field the codec can't encode is an [`Unencodable`] error. A config that
+/// stored a field at its default value therefore loses that opcode when it is
+/// decoded and encoded again.
+///
+/// [`Unencodable`]: super::DefinitionError::Unencodable
+macro_rules! opcodes {
+    (@get $self:ident, ($field:ident [$index:expr])) => {
+        $self.$field[$index]
+    };
+    (@get $self:ident, ($($field:ident),+)) => {
+        ($($self.$field.clone()),+)
+    };
+    (@get $self:ident, $field:ident) => {
+        $self.$field
+    };
+    (@set $self:ident, ($field:ident [$index:expr]), $value:expr) => {
+        $self.$field[$index] = $value
+    };
+    (@set $self:ident, ($($field:ident),+), $value:expr) => {
+        ($($self.$field),+) = $value
+    };
+    (@set $self:ident, $field:ident, $value:expr) => {
+        $self.$field = $value
+    };
+    ($type:ty {
+        $(
+            $opcode:literal $(@ $revisions:expr)? => $field:tt: $codec:ty $(where $guard:expr)?
+        ),* $(,)?
+    }) => {
+        impl $crate::definitions::Opcodes for $type {
+            fn decode_opcode(
+                &mut self,
+                opcode: u8,
+                buf: &mut &[u8],
+                revision: $crate::revision::Revision,
+            ) -> Result<bool, $crate::definitions::DefinitionError> {
+                let _ = revision;
+                $(
+                    if opcode == $opcode
+                        $(&& ::std::ops::RangeBounds::contains(&($revisions), &revision))?
+                    {
+                        $crate::definitions::codec::opcodes!(
+                            @set self,
+                            $field,
+                            <$codec as $crate::definitions::codec::Codec<_>>::read(buf)?
+                        );
+                        return Ok(true);
+                    }
+                )*
+                Ok(false)
+            }
+
+            fn encode_opcodes(
+                &self,
+                buf: &mut Vec<u8>,
+                revision: $crate::revision::Revision,
+            ) -> Result<(), $crate::definitions::DefinitionError> {
+                let _ = revision;
+                let default = <Self as Default>::default();
+                $(
+                    if true
+                        $(&& ::std::ops::RangeBounds::contains(&($revisions), &revision))?
+                        $(&& $crate::definitions::codec::guard($guard, self))?
+                    {
+                        let value = &$crate::definitions::codec::opcodes!(@get self, $field);
+                        if *value != $crate::definitions::codec::opcodes!(@get default, $field)
+                            && <$codec as $crate::definitions::codec::Codec<_>>::is_held(value)
+                        {
+                            if !<$codec as $crate::definitions::codec::Codec<_>>::is_encodable(
+                                value,
+                            ) {
+                                return Err($crate::definitions::DefinitionError::Unencodable {
+                                    opcode: $opcode,
+                                    revision,
+                                });
+                            }
+                            ::osrs_bytes::WriteExt::write_u8(buf, $opcode)?;
+                            <$codec as $crate::definitions::codec::Codec<_>>::write(value, buf)?;
+                        }
+                    }
+                )*
+                Ok(())
+            }
+        }
+    };
+}
+
+pub(crate) use opcodes;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        definitions::{Definition, Opcodes},
+        revision::Revision,
+    };
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Config {
+        id: u32,
+        size: u16,
+        name: Option<String>,
+        hidden: bool,
+        range: (u16, u8),
+        actions: [Option<String>; 2],
+        old: u8,
+        new: u16,
+    }
+
+    opcodes!(Config {
+        1 => size: U16,
+        2 => name: Option<Cp1252>,
+        3 => hidden: Set<true>,
+        4 => range: (U16, U8),
+        10 => (actions[0]): Option<Cp1252>,
+        11 => (actions[1]): Option<Cp1252>,
+        20 @ ..Revision(200) => old: U8,
+        20 @ Revision(200).. => new: U16,
+    });
+
+    impl Definition for Config {
+        const GROUP: u32 = 0;
+
+        fn with_id(id: u32) -> Self {
+            Config {
+                id,
+                ..Default::default()
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let buf = [
+            1, 0, 28, 3, 4, 0, 5, 6, 11, b'U', b's', b'e', 0, 20, 1, 2, 0,
+        ];
+        let config = Config::decode(7, &buf).unwrap();
+        assert_eq!(
+            Config {
+                id: 7,
+                size: 28,
+                hidden: true,
+                range: (5, 6),
+                actions: [None, Some("Use".into())],
+                new: 0x102,
+                ..Default::default()
+            },
+            config
+        );
+        assert_eq!(buf.to_vec(), config.encode().unwrap());
+    }
+
+    #[test]
+    fn test_revisions() {
+        let config = Config::decode_revision(0, &[20, 9, 0], Revision(190)).unwrap();
+        assert_eq!((9, 0), (config.old, config.new));
+        assert_eq!(
+            vec![20, 9, 0],
+            config.encode_revision(Revision(190)).unwrap()
+        );
+        // Fields of other revisions aren't encoded
+        assert_eq!(vec![0], config.encode_revision(Revision(200)).unwrap());
+
+        let mut buf = Vec::new();
+        Config::default()
+            .encode_opcodes(&mut buf, Revision(200))
+            .unwrap();
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_opcode() {
+        assert!(matches!(
+            Config::decode(0, &[1, 0, 1, 3, 5, 0]),
+            Err(DefinitionError::UnknownOpcode {
+                opcode: 5,
+                offset: 4
+            })
+        ));
+    }
+}
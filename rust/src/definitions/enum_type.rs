@@ -0,0 +1,167 @@
+use super::codec::{opcodes, Char, Codec, Cp1252, I32};
+use super::{is_string_type, Definition, DefinitionError, ParamValue};
+use crate::buffer::{CacheReadExt, CacheWriteExt};
+use osrs_bytes::{ReadExt, WriteExt};
+
+/// An enum definition from the enum group of the config archive, mapping
+/// integer keys to values of a single type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EnumDefinition {
+    pub id: u32,
+    /// The type char of the keys, such as `i` or `o` for objs
+    pub key_type: Option<char>,
+    /// The type char of the values. `s` values are strings, any other type is
+    /// stored as an int.
+    pub value_type: Option<char>,
+    pub default_string: String,
+    pub default_int: i32,
+    /// The entries in the order they were decoded
+    pub values: Vec<(i32, ParamValue)>,
+}
+
+impl Default for EnumDefinition {
+    fn default() -> Self {
+        EnumDefinition {
+            id: 0,
+            key_type: None,
+            value_type: None,
+            default_string: "null".to_string(),
+            default_int: 0,
+            values: Vec::new(),
+        }
+    }
+}
+
+impl EnumDefinition {
+    /// Get the value of a key, if the enum has an entry for it
+    pub fn get(&self, key: i32) -> Option<&ParamValue> {
+        self.values
+            .iter()
+            .find(|(entry_key, _)| *entry_key == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Get the value of a key, falling back to the default as the client does
+    pub fn value(&self, key: i32) -> ParamValue {
+        self.get(key)
+            .cloned()
+            .unwrap_or_else(|| self.default_value())
+    }
+
+    /// Get the default value for the enum's value type
+    pub fn default_value(&self) -> ParamValue {
+        if self.value_type.is_some_and(is_string_type) {
+            ParamValue::String(self.default_string.clone())
+        } else {
+            ParamValue::Int(self.default_int)
+        }
+    }
+}
+
+/// The entries of an enum, with string values if `STRINGS` is set and int
+/// values otherwise. The opcode is picked by the type of the first value.
+struct EnumValues<const STRINGS: bool>;
+
+impl<const STRINGS: bool> Codec<Vec<(i32, ParamValue)>> for EnumValues<STRINGS> {
+    fn read(buf: &mut &[u8]) -> Result<Vec<(i32, ParamValue)>, DefinitionError> {
+        let len = buf.read_u16()?;
+        let mut values = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            let key = buf.read_i32()?;
+            let value = if STRINGS {
+                ParamValue::String(buf.read_cp1252_string()?)
+            } else {
+                ParamValue::Int(buf.read_i32()?)
+            };
+            values.push((key, value));
+        }
+        Ok(values)
+    }
+
+    fn write(value: &Vec<(i32, ParamValue)>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        buf.write_u16(value.len() as u16)?;
+        for (key, value) in value {
+            buf.write_i32(*key)?;
+            match value {
+                ParamValue::String(value) if STRINGS => buf.write_cp1252_string(value)?,
+                ParamValue::Int(value) if !STRINGS => buf.write_i32(*value)?,
+                _ => return Err(DefinitionError::MixedValueTypes),
+            }
+        }
+        Ok(())
+    }
+
+    fn is_held(value: &Vec<(i32, ParamValue)>) -> bool {
+        matches!(value.first(), Some((_, ParamValue::String(_)))) == STRINGS
+    }
+}
+
+opcodes!(EnumDefinition {
+    1 => key_type: Option<Char>,
+    2 => value_type: Option<Char>,
+    3 => default_string: Cp1252,
+    4 => default_int: I32,
+    5 => values: EnumValues<true>,
+    6 => values: EnumValues<false>,
+});
+
+impl Definition for EnumDefinition {
+    const GROUP: u32 = 8;
+
+    fn with_id(id: u32) -> Self {
+        EnumDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<EnumDefinition>("tests/data/cache/cache-config");
+
+        let mixed = EnumDefinition {
+            values: vec![(0, ParamValue::Int(1)), (1, ParamValue::String("a".into()))],
+            ..Default::default()
+        };
+        assert!(matches!(
+            mixed.encode(),
+            Err(DefinitionError::MixedValueTypes)
+        ));
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+
+        let tiers = cache.enum_definition(0).unwrap();
+        assert_eq!((Some('i'), Some('s')), (tiers.key_type, tiers.value_type));
+        assert_eq!(3, tiers.values.len());
+        assert_eq!(Some(&ParamValue::String("Iron".into())), tiers.get(2));
+        assert_eq!(ParamValue::String("None".into()), tiers.value(3));
+
+        let objs = cache.enum_definition(1).unwrap();
+        assert_eq!(ParamValue::Int(995), objs.value(1));
+        assert_eq!(ParamValue::Int(-1), objs.value(2));
+
+        let empty = cache.enum_definition(2).unwrap();
+        assert!(empty.values.is_empty());
+        assert_eq!(ParamValue::Int(0), empty.value(0));
+    }
+
+    #[test]
+    fn test_enum_value() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+        assert_eq!(
+            ParamValue::String("Steel".into()),
+            cache.enum_value(0, 5).unwrap()
+        );
+        assert_eq!(ParamValue::Int(-1), cache.enum_value(1, 100).unwrap());
+    }
+}
@@ -0,0 +1,117 @@
+use super::codec::{opcodes, FadeNow, OptionalBigSmart, U16, U8};
+use super::Definition;
+
+/// A health bar definition from the healthbar group of the config archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HealthBarDefinition {
+    pub id: u32,
+    pub unknown_1: Option<u16>,
+    pub unknown_2: u8,
+    pub unknown_3: u8,
+    /// The number of client ticks the bar is shown for
+    pub duration: u16,
+    pub unknown_6: Option<u8>,
+    /// The sprite group drawn over the remaining health
+    pub front_sprite: Option<u32>,
+    /// The sprite group drawn over the missing health
+    pub back_sprite: Option<u32>,
+    /// The tick the bar starts fading out at, or `None` to never fade
+    pub fade_start: Option<u16>,
+    /// The width of the bar in pixels, which health is scaled to
+    pub width: u8,
+    /// The padding on each side of the sprites
+    pub width_padding: u8,
+}
+
+impl Default for HealthBarDefinition {
+    fn default() -> Self {
+        HealthBarDefinition {
+            id: 0,
+            unknown_1: None,
+            unknown_2: 255,
+            unknown_3: 255,
+            duration: 70,
+            unknown_6: None,
+            front_sprite: None,
+            back_sprite: None,
+            fade_start: None,
+            width: 30,
+            width_padding: 0,
+        }
+    }
+}
+
+opcodes!(HealthBarDefinition {
+    1 => unknown_1: Option<U16>,
+    2 => unknown_2: U8,
+    3 => unknown_3: U8,
+    4 => fade_start: FadeNow,
+    5 => duration: U16,
+    6 => unknown_6: Option<U8>,
+    7 => front_sprite: OptionalBigSmart,
+    8 => back_sprite: OptionalBigSmart,
+    11 => fade_start: Option<U16> where |bar| bar.fade_start != Some(0),
+    14 => width: U8,
+    15 => width_padding: U8,
+});
+
+impl Definition for HealthBarDefinition {
+    const GROUP: u32 = 33;
+
+    fn with_id(id: u32) -> Self {
+        HealthBarDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_decode_sprites() {
+        // A sprite past 32767 takes the four byte form of the big smart
+        let mut buf = vec![7, 0x08, 0x80];
+        buf.extend([8, 0x80, 0x01, 0x86, 0xa0]);
+        buf.extend([14, 160, 15, 2, 0]);
+
+        let bar = HealthBarDefinition::decode(0, &buf).unwrap();
+        assert_eq!(
+            (Some(2176), Some(100000)),
+            (bar.front_sprite, bar.back_sprite)
+        );
+        assert_eq!((160, 2), (bar.width, bar.width_padding));
+        assert_eq!(buf, bar.encode().unwrap());
+    }
+
+    #[test]
+    fn test_fade_start() {
+        let bar = HealthBarDefinition::decode(0, &[4, 0]).unwrap();
+        assert_eq!(Some(0), bar.fade_start);
+
+        let bar = HealthBarDefinition::decode(0, &[11, 0x00, 0x1e, 0]).unwrap();
+        assert_eq!(Some(30), bar.fade_start);
+        assert_eq!(vec![11, 0x00, 0x1e, 0], bar.encode().unwrap());
+    }
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<HealthBarDefinition>("tests/data/cache/cache-config");
+    }
+
+    #[test]
+    fn test_cache_health_bar() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+        let boss = cache.health_bar(1).unwrap();
+        assert_eq!(
+            (Some(2176), Some(2177)),
+            (boss.front_sprite, boss.back_sprite)
+        );
+        assert_eq!(160, boss.width);
+        assert_eq!(70, cache.health_bar(0).unwrap().duration);
+    }
+}
@@ -0,0 +1,149 @@
+use super::codec::{
+    opcodes, FadeNow, OptionalBigSmart, PrefixedCp1252, Transforms, I16, U16, U24, U8,
+};
+use super::{Definition, Transform};
+
+/// A hitsplat definition from the hitsplat group of the config archive,
+/// describing how a kind of damage is drawn over an entity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HitsplatDefinition {
+    pub id: u32,
+    /// The font group the amount is drawn in
+    pub font: Option<u32>,
+    /// The colour of the amount as `0xRRGGBB`
+    pub text_colour: u32,
+    /// The sprite group drawn before the background
+    pub icon_sprite: Option<u32>,
+    pub left_sprite: Option<u32>,
+    pub middle_sprite: Option<u32>,
+    pub right_sprite: Option<u32>,
+    pub offset_x: i16,
+    /// The text drawn, with `%1` replaced by the amount
+    pub format: String,
+    /// The number of client ticks the hitsplat is shown for
+    pub duration: u16,
+    /// How far the hitsplat moves horizontally while shown
+    pub scroll_offset_x: i16,
+    /// How far the hitsplat moves vertically while shown
+    pub scroll_offset_y: i16,
+    /// The tick the hitsplat starts fading out at, or `None` to never fade
+    pub fade_start: Option<u16>,
+    pub use_damage: Option<u8>,
+    pub transform: Option<Transform>,
+}
+
+impl Default for HitsplatDefinition {
+    fn default() -> Self {
+        HitsplatDefinition {
+            id: 0,
+            font: None,
+            text_colour: 0xffffff,
+            icon_sprite: None,
+            left_sprite: None,
+            middle_sprite: None,
+            right_sprite: None,
+            offset_x: 0,
+            format: String::new(),
+            duration: 70,
+            scroll_offset_x: 0,
+            scroll_offset_y: 0,
+            fade_start: None,
+            use_damage: None,
+            transform: None,
+        }
+    }
+}
+
+opcodes!(HitsplatDefinition {
+    1 => font: OptionalBigSmart,
+    2 => text_colour: U24,
+    3 => icon_sprite: OptionalBigSmart,
+    4 => left_sprite: OptionalBigSmart,
+    5 => middle_sprite: OptionalBigSmart,
+    6 => right_sprite: OptionalBigSmart,
+    7 => offset_x: I16,
+    8 => format: PrefixedCp1252,
+    9 => duration: U16,
+    10 => scroll_offset_x: I16,
+    11 => fade_start: FadeNow,
+    12 => use_damage: Option<U8>,
+    13 => scroll_offset_y: I16,
+    14 => fade_start: Option<U16> where |hitsplat| hitsplat.fade_start != Some(0),
+    17 => transform: Transforms<false>,
+    18 => transform: Transforms<true>,
+});
+
+impl Definition for HitsplatDefinition {
+    const GROUP: u32 = 32;
+
+    fn with_id(id: u32) -> Self {
+        HitsplatDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_varbit_transform() {
+        let mut buf = vec![18];
+        buf.extend([0x01, 0x2c, 0xff, 0xff, 0x00, 0x07]);
+        buf.extend([2, 0x00, 0x03, 0xff, 0xff, 0x00, 0x05]);
+        buf.push(0);
+
+        let hitsplat = HitsplatDefinition::decode(0, &buf).unwrap();
+        assert_eq!(
+            Some(Transform {
+                varbit: Some(300),
+                varp: None,
+                children: vec![Some(3), None, Some(5)],
+                default_child: Some(Some(7)),
+            }),
+            hitsplat.transform
+        );
+        assert_eq!(buf, hitsplat.encode().unwrap());
+
+        // Without a default child the transform moves to opcode 17
+        let mut hitsplat = hitsplat;
+        hitsplat.transform.as_mut().unwrap().default_child = None;
+        let buf = hitsplat.encode().unwrap();
+        assert_eq!(17, buf[0]);
+        assert_eq!(hitsplat, HitsplatDefinition::decode(0, &buf).unwrap());
+    }
+
+    #[test]
+    fn test_fade_start() {
+        // Fading from the first tick has an opcode of its own
+        let mut hitsplat = HitsplatDefinition::with_id(0);
+        hitsplat.fade_start = Some(0);
+        assert_eq!(vec![11, 0], hitsplat.encode().unwrap());
+
+        hitsplat.fade_start = Some(30);
+        assert_eq!(vec![14, 0x00, 0x1e, 0], hitsplat.encode().unwrap());
+    }
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<HitsplatDefinition>("tests/data/cache/cache-config");
+    }
+
+    #[test]
+    fn test_cache_hitsplat() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+        let damage = cache.hitsplat(1).unwrap();
+        assert_eq!((Some(494), 0xffffff), (damage.font, damage.text_colour));
+        assert_eq!(Some(100000), damage.middle_sprite);
+        assert_eq!("%1", damage.format);
+        assert_eq!((Some(0), -15), (damage.fade_start, damage.scroll_offset_y));
+        assert_eq!(
+            Some(1021),
+            cache.hitsplat(2).unwrap().transform.unwrap().varp
+        );
+    }
+}
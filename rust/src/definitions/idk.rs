@@ -0,0 +1,103 @@
+use super::codec::{opcodes, Set, U16List, U16Pairs, U16, U8};
+use super::Definition;
+
+/// An identity kit definition from the idk group of the config archive,
+/// giving the models of a body part players can choose their look from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IdkDefinition {
+    pub id: u32,
+    /// The body part, such as 0 for the head and 1 for the jaw of male
+    /// players, or 7 onwards for female players
+    pub body_part: Option<u8>,
+    pub models: Vec<u16>,
+    /// Whether players can pick the kit when designing their look
+    pub selectable: bool,
+    pub recolor_find: Vec<u16>,
+    pub recolor_replace: Vec<u16>,
+    pub retexture_find: Vec<u16>,
+    pub retexture_replace: Vec<u16>,
+    /// The models shown in chat head dialogues
+    pub chathead_models: [Option<u16>; 5],
+}
+
+impl Default for IdkDefinition {
+    fn default() -> Self {
+        IdkDefinition {
+            id: 0,
+            body_part: None,
+            models: Vec::new(),
+            selectable: true,
+            recolor_find: Vec::new(),
+            recolor_replace: Vec::new(),
+            retexture_find: Vec::new(),
+            retexture_replace: Vec::new(),
+            chathead_models: [None; 5],
+        }
+    }
+}
+
+opcodes!(IdkDefinition {
+    1 => body_part: Option<U8>,
+    2 => models: U16List,
+    3 => selectable: Set<false>,
+    40 => (recolor_find, recolor_replace): U16Pairs,
+    41 => (retexture_find, retexture_replace): U16Pairs,
+    60 => (chathead_models[0]): Option<U16>,
+    61 => (chathead_models[1]): Option<U16>,
+    62 => (chathead_models[2]): Option<U16>,
+    63 => (chathead_models[3]): Option<U16>,
+    64 => (chathead_models[4]): Option<U16>,
+});
+
+impl Definition for IdkDefinition {
+    const GROUP: u32 = 3;
+
+    fn with_id(id: u32) -> Self {
+        IdkDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_chathead_slots() {
+        // Each chat head model has its own opcode, so gaps stay in place
+        let buf = [61, 0x00, 0x3a, 64, 0x00, 0x3b, 0];
+        let kit = IdkDefinition::decode(0, &buf).unwrap();
+        assert_eq!([None, Some(58), None, None, Some(59)], kit.chathead_models);
+        assert_eq!(buf.to_vec(), kit.encode().unwrap());
+    }
+
+    #[test]
+    fn test_head_body_part() {
+        // The head is body part 0, which is kept apart from no body part
+        let mut kit = IdkDefinition::with_id(0);
+        kit.body_part = Some(0);
+        kit.selectable = false;
+        let buf = kit.encode().unwrap();
+        assert_eq!(vec![1, 0, 3, 0], buf);
+        assert_eq!(kit, IdkDefinition::decode(0, &buf).unwrap());
+    }
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<IdkDefinition>("tests/data/cache/cache-config");
+    }
+
+    #[test]
+    fn test_cache_idk() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+        let beard = cache.idk(10).unwrap();
+        assert_eq!(Some(1), beard.body_part);
+        assert_eq!(vec![252, 253], beard.models);
+        assert_eq!([Some(58), None, None, None, None], beard.chathead_models);
+        assert!(!cache.idk(0).unwrap().selectable);
+    }
+}
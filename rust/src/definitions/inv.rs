@@ -0,0 +1,54 @@
+use super::codec::{opcodes, U16};
+use super::Definition;
+
+/// An inventory definition from the inv group of the config archive.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InvDefinition {
+    pub id: u32,
+    /// The number of slots
+    pub size: u16,
+}
+
+opcodes!(InvDefinition {
+    2 => size: U16,
+});
+
+impl Definition for InvDefinition {
+    const GROUP: u32 = 5;
+
+    fn with_id(id: u32) -> Self {
+        InvDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_decode_size() {
+        let buf = [2, 0x03, 0x20, 0];
+        let bank = InvDefinition::decode(95, &buf).unwrap();
+        assert_eq!(800, bank.size);
+        assert_eq!(buf.to_vec(), bank.encode().unwrap());
+
+        // An inventory without slots has no opcodes
+        assert_eq!(vec![0], InvDefinition::with_id(0).encode().unwrap());
+    }
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<InvDefinition>("tests/data/cache/cache-config");
+    }
+
+    #[test]
+    fn test_cache_inv() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+        assert_eq!(28, cache.inv(93).unwrap().size);
+    }
+}
@@ -0,0 +1,370 @@
+use super::codec::{opcodes, Codec, Cp1252, ParamList, Set, U16Pairs, I16, I32, I8, U16, U8};
+use super::{Definition, DefinitionError, Params};
+use crate::buffer::{CacheReadExt, CacheWriteExt};
+
+const DEFAULT_GROUND_OPTIONS: [Option<&str>; 5] = [None, None, Some("Take"), None, None];
+const DEFAULT_INTERFACE_OPTIONS: [Option<&str>; 5] = [None, None, None, None, Some("Drop")];
+/// The ground option the client treats as absent, used to remove a default option.
+const HIDDEN_OPTION: &str = "Hidden";
+
+/// An item (obj) definition from the obj group of the config archive.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ItemDefinition {
+    pub id: u32,
+    pub inventory_model: u16,
+    pub name: String,
+    pub examine: Option<String>,
+    pub zoom2d: u16,
+    pub xan2d: u16,
+    pub yan2d: u16,
+    pub zan2d: u16,
+    pub offset_x2d: i16,
+    pub offset_y2d: i16,
+    pub unknown_9: Option<String>,
+    pub stackable: bool,
+    pub cost: i32,
+    pub wear_pos1: i8,
+    pub wear_pos2: i8,
+    pub wear_pos3: i8,
+    pub members: bool,
+    pub male_model0: Option<u16>,
+    pub male_model1: Option<u16>,
+    pub male_model2: Option<u16>,
+    pub male_offset: u8,
+    pub female_model0: Option<u16>,
+    pub female_model1: Option<u16>,
+    pub female_model2: Option<u16>,
+    pub female_offset: u8,
+    pub male_head_model: Option<u16>,
+    pub male_head_model2: Option<u16>,
+    pub female_head_model: Option<u16>,
+    pub female_head_model2: Option<u16>,
+    /// The right click options when the item is on the ground. `Hidden` hides an
+    /// option, and a default option set to `None` is encoded as `Hidden`.
+    pub options: [Option<String>; 5],
+    /// The right click options when the item is in an inventory. The client has
+    /// no hidden value for these, so the default `Drop` can be renamed but not removed.
+    pub interface_options: [Option<String>; 5],
+    pub recolor_find: Vec<u16>,
+    pub recolor_replace: Vec<u16>,
+    pub retexture_find: Vec<u16>,
+    pub retexture_replace: Vec<u16>,
+    pub shift_click_drop_index: i8,
+    pub tradeable: bool,
+    pub weight: i16,
+    pub category: Option<u16>,
+    pub noted_id: Option<u16>,
+    pub noted_template: Option<u16>,
+    /// The item shown for stacks of at least a given size, as `(item, count)` pairs
+    pub count_objs: [(u16, u16); 10],
+    pub resize_x: u16,
+    pub resize_y: u16,
+    pub resize_z: u16,
+    pub ambient: i8,
+    pub contrast: i8,
+    pub team: u8,
+    pub bought_id: Option<u16>,
+    pub bought_template: Option<u16>,
+    pub placeholder_id: Option<u16>,
+    pub placeholder_template: Option<u16>,
+    pub params: Params,
+}
+
+impl Default for ItemDefinition {
+    fn default() -> Self {
+        ItemDefinition {
+            id: 0,
+            inventory_model: 0,
+            name: String::from("null"),
+            examine: None,
+            zoom2d: 2000,
+            xan2d: 0,
+            yan2d: 0,
+            zan2d: 0,
+            offset_x2d: 0,
+            offset_y2d: 0,
+            unknown_9: None,
+            stackable: false,
+            cost: 1,
+            wear_pos1: -1,
+            wear_pos2: -1,
+            wear_pos3: -1,
+            members: false,
+            male_model0: None,
+            male_model1: None,
+            male_model2: None,
+            male_offset: 0,
+            female_model0: None,
+            female_model1: None,
+            female_model2: None,
+            female_offset: 0,
+            male_head_model: None,
+            male_head_model2: None,
+            female_head_model: None,
+            female_head_model2: None,
+            options: DEFAULT_GROUND_OPTIONS.map(|option| option.map(String::from)),
+            interface_options: DEFAULT_INTERFACE_OPTIONS.map(|option| option.map(String::from)),
+            recolor_find: Vec::new(),
+            recolor_replace: Vec::new(),
+            retexture_find: Vec::new(),
+            retexture_replace: Vec::new(),
+            shift_click_drop_index: -2,
+            tradeable: false,
+            weight: 0,
+            category: None,
+            noted_id: None,
+            noted_template: None,
+            count_objs: [(0, 0); 10],
+            resize_x: 128,
+            resize_y: 128,
+            resize_z: 128,
+            ambient: 0,
+            contrast: 0,
+            team: 0,
+            bought_id: None,
+            bought_template: None,
+            placeholder_id: None,
+            placeholder_template: None,
+            params: Params::new(),
+        }
+    }
+}
+
+impl ItemDefinition {
+    /// Check whether this item is the noted version of another item
+    pub fn is_noted(&self) -> bool {
+        self.noted_template.is_some()
+    }
+
+    /// Check whether this item is the bank placeholder of another item
+    pub fn is_placeholder(&self) -> bool {
+        self.placeholder_template.is_some()
+    }
+}
+
+/// A ground option, where a default option set to `None` is written as
+/// `Hidden`.
+struct GroundOption;
+
+impl Codec<Option<String>> for GroundOption {
+    fn read(buf: &mut &[u8]) -> Result<Option<String>, DefinitionError> {
+        Ok(Some(buf.read_cp1252_string()?))
+    }
+
+    fn write(value: &Option<String>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(buf.write_cp1252_string(value.as_deref().unwrap_or(HIDDEN_OPTION))?)
+    }
+}
+
+opcodes!(ItemDefinition {
+    1 => inventory_model: U16,
+    2 => name: Cp1252,
+    3 => examine: Option<Cp1252>,
+    4 => zoom2d: U16,
+    5 => xan2d: U16,
+    6 => yan2d: U16,
+    7 => offset_x2d: I16,
+    8 => offset_y2d: I16,
+    9 => unknown_9: Option<Cp1252>,
+    11 => stackable: Set<true>,
+    12 => cost: I32,
+    13 => wear_pos1: I8,
+    14 => wear_pos2: I8,
+    16 => members: Set<true>,
+    23 => (male_model0, male_offset): (Option<U16>, U8),
+    24 => male_model1: Option<U16>,
+    25 => (female_model0, female_offset): (Option<U16>, U8),
+    26 => female_model1: Option<U16>,
+    27 => wear_pos3: I8,
+    30 => (options[0]): GroundOption,
+    31 => (options[1]): GroundOption,
+    32 => (options[2]): GroundOption,
+    33 => (options[3]): GroundOption,
+    34 => (options[4]): GroundOption,
+    35 => (interface_options[0]): Option<Cp1252>,
+    36 => (interface_options[1]): Option<Cp1252>,
+    37 => (interface_options[2]): Option<Cp1252>,
+    38 => (interface_options[3]): Option<Cp1252>,
+    39 => (interface_options[4]): Option<Cp1252>,
+    40 => (recolor_find, recolor_replace): U16Pairs,
+    41 => (retexture_find, retexture_replace): U16Pairs,
+    42 => shift_click_drop_index: I8,
+    65 => tradeable: Set<true>,
+    75 => weight: I16,
+    78 => male_model2: Option<U16>,
+    79 => female_model2: Option<U16>,
+    90 => male_head_model: Option<U16>,
+    91 => female_head_model: Option<U16>,
+    92 => male_head_model2: Option<U16>,
+    93 => female_head_model2: Option<U16>,
+    94 => category: Option<U16>,
+    95 => zan2d: U16,
+    97 => noted_id: Option<U16>,
+    98 => noted_template: Option<U16>,
+    100 => (count_objs[0]): (U16, U16),
+    101 => (count_objs[1]): (U16, U16),
+    102 => (count_objs[2]): (U16, U16),
+    103 => (count_objs[3]): (U16, U16),
+    104 => (count_objs[4]): (U16, U16),
+    105 => (count_objs[5]): (U16, U16),
+    106 => (count_objs[6]): (U16, U16),
+    107 => (count_objs[7]): (U16, U16),
+    108 => (count_objs[8]): (U16, U16),
+    109 => (count_objs[9]): (U16, U16),
+    110 => resize_x: U16,
+    111 => resize_y: U16,
+    112 => resize_z: U16,
+    113 => ambient: I8,
+    114 => contrast: I8,
+    115 => team: U8,
+    139 => bought_id: Option<U16>,
+    140 => bought_template: Option<U16>,
+    148 => placeholder_id: Option<U16>,
+    149 => placeholder_template: Option<U16>,
+    249 => params: ParamList,
+});
+
+impl Definition for ItemDefinition {
+    const GROUP: u32 = 10;
+
+    fn with_id(id: u32) -> Self {
+        ItemDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cache::CacheError,
+        definitions::{assert_round_trip, ParamValue},
+        Cache,
+    };
+
+    #[test]
+    fn test_decode_blue_partyhat() {
+        let item = ItemDefinition::decode(1042, &blue_partyhat()).unwrap();
+
+        assert_eq!(1042, item.id);
+        assert_eq!("Blue partyhat", item.name);
+        assert_eq!(2635, item.inventory_model);
+        assert_eq!(Some(187), item.male_model0);
+        assert_eq!(Some(363), item.female_model0);
+        assert_eq!(vec![926], item.recolor_find);
+        assert_eq!(vec![43955], item.recolor_replace);
+        assert_eq!(Some("Wear"), item.interface_options[1].as_deref());
+        assert_eq!(Some("Drop"), item.interface_options[4].as_deref());
+        assert_eq!(Some("Take"), item.options[2].as_deref());
+        assert_eq!(Some(1), item.params.get_int(1234));
+        assert_eq!(Some("hat"), item.params.get_string(1235));
+        assert!(item.tradeable);
+        assert!(!item.stackable);
+        assert!(!item.is_noted());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let buf = blue_partyhat();
+        let item = ItemDefinition::decode(1042, &buf).unwrap();
+
+        assert_eq!(buf, item.encode().unwrap());
+    }
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<ItemDefinition>("tests/data/cache/cache-config");
+    }
+
+    #[test]
+    fn test_cache_item() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+
+        let noted = cache.item(1043).unwrap();
+        assert!(noted.is_noted());
+        assert!(noted.stackable);
+        assert_eq!(Some(1042), noted.noted_id);
+
+        let coins = cache.item(995).unwrap();
+        assert_eq!((996, 2), coins.count_objs[0]);
+        assert_eq!(Some(20594), coins.placeholder_id);
+
+        // Ids past the last file id don't wrap onto another item
+        assert!(matches!(
+            cache.item(65536 + 1043),
+            Err(CacheError::FileIdOutOfRange(66579))
+        ));
+    }
+
+    #[test]
+    fn test_explicit_default_dropped() {
+        let buf = [4, 0x07, 0xd0, 0];
+        let item = ItemDefinition::decode(0, &buf).unwrap();
+        assert_eq!(ItemDefinition::default(), item);
+        assert_eq!(vec![0], item.encode().unwrap());
+    }
+
+    #[test]
+    fn test_unknown_opcode() {
+        assert!(matches!(
+            ItemDefinition::decode(0, &[255, 0]),
+            Err(DefinitionError::UnknownOpcode {
+                opcode: 255,
+                offset: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn test_encode_default() {
+        let mut item = ItemDefinition::default();
+        item.params.insert(1, ParamValue::Int(5));
+
+        let decoded = ItemDefinition::decode(0, &item.encode().unwrap()).unwrap();
+        assert_eq!(item, decoded);
+    }
+
+    #[test]
+    fn test_remove_default_option() {
+        let mut item = ItemDefinition::default();
+        item.options[2] = None;
+
+        let buf = item.encode().unwrap();
+        assert_eq!(b"\x20Hidden\0\0".to_vec(), buf);
+        let decoded = ItemDefinition::decode(0, &buf).unwrap();
+        assert_eq!(Some("Hidden"), decoded.options[2].as_deref());
+
+        let mut item = ItemDefinition::default();
+        item.interface_options[4] = None;
+        assert!(matches!(
+            item.encode(),
+            Err(DefinitionError::Unencodable { opcode: 39, .. })
+        ));
+    }
+
+    fn blue_partyhat() -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend([1, 0x0A, 0x4B]);
+        buf.push(2);
+        buf.extend(b"Blue partyhat\0");
+        buf.extend([4, 0x01, 0xB8]);
+        buf.extend([5, 0x00, 0x78]);
+        buf.extend([6, 0x07, 0xC6]);
+        buf.extend([7, 0xFF, 0xFE]);
+        buf.extend([13, 0]);
+        buf.extend([23, 0x00, 0xBB, 0x00]);
+        buf.extend([25, 0x01, 0x6B, 0x00]);
+        buf.push(36);
+        buf.extend(b"Wear\0");
+        buf.extend([40, 1, 0x03, 0x9E, 0xAB, 0xB3]);
+        buf.push(65);
+        buf.extend([75, 0x00, 0x01]);
+        buf.extend([249, 2, 0, 0x00, 0x04, 0xD2, 0, 0, 0, 1, 1, 0x00, 0x04, 0xD3]);
+        buf.extend(b"hat\0");
+        buf.push(0);
+        buf
+    }
+}
@@ -0,0 +1,436 @@
+use super::codec::{
+    opcodes, Codec, Cp1252, NullableU16, ParamList, Set, Transforms, U16List, U16Pairs, I8, U16, U8,
+};
+use super::{Definition, DefinitionError, Params, Transform};
+use crate::{
+    buffer::{CacheReadExt, CacheWriteExt},
+    revision::Revision,
+};
+use osrs_bytes::{ReadExt, WriteExt};
+
+/// An icon drawn above an npc's head, as a sprite group and the index of the
+/// frame within it. Caches before [`Revision::NPC_HEAD_ICON_GROUPS`] only hold
+/// the frame, and the group is -1 for the default head icons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeadIcon {
+    pub group: i32,
+    pub frame: i32,
+}
+
+/// An npc definition from the npc group of the config archive.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct NpcDefinition {
+    pub id: u32,
+    pub models: Vec<u16>,
+    pub name: String,
+    pub size: u8,
+    pub standing_animation: Option<u16>,
+    pub walk_animation: Option<u16>,
+    pub idle_rotate_left_animation: Option<u16>,
+    pub idle_rotate_right_animation: Option<u16>,
+    pub rotate180_animation: Option<u16>,
+    pub rotate_left_animation: Option<u16>,
+    pub rotate_right_animation: Option<u16>,
+    pub run_animation: Option<u16>,
+    pub run_rotate180_animation: Option<u16>,
+    pub run_rotate_left_animation: Option<u16>,
+    pub run_rotate_right_animation: Option<u16>,
+    pub crawl_animation: Option<u16>,
+    pub crawl_rotate180_animation: Option<u16>,
+    pub crawl_rotate_left_animation: Option<u16>,
+    pub crawl_rotate_right_animation: Option<u16>,
+    pub category: Option<u16>,
+    /// The right click options. `Hidden` hides an option.
+    pub actions: [Option<String>; 5],
+    pub recolor_find: Vec<u16>,
+    pub recolor_replace: Vec<u16>,
+    pub retexture_find: Vec<u16>,
+    pub retexture_replace: Vec<u16>,
+    pub chathead_models: Vec<u16>,
+    /// Attack, defence, strength, hitpoints, ranged and magic levels
+    pub stats: [u16; 6],
+    pub minimap_visible: bool,
+    pub combat_level: Option<u16>,
+    pub width_scale: u16,
+    pub height_scale: u16,
+    pub render_priority: bool,
+    pub ambient: i8,
+    pub contrast: i8,
+    /// The head icons, `None` for slots without an icon
+    pub head_icons: Vec<Option<HeadIcon>>,
+    pub rotation_speed: u16,
+    pub transform: Option<Transform>,
+    pub interactable: bool,
+    pub rotation_flag: bool,
+    pub pet: bool,
+    pub follower: bool,
+    pub low_priority_follower_ops: bool,
+    pub height: Option<u16>,
+    pub footprint_size: Option<u16>,
+    pub unknown_129: bool,
+    pub idle_animation_restart: bool,
+    pub can_hide_for_overlap: bool,
+    pub overlap_tint_hsl: Option<u16>,
+    pub zbuf: bool,
+    pub params: Params,
+}
+
+impl Default for NpcDefinition {
+    fn default() -> Self {
+        NpcDefinition {
+            id: 0,
+            models: Vec::new(),
+            name: String::from("null"),
+            size: 1,
+            standing_animation: None,
+            walk_animation: None,
+            idle_rotate_left_animation: None,
+            idle_rotate_right_animation: None,
+            rotate180_animation: None,
+            rotate_left_animation: None,
+            rotate_right_animation: None,
+            run_animation: None,
+            run_rotate180_animation: None,
+            run_rotate_left_animation: None,
+            run_rotate_right_animation: None,
+            crawl_animation: None,
+            crawl_rotate180_animation: None,
+            crawl_rotate_left_animation: None,
+            crawl_rotate_right_animation: None,
+            category: None,
+            actions: Default::default(),
+            recolor_find: Vec::new(),
+            recolor_replace: Vec::new(),
+            retexture_find: Vec::new(),
+            retexture_replace: Vec::new(),
+            chathead_models: Vec::new(),
+            stats: [1; 6],
+            minimap_visible: true,
+            combat_level: None,
+            width_scale: 128,
+            height_scale: 128,
+            render_priority: false,
+            ambient: 0,
+            contrast: 0,
+            head_icons: Vec::new(),
+            rotation_speed: 32,
+            transform: None,
+            interactable: true,
+            rotation_flag: true,
+            pet: false,
+            follower: false,
+            low_priority_follower_ops: false,
+            height: None,
+            footprint_size: None,
+            unknown_129: false,
+            idle_animation_restart: false,
+            can_hide_for_overlap: false,
+            overlap_tint_hsl: None,
+            zbuf: true,
+            params: Params::new(),
+        }
+    }
+}
+
+impl NpcDefinition {
+    /// Check whether walking uses turn animations, which are held together
+    /// with the walk animation by opcode 17
+    fn has_turn_animations(&self) -> bool {
+        [
+            self.rotate180_animation,
+            self.rotate_left_animation,
+            self.rotate_right_animation,
+        ]
+        .iter()
+        .any(Option::is_some)
+    }
+
+    /// Check whether running uses turn animations, held by opcode 115
+    fn has_run_turn_animations(&self) -> bool {
+        [
+            self.run_rotate180_animation,
+            self.run_rotate_left_animation,
+            self.run_rotate_right_animation,
+        ]
+        .iter()
+        .any(Option::is_some)
+    }
+
+    /// Check whether crawling uses turn animations, held by opcode 117
+    fn has_crawl_turn_animations(&self) -> bool {
+        [
+            self.crawl_rotate180_animation,
+            self.crawl_rotate_left_animation,
+            self.crawl_rotate_right_animation,
+        ]
+        .iter()
+        .any(Option::is_some)
+    }
+}
+
+/// A single head icon frame, as held by opcode 102 before
+/// [`Revision::NPC_HEAD_ICON_GROUPS`].
+struct LegacyHeadIcons;
+
+impl Codec<Vec<Option<HeadIcon>>> for LegacyHeadIcons {
+    fn read(buf: &mut &[u8]) -> Result<Vec<Option<HeadIcon>>, DefinitionError> {
+        Ok(vec![Some(HeadIcon {
+            group: -1,
+            frame: buf.read_u16()? as i32,
+        })])
+    }
+
+    fn write(value: &Vec<Option<HeadIcon>>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        match value.as_slice() {
+            [Some(icon)] => Ok(buf.write_u16(icon.frame as u16)?),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_encodable(value: &Vec<Option<HeadIcon>>) -> bool {
+        matches!(value.as_slice(), [Some(HeadIcon { group: -1, .. })])
+    }
+}
+
+/// A bit set of head icon slots followed by the group and frame of each set
+/// slot.
+struct HeadIcons;
+
+impl Codec<Vec<Option<HeadIcon>>> for HeadIcons {
+    fn read(buf: &mut &[u8]) -> Result<Vec<Option<HeadIcon>>, DefinitionError> {
+        let mut bits = buf.read_u8()?;
+        let mut icons = Vec::new();
+        while bits != 0 {
+            icons.push(if bits & 1 == 0 {
+                None
+            } else {
+                Some(HeadIcon {
+                    group: buf.read_big_smart()?,
+                    frame: buf.read_u16_smart_minus_one()?,
+                })
+            });
+            bits >>= 1;
+        }
+        Ok(icons)
+    }
+
+    fn write(value: &Vec<Option<HeadIcon>>, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        let bits = value
+            .iter()
+            .enumerate()
+            .filter(|(_, icon)| icon.is_some())
+            .fold(0, |bits, (i, _)| bits | (1 << i));
+        buf.write_u8(bits)?;
+        for icon in value.iter().flatten() {
+            buf.write_big_smart(icon.group)?;
+            buf.write_u16_smart_minus_one(icon.frame)?;
+        }
+        Ok(())
+    }
+}
+
+opcodes!(NpcDefinition {
+    1 => models: U16List,
+    2 => name: Cp1252,
+    12 => size: U8,
+    13 => standing_animation: Option<U16>,
+    14 => walk_animation: Option<U16> where |npc| !npc.has_turn_animations(),
+    15 => idle_rotate_left_animation: Option<U16>,
+    16 => idle_rotate_right_animation: Option<U16>,
+    17 => (
+        walk_animation,
+        rotate180_animation,
+        rotate_left_animation,
+        rotate_right_animation
+    ): (NullableU16, NullableU16, NullableU16, NullableU16)
+        where NpcDefinition::has_turn_animations,
+    18 => category: Option<U16>,
+    30 => (actions[0]): Option<Cp1252>,
+    31 => (actions[1]): Option<Cp1252>,
+    32 => (actions[2]): Option<Cp1252>,
+    33 => (actions[3]): Option<Cp1252>,
+    34 => (actions[4]): Option<Cp1252>,
+    40 => (recolor_find, recolor_replace): U16Pairs,
+    41 => (retexture_find, retexture_replace): U16Pairs,
+    60 => chathead_models: U16List,
+    74 => (stats[0]): U16,
+    75 => (stats[1]): U16,
+    76 => (stats[2]): U16,
+    77 => (stats[3]): U16,
+    78 => (stats[4]): U16,
+    79 => (stats[5]): U16,
+    93 => minimap_visible: Set<false>,
+    95 => combat_level: Option<U16>,
+    97 => width_scale: U16,
+    98 => height_scale: U16,
+    99 => render_priority: Set<true>,
+    100 => ambient: I8,
+    101 => contrast: I8,
+    102 @ ..Revision::NPC_HEAD_ICON_GROUPS => head_icons: LegacyHeadIcons,
+    102 @ Revision::NPC_HEAD_ICON_GROUPS.. => head_icons: HeadIcons,
+    103 => rotation_speed: U16,
+    106 => transform: Transforms<false>,
+    107 => interactable: Set<false>,
+    109 => rotation_flag: Set<false>,
+    111 => pet: Set<true>,
+    114 => run_animation: Option<U16> where |npc| !npc.has_run_turn_animations(),
+    115 => (
+        run_animation,
+        run_rotate180_animation,
+        run_rotate_left_animation,
+        run_rotate_right_animation
+    ): (NullableU16, NullableU16, NullableU16, NullableU16)
+        where NpcDefinition::has_run_turn_animations,
+    116 => crawl_animation: Option<U16> where |npc| !npc.has_crawl_turn_animations(),
+    117 => (
+        crawl_animation,
+        crawl_rotate180_animation,
+        crawl_rotate_left_animation,
+        crawl_rotate_right_animation
+    ): (NullableU16, NullableU16, NullableU16, NullableU16)
+        where NpcDefinition::has_crawl_turn_animations,
+    118 => transform: Transforms<true>,
+    122 => follower: Set<true>,
+    123 => low_priority_follower_ops: Set<true>,
+    124 => height: Option<U16>,
+    126 => footprint_size: Option<U16>,
+    129 => unknown_129: Set<true>,
+    130 => idle_animation_restart: Set<true>,
+    145 => can_hide_for_overlap: Set<true>,
+    146 => overlap_tint_hsl: Option<U16>,
+    147 => zbuf: Set<false>,
+    249 => params: ParamList,
+});
+
+impl Definition for NpcDefinition {
+    const GROUP: u32 = 9;
+
+    fn with_id(id: u32) -> Self {
+        NpcDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<NpcDefinition>("tests/data/cache/cache-config");
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+
+        let guard = cache.npc(3010).unwrap();
+        assert_eq!("Guard", guard.name);
+        assert_eq!(Some(21), guard.combat_level);
+        assert_eq!(Some("Attack"), guard.actions[2].as_deref());
+        assert_eq!([18, 14, 1, 22, 1, 1], guard.stats);
+        assert_eq!(Some(822), guard.rotate_right_animation);
+        assert_eq!(
+            vec![
+                Some(HeadIcon {
+                    group: 440,
+                    frame: 2
+                }),
+                None,
+                Some(HeadIcon {
+                    group: 100000,
+                    frame: 0
+                }),
+            ],
+            guard.head_icons
+        );
+        assert_eq!(Some("guard"), guard.params.get_string(20));
+
+        let shapeshifter = cache.npc(7).unwrap();
+        assert_eq!(
+            Some(Transform {
+                varbit: Some(1234),
+                varp: None,
+                children: vec![Some(0), None, Some(3010)],
+                default_child: None,
+            }),
+            shapeshifter.transform
+        );
+        assert!(!shapeshifter.interactable);
+    }
+
+    #[test]
+    fn test_hidden_default_child() {
+        let buf = [118, 0xff, 0xff, 0, 99, 0xff, 0xff, 0, 0, 7, 0];
+        let npc = NpcDefinition::decode(0, &buf).unwrap();
+        assert_eq!(
+            Some(Transform {
+                varbit: None,
+                varp: Some(99),
+                children: vec![Some(7)],
+                default_child: Some(None),
+            }),
+            npc.transform
+        );
+        assert_eq!(buf.to_vec(), npc.encode().unwrap());
+    }
+
+    #[test]
+    fn test_transform_child_count() {
+        let mut npc = NpcDefinition {
+            transform: Some(Transform {
+                varp: Some(99),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            npc.encode(),
+            Err(DefinitionError::Unencodable { opcode: 106, .. })
+        ));
+
+        let transform = npc.transform.as_mut().unwrap();
+        transform.children = vec![Some(7); 256];
+        let decoded = NpcDefinition::decode(0, &npc.encode().unwrap()).unwrap();
+        assert_eq!(npc, decoded);
+
+        npc.transform.as_mut().unwrap().children.push(None);
+        assert!(matches!(
+            npc.encode(),
+            Err(DefinitionError::Unencodable { opcode: 106, .. })
+        ));
+    }
+
+    #[test]
+    fn test_legacy_head_icon() {
+        let buf = [102, 0, 5, 0];
+        let npc = NpcDefinition::decode_revision(0, &buf, Revision::LEGACY).unwrap();
+        assert_eq!(
+            vec![Some(HeadIcon {
+                group: -1,
+                frame: 5
+            })],
+            npc.head_icons
+        );
+        assert_eq!(buf.to_vec(), npc.encode_revision(Revision::LEGACY).unwrap());
+
+        let npc = NpcDefinition {
+            head_icons: vec![
+                None,
+                Some(HeadIcon {
+                    group: 440,
+                    frame: 1,
+                }),
+            ],
+            ..Default::default()
+        };
+        assert!(matches!(
+            npc.encode_revision(Revision::LEGACY),
+            Err(DefinitionError::Unencodable { opcode: 102, .. })
+        ));
+    }
+}
@@ -0,0 +1,409 @@
+use super::codec::{
+    opcodes, Codec, Const, Cp1252, OptionalU16, ParamList, Set, Transforms, U16List, U16Pairs, I16,
+    I8, U16, U8,
+};
+use super::{read_u16_list, write_u16_list, Definition, DefinitionError, Params, Transform};
+use crate::revision::Revision;
+use osrs_bytes::{ReadExt, WriteExt};
+
+/// The interact type of a loc that doesn't block movement.
+pub const INTERACT_TYPE_NONE: u8 = 0;
+/// The interact type of a loc that blocks movement but not interaction.
+pub const INTERACT_TYPE_BLOCKING: u8 = 1;
+/// The default interact type, blocking movement and interaction.
+pub const INTERACT_TYPE_SOLID: u8 = 2;
+
+/// An object (loc) definition from the loc group of the config archive.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ObjectDefinition {
+    pub id: u32,
+    pub models: Vec<u16>,
+    /// The loc shape of each model, or `None` if the models are used for every shape
+    pub model_types: Option<Vec<u8>>,
+    pub name: String,
+    pub size_x: u8,
+    pub size_y: u8,
+    /// Whether and how the loc blocks movement, see the `INTERACT_TYPE_*` constants
+    pub interact_type: u8,
+    pub blocks_projectile: bool,
+    /// Whether the loc is a wall or door, `None` to decide from its actions
+    pub wall_or_door: Option<u8>,
+    /// How the loc follows the ground, -1 for not at all, 0 for the default
+    /// contouring and higher values for the skew height
+    pub contour_ground: i32,
+    pub merge_normals: bool,
+    pub occludes: bool,
+    /// The animation the loc plays, with the client's 65535 read as `None`
+    pub animation: Option<u16>,
+    pub decor_displacement: u8,
+    pub ambient: i8,
+    pub contrast: i8,
+    /// The right click options. `Hidden` hides an option.
+    pub actions: [Option<String>; 5],
+    pub recolor_find: Vec<u16>,
+    pub recolor_replace: Vec<u16>,
+    pub retexture_find: Vec<u16>,
+    pub retexture_replace: Vec<u16>,
+    pub category: Option<u16>,
+    pub rotated: bool,
+    pub shadow: bool,
+    pub model_size_x: u16,
+    pub model_size_height: u16,
+    pub model_size_y: u16,
+    pub map_scene: Option<u16>,
+    /// The sides of the loc it can't be interacted with from, one bit per
+    /// direction starting at north and rotating with the loc
+    pub clip_flags: u8,
+    pub offset_x: i16,
+    pub offset_height: i16,
+    pub offset_y: i16,
+    pub obstructs_ground: bool,
+    pub hollow: bool,
+    pub supports_items: Option<u8>,
+    pub transform: Option<Transform>,
+    pub ambient_sound: Option<u16>,
+    pub ambient_sound_distance: u8,
+    /// Whether the sound keeps playing once out of range, only held by caches
+    /// from [`Revision::AMBIENT_SOUND_RETAIN`]
+    pub ambient_sound_retain: u8,
+    pub ambient_sound_change_ticks_min: u16,
+    pub ambient_sound_change_ticks_max: u16,
+    /// The sounds played at random intervals
+    pub ambient_sounds: Vec<u16>,
+    pub map_function: Option<u16>,
+    pub randomize_animation_start: bool,
+    pub params: Params,
+}
+
+impl Default for ObjectDefinition {
+    fn default() -> Self {
+        ObjectDefinition {
+            id: 0,
+            models: Vec::new(),
+            model_types: None,
+            name: String::from("null"),
+            size_x: 1,
+            size_y: 1,
+            interact_type: INTERACT_TYPE_SOLID,
+            blocks_projectile: true,
+            wall_or_door: None,
+            contour_ground: -1,
+            merge_normals: false,
+            occludes: false,
+            animation: None,
+            decor_displacement: 16,
+            ambient: 0,
+            contrast: 0,
+            actions: Default::default(),
+            recolor_find: Vec::new(),
+            recolor_replace: Vec::new(),
+            retexture_find: Vec::new(),
+            retexture_replace: Vec::new(),
+            category: None,
+            rotated: false,
+            shadow: true,
+            model_size_x: 128,
+            model_size_height: 128,
+            model_size_y: 128,
+            map_scene: None,
+            clip_flags: 0,
+            offset_x: 0,
+            offset_height: 0,
+            offset_y: 0,
+            obstructs_ground: false,
+            hollow: false,
+            supports_items: None,
+            transform: None,
+            ambient_sound: None,
+            ambient_sound_distance: 0,
+            ambient_sound_retain: 0,
+            ambient_sound_change_ticks_min: 0,
+            ambient_sound_change_ticks_max: 0,
+            ambient_sounds: Vec::new(),
+            map_function: None,
+            randomize_animation_start: false,
+            params: Params::new(),
+        }
+    }
+}
+
+impl ObjectDefinition {
+    /// Check whether the loc blocks movement
+    pub fn solid(&self) -> bool {
+        self.interact_type != INTERACT_TYPE_NONE
+    }
+
+    /// Get the size of the loc for a rotation, swapping the sides when the
+    /// loc is rotated by 90 or 270 degrees
+    ///
+    /// # Arguments
+    ///
+    /// * `rotation` - The rotation of the loc, from 0 to 3
+    pub fn rotated_size(&self, rotation: u8) -> (u8, u8) {
+        if rotation & 1 == 1 {
+            (self.size_y, self.size_x)
+        } else {
+            (self.size_x, self.size_y)
+        }
+    }
+}
+
+/// Models paired with the loc shape each is used for. A list without models
+/// leaves them unset.
+struct TypedModels;
+
+impl Codec<(Vec<u16>, Option<Vec<u8>>)> for TypedModels {
+    fn read(buf: &mut &[u8]) -> Result<(Vec<u16>, Option<Vec<u8>>), DefinitionError> {
+        let len = buf.read_u8()?;
+        if len == 0 {
+            return Ok((Vec::new(), None));
+        }
+        let mut models = Vec::with_capacity(len as usize);
+        let mut types = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            models.push(buf.read_u16()?);
+            types.push(buf.read_u8()?);
+        }
+        Ok((models, Some(types)))
+    }
+
+    fn write(
+        (models, types): &(Vec<u16>, Option<Vec<u8>>),
+        buf: &mut Vec<u8>,
+    ) -> Result<(), DefinitionError> {
+        buf.write_u8(models.len() as u8)?;
+        for (model, model_type) in models.iter().zip(types.iter().flatten()) {
+            buf.write_u16(*model)?;
+            buf.write_u8(*model_type)?;
+        }
+        Ok(())
+    }
+
+    fn is_held((_, types): &(Vec<u16>, Option<Vec<u8>>)) -> bool {
+        types.is_some()
+    }
+}
+
+/// Models used for every loc shape.
+struct Models;
+
+impl Codec<(Vec<u16>, Option<Vec<u8>>)> for Models {
+    fn read(buf: &mut &[u8]) -> Result<(Vec<u16>, Option<Vec<u8>>), DefinitionError> {
+        Ok((read_u16_list(buf)?, None))
+    }
+
+    fn write(
+        (models, _): &(Vec<u16>, Option<Vec<u8>>),
+        buf: &mut Vec<u8>,
+    ) -> Result<(), DefinitionError> {
+        write_u16_list(buf, models)
+    }
+
+    fn is_held((_, types): &(Vec<u16>, Option<Vec<u8>>)) -> bool {
+        types.is_none()
+    }
+}
+
+/// An opcode without fields that stops the loc from blocking movement and
+/// projectiles.
+struct NoInteraction;
+
+impl Codec<(u8, bool)> for NoInteraction {
+    fn read(_buf: &mut &[u8]) -> Result<(u8, bool), DefinitionError> {
+        Ok((INTERACT_TYPE_NONE, false))
+    }
+
+    fn write(_value: &(u8, bool), _buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(())
+    }
+
+    fn is_held((interact_type, _): &(u8, bool)) -> bool {
+        *interact_type == INTERACT_TYPE_NONE
+    }
+
+    fn is_encodable((_, blocks_projectile): &(u8, bool)) -> bool {
+        !blocks_projectile
+    }
+}
+
+/// The skew height of a loc following the ground, in units of 256.
+struct ContourSkew;
+
+impl Codec<i32> for ContourSkew {
+    fn read(buf: &mut &[u8]) -> Result<i32, DefinitionError> {
+        Ok(buf.read_u8()? as i32 * 256)
+    }
+
+    fn write(value: &i32, buf: &mut Vec<u8>) -> Result<(), DefinitionError> {
+        Ok(buf.write_u8((value / 256) as u8)?)
+    }
+
+    fn is_held(value: &i32) -> bool {
+        *value > 0
+    }
+}
+
+opcodes!(ObjectDefinition {
+    1 => (models, model_types): TypedModels,
+    2 => name: Cp1252,
+    5 => (models, model_types): Models,
+    14 => size_x: U8,
+    15 => size_y: U8,
+    17 => (interact_type, blocks_projectile): NoInteraction,
+    18 => blocks_projectile: Set<false> where |object| object.interact_type != INTERACT_TYPE_NONE,
+    19 => wall_or_door: Option<U8>,
+    21 => contour_ground: Const<0>,
+    22 => merge_normals: Set<true>,
+    23 => occludes: Set<true>,
+    24 => animation: OptionalU16,
+    27 => interact_type: Const<{ INTERACT_TYPE_BLOCKING as i32 }>,
+    28 => decor_displacement: U8,
+    29 => ambient: I8,
+    30 => (actions[0]): Option<Cp1252>,
+    31 => (actions[1]): Option<Cp1252>,
+    32 => (actions[2]): Option<Cp1252>,
+    33 => (actions[3]): Option<Cp1252>,
+    34 => (actions[4]): Option<Cp1252>,
+    39 => contrast: I8,
+    40 => (recolor_find, recolor_replace): U16Pairs,
+    41 => (retexture_find, retexture_replace): U16Pairs,
+    61 => category: Option<U16>,
+    62 => rotated: Set<true>,
+    64 => shadow: Set<false>,
+    65 => model_size_x: U16,
+    66 => model_size_height: U16,
+    67 => model_size_y: U16,
+    68 => map_scene: Option<U16>,
+    69 => clip_flags: U8,
+    70 => offset_x: I16,
+    71 => offset_height: I16,
+    72 => offset_y: I16,
+    73 => obstructs_ground: Set<true>,
+    74 => hollow: Set<true>,
+    75 => supports_items: Option<U8>,
+    77 => transform: Transforms<false>,
+    78 @ ..Revision::AMBIENT_SOUND_RETAIN => (ambient_sound, ambient_sound_distance):
+        (Option<U16>, U8) where |object| object.ambient_sound.is_some(),
+    78 @ Revision::AMBIENT_SOUND_RETAIN.. => (
+        ambient_sound,
+        ambient_sound_distance,
+        ambient_sound_retain
+    ): (Option<U16>, U8, U8) where |object| object.ambient_sound.is_some(),
+    79 @ ..Revision::AMBIENT_SOUND_RETAIN => (
+        ambient_sound_change_ticks_min,
+        ambient_sound_change_ticks_max,
+        ambient_sound_distance,
+        ambient_sounds
+    ): (U16, U16, U8, U16List) where |object| !object.ambient_sounds.is_empty(),
+    79 @ Revision::AMBIENT_SOUND_RETAIN.. => (
+        ambient_sound_change_ticks_min,
+        ambient_sound_change_ticks_max,
+        ambient_sound_distance,
+        ambient_sound_retain,
+        ambient_sounds
+    ): (U16, U16, U8, U8, U16List) where |object| !object.ambient_sounds.is_empty(),
+    81 => contour_ground: ContourSkew,
+    82 => map_function: Option<U16>,
+    89 => randomize_animation_start: Set<true>,
+    92 => transform: Transforms<true>,
+    249 => params: ParamList,
+});
+
+impl Definition for ObjectDefinition {
+    const GROUP: u32 = 6;
+
+    fn with_id(id: u32) -> Self {
+        ObjectDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<ObjectDefinition>("tests/data/cache/cache-config");
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+
+        let door = cache.object(1530).unwrap();
+        assert_eq!("Door", door.name);
+        assert_eq!(vec![1203], door.models);
+        assert_eq!(Some(vec![0]), door.model_types);
+        assert_eq!(Some("Open"), door.actions[0].as_deref());
+        assert_eq!(Some(1), door.wall_or_door);
+        assert!(door.solid());
+        assert!(door.blocks_projectile);
+
+        let tree = cache.object(1276).unwrap();
+        assert_eq!((2, 2), tree.rotated_size(1));
+        assert_eq!(Some(0), tree.map_scene);
+        assert_eq!(Some(1), tree.map_function);
+
+        let table = cache.object(595).unwrap();
+        assert_eq!((3, 1), table.rotated_size(1));
+        assert_eq!(INTERACT_TYPE_SOLID, table.interact_type);
+        assert!(!table.blocks_projectile);
+        assert_eq!(0b1010, table.clip_flags);
+
+        let rug = cache.object(1000).unwrap();
+        assert!(!rug.solid());
+        assert_eq!(vec![3000, 3001], rug.models);
+        assert_eq!(None, rug.model_types);
+
+        let lever = cache.object(2000).unwrap();
+        assert_eq!(
+            Some(Transform {
+                varbit: None,
+                varp: Some(500),
+                children: vec![Some(1000), Some(1530)],
+                default_child: Some(Some(595)),
+            }),
+            lever.transform
+        );
+        assert_eq!(vec![2400, 2401], lever.ambient_sounds);
+        assert_eq!(Some(7), lever.params.get_int(10));
+    }
+
+    #[test]
+    fn test_legacy_ambient_sound() {
+        let buf = [78, 0, 10, 3, 0];
+        let object = ObjectDefinition::decode_revision(0, &buf, Revision::LEGACY).unwrap();
+        assert_eq!(Some(10), object.ambient_sound);
+        assert_eq!(3, object.ambient_sound_distance);
+        assert_eq!(
+            buf.to_vec(),
+            object.encode_revision(Revision::LEGACY).unwrap()
+        );
+
+        assert!(ObjectDefinition::decode(0, &buf).is_err());
+    }
+
+    #[test]
+    fn test_hidden_values() {
+        let object = ObjectDefinition::decode(0, &[24, 0xff, 0xff, 0]).unwrap();
+        assert_eq!(None, object.animation);
+
+        let buf = [92, 0xff, 0xff, 0, 99, 0xff, 0xff, 0, 0, 7, 0];
+        let object = ObjectDefinition::decode(0, &buf).unwrap();
+        assert_eq!(
+            Some(Transform {
+                varbit: None,
+                varp: Some(99),
+                children: vec![Some(7)],
+                default_child: Some(None),
+            }),
+            object.transform
+        );
+        assert_eq!(buf.to_vec(), object.encode().unwrap());
+    }
+}
@@ -0,0 +1,100 @@
+use super::codec::{opcodes, Set, U24, U8};
+use super::Definition;
+use crate::hsl::FloorColour;
+
+/// The colour of overlays that aren't drawn, leaving the underlay visible.
+pub const HIDDEN_RGB: u32 = 0xff00ff;
+
+/// An overlay definition from the overlay group of the config archive, giving
+/// the colour or texture of paths, water and other ground drawn over
+/// underlays.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OverlayDefinition {
+    pub id: u32,
+    /// The colour as `0xRRGGBB`, or [`HIDDEN_RGB`]
+    pub rgb: u32,
+    /// The texture drawn instead of the colour
+    pub texture: Option<u8>,
+    /// Whether the underlay is left out below the overlay
+    pub hide_underlay: bool,
+    /// The colour drawn on the minimap instead of `rgb`
+    pub secondary_rgb: Option<u32>,
+}
+
+impl Default for OverlayDefinition {
+    fn default() -> Self {
+        OverlayDefinition {
+            id: 0,
+            rgb: 0,
+            texture: None,
+            hide_underlay: true,
+            secondary_rgb: None,
+        }
+    }
+}
+
+impl OverlayDefinition {
+    /// Get the packed HSL colour of the overlay, or `None` if it isn't drawn
+    pub fn hsl(&self) -> Option<u16> {
+        (self.rgb != HIDDEN_RGB).then(|| FloorColour::from_rgb(self.rgb).to_hsl())
+    }
+
+    /// Get the packed HSL colour of the secondary colour, if any
+    pub fn secondary_hsl(&self) -> Option<u16> {
+        self.secondary_rgb
+            .map(|rgb| FloorColour::from_rgb(rgb).to_hsl())
+    }
+}
+
+opcodes!(OverlayDefinition {
+    1 => rgb: U24,
+    2 => texture: Option<U8>,
+    5 => hide_underlay: Set<false>,
+    7 => secondary_rgb: Option<U24>,
+});
+
+impl Definition for OverlayDefinition {
+    const GROUP: u32 = 4;
+
+    fn with_id(id: u32) -> Self {
+        OverlayDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        definitions::{assert_round_trip, UnderlayDefinition},
+        Cache,
+    };
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_eq!(
+            4,
+            assert_round_trip::<OverlayDefinition>("tests/data/cache/cache-minimap")
+        );
+
+        assert_eq!(
+            2,
+            assert_round_trip::<UnderlayDefinition>("tests/data/cache/cache-minimap")
+        );
+    }
+
+    #[test]
+    fn test_hsl() {
+        let mut cache = Cache::open("tests/data/cache/cache-minimap").unwrap();
+
+        let water = cache.overlay(0).unwrap();
+        assert!(!water.hide_underlay);
+        assert_eq!(Some(FloorColour::from_rgb(0x0000ff).to_hsl()), water.hsl());
+        assert_eq!(None, cache.overlay(1).unwrap().hsl());
+        assert_eq!(Some(3), cache.overlay(2).unwrap().texture);
+        assert!(cache.overlay(3).unwrap().secondary_hsl().is_some());
+    }
+}
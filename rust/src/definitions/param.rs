@@ -0,0 +1,107 @@
+use super::codec::{opcodes, Char, Cp1252, Set, I32};
+use super::{is_string_type, Definition, ParamValue};
+
+/// A param definition from the params group of the config archive, giving
+/// the type and default of the values stored in configs' `params`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParamDefinition {
+    pub id: u32,
+    /// The type char of the values. `s` values are strings, any other type is
+    /// stored as an int.
+    pub value_type: Option<char>,
+    pub default_int: i32,
+    pub default_string: Option<String>,
+    /// Whether the client ignores the param on members' configs in free
+    /// worlds
+    pub auto_disable: bool,
+}
+
+impl Default for ParamDefinition {
+    fn default() -> Self {
+        ParamDefinition {
+            id: 0,
+            value_type: None,
+            default_int: 0,
+            default_string: None,
+            auto_disable: true,
+        }
+    }
+}
+
+impl ParamDefinition {
+    /// Get whether the param's values are strings
+    pub fn is_string(&self) -> bool {
+        self.value_type.is_some_and(is_string_type)
+    }
+
+    /// Get the default value for the param's type. String params without a
+    /// default yield an empty string.
+    pub fn default_value(&self) -> ParamValue {
+        if self.is_string() {
+            ParamValue::String(self.default_string.clone().unwrap_or_default())
+        } else {
+            ParamValue::Int(self.default_int)
+        }
+    }
+}
+
+opcodes!(ParamDefinition {
+    1 => value_type: Option<Char>,
+    2 => default_int: I32,
+    4 => auto_disable: Set<false>,
+    5 => default_string: Option<Cp1252>,
+});
+
+impl Definition for ParamDefinition {
+    const GROUP: u32 = 11;
+
+    fn with_id(id: u32) -> Self {
+        ParamDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<ParamDefinition>("tests/data/cache/cache-config");
+    }
+
+    #[test]
+    fn test_param_value() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+
+        let param = cache.param(1).unwrap();
+        assert!(param.is_string() && !param.auto_disable);
+        assert_eq!(ParamValue::String("Nothing".into()), param.default_value());
+
+        // Params shared by items, npcs and structs fall back to the default
+        let hat = cache.item(1042).unwrap();
+        assert_eq!(
+            ParamValue::Int(1),
+            cache.param_value(&hat.params, 1234).unwrap()
+        );
+        assert_eq!(
+            ParamValue::Int(5),
+            cache.param_value(&hat.params, 0).unwrap()
+        );
+
+        let shop = cache.struct_definition(0).unwrap();
+        assert_eq!(
+            ParamValue::String("Shop".into()),
+            cache.param_value(&shop.params, 1).unwrap()
+        );
+        let empty = cache.struct_definition(1).unwrap();
+        assert_eq!(
+            ParamValue::String("Nothing".into()),
+            cache.param_value(&empty.params, 1).unwrap()
+        );
+    }
+}
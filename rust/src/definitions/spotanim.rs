@@ -0,0 +1,111 @@
+use super::codec::{opcodes, U16Pairs, U16, U8};
+use super::Definition;
+
+/// A spot animation definition from the spotanim group of the config archive,
+/// a graphic such as a spell or projectile played at a tile or on an entity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SpotAnimDefinition {
+    pub id: u32,
+    pub model: u16,
+    pub sequence: Option<u16>,
+    /// The horizontal scale, 128 being the model's size
+    pub width_scale: u16,
+    /// The vertical scale, 128 being the model's size
+    pub height_scale: u16,
+    /// The rotation in degrees, one of 0, 90, 180 or 270
+    pub rotation: u16,
+    pub ambient: u8,
+    pub contrast: u8,
+    pub recolor_find: Vec<u16>,
+    pub recolor_replace: Vec<u16>,
+    pub retexture_find: Vec<u16>,
+    pub retexture_replace: Vec<u16>,
+}
+
+impl Default for SpotAnimDefinition {
+    fn default() -> Self {
+        SpotAnimDefinition {
+            id: 0,
+            model: 0,
+            sequence: None,
+            width_scale: 128,
+            height_scale: 128,
+            rotation: 0,
+            ambient: 0,
+            contrast: 0,
+            recolor_find: Vec::new(),
+            recolor_replace: Vec::new(),
+            retexture_find: Vec::new(),
+            retexture_replace: Vec::new(),
+        }
+    }
+}
+
+opcodes!(SpotAnimDefinition {
+    1 => model: U16,
+    2 => sequence: Option<U16>,
+    4 => width_scale: U16,
+    5 => height_scale: U16,
+    6 => rotation: U16,
+    7 => ambient: U8,
+    8 => contrast: U8,
+    40 => (recolor_find, recolor_replace): U16Pairs,
+    41 => (retexture_find, retexture_replace): U16Pairs,
+});
+
+impl Definition for SpotAnimDefinition {
+    const GROUP: u32 = 13;
+
+    fn with_id(id: u32) -> Self {
+        SpotAnimDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_decode_scale() {
+        // Only the width is scaled, the height keeps the model's size
+        let buf = [4, 0x00, 0x40, 6, 0x01, 0x0e, 0];
+        let spotanim = SpotAnimDefinition::decode(0, &buf).unwrap();
+        assert_eq!(
+            (64, 128, 270),
+            (
+                spotanim.width_scale,
+                spotanim.height_scale,
+                spotanim.rotation
+            )
+        );
+        assert_eq!(buf.to_vec(), spotanim.encode().unwrap());
+    }
+
+    #[test]
+    fn test_first_sequence() {
+        let mut spotanim = SpotAnimDefinition::with_id(0);
+        spotanim.sequence = Some(0);
+        assert_eq!(vec![2, 0, 0, 0], spotanim.encode().unwrap());
+    }
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<SpotAnimDefinition>("tests/data/cache/cache-config");
+    }
+
+    #[test]
+    fn test_cache_spotanim() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+        let splash = cache.spotanim(85).unwrap();
+        assert_eq!((3080, Some(653)), (splash.model, splash.sequence));
+        assert_eq!(
+            (vec![926], vec![10]),
+            (splash.recolor_find, splash.recolor_replace)
+        );
+    }
+}
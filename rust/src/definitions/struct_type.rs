@@ -0,0 +1,50 @@
+use super::codec::{opcodes, ParamList};
+use super::{Definition, Params};
+
+/// A struct definition from the struct group of the config archive, which is
+/// nothing but a set of params.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StructDefinition {
+    pub id: u32,
+    pub params: Params,
+}
+
+opcodes!(StructDefinition {
+    249 => params: ParamList,
+});
+
+impl Definition for StructDefinition {
+    const GROUP: u32 = 34;
+
+    fn with_id(id: u32) -> Self {
+        StructDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        definitions::{assert_round_trip, ParamValue},
+        Cache,
+    };
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_round_trip::<StructDefinition>("tests/data/cache/cache-config");
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+
+        let shop = cache.struct_definition(0).unwrap();
+        assert_eq!(Some(10), shop.params.get_int(0));
+        assert_eq!(Some(&ParamValue::String("Shop".into())), shop.params.get(1));
+        assert!(cache.struct_definition(1).unwrap().params.is_empty());
+    }
+}
@@ -0,0 +1,35 @@
+use super::codec::{opcodes, U24};
+use super::Definition;
+use crate::hsl::FloorColour;
+
+/// An underlay definition from the underlay group of the config archive,
+/// giving the colour of the ground below overlays.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UnderlayDefinition {
+    pub id: u32,
+    /// The colour as `0xRRGGBB`
+    pub rgb: u32,
+}
+
+impl UnderlayDefinition {
+    /// Get the colour as the client blends it with neighbouring underlays
+    pub fn colour(&self) -> FloorColour {
+        FloorColour::from_rgb(self.rgb)
+    }
+}
+
+opcodes!(UnderlayDefinition {
+    1 => rgb: U24,
+});
+
+impl Definition for UnderlayDefinition {
+    const GROUP: u32 = 1;
+
+    fn with_id(id: u32) -> Self {
+        UnderlayDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::codec::{opcodes, U16, U8};
+use super::Definition;
+
+/// A varbit definition from the varbit group of the config archive. A varbit
+/// is a range of bits packed into a varp.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct VarbitDefinition {
+    pub id: u32,
+    /// The varp the bits are stored in
+    pub base_var: u16,
+    /// The lowest bit, inclusive
+    pub lsb: u8,
+    /// The highest bit, inclusive
+    pub msb: u8,
+}
+
+impl VarbitDefinition {
+    /// Get the mask of the varbit's value before it is shifted into place,
+    /// or `None` if the bit range is invalid
+    pub fn mask(&self) -> Option<u32> {
+        if self.lsb > self.msb || self.msb > 31 {
+            return None;
+        }
+        Some(u32::MAX >> (31 - (self.msb - self.lsb)))
+    }
+}
+
+opcodes!(VarbitDefinition {
+    1 => (base_var, lsb, msb): (U16, U8, U8),
+});
+
+impl Definition for VarbitDefinition {
+    const GROUP: u32 = 14;
+
+    fn with_id(id: u32) -> Self {
+        VarbitDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_eq!(
+            3,
+            assert_round_trip::<VarbitDefinition>("tests/data/cache/cache-config")
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+
+        let varbit = cache.varbit(1).unwrap();
+        assert_eq!((1, 4, 4), (varbit.base_var, varbit.lsb, varbit.msb));
+        assert_eq!(Some(1), varbit.mask());
+        assert_eq!(Some(0xf), cache.varbit(0).unwrap().mask());
+        assert_eq!(Some(u32::MAX), cache.varbit(2).unwrap().mask());
+
+        let invalid = VarbitDefinition {
+            lsb: 5,
+            msb: 4,
+            ..Default::default()
+        };
+        assert_eq!(None, invalid.mask());
+    }
+}
@@ -0,0 +1,46 @@
+use super::codec::{opcodes, U16};
+use super::Definition;
+
+/// A varp (player variable) definition from the varp group of the config
+/// archive.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct VarpDefinition {
+    pub id: u32,
+    /// The client code run when the varp changes, also known as its type,
+    /// such as 1 for the brightness setting. 0 runs nothing.
+    pub client_code: u16,
+}
+
+opcodes!(VarpDefinition {
+    5 => client_code: U16,
+});
+
+impl Definition for VarpDefinition {
+    const GROUP: u32 = 16;
+
+    fn with_id(id: u32) -> Self {
+        VarpDefinition {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::assert_round_trip, Cache};
+
+    #[test]
+    fn test_round_trip_all() {
+        assert_eq!(
+            3,
+            assert_round_trip::<VarpDefinition>("tests/data/cache/cache-config")
+        );
+
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+        assert_eq!(3, cache.varp(1).unwrap().client_code);
+        assert_eq!(0, cache.varp(2).unwrap().client_code);
+    }
+}
@@ -136,6 +136,61 @@ pub unsafe extern "C" fn cache_read_named_group(
     data
 }
 
+/// Read a named file from a named group in the cache
+///
+/// # Arguments
+///
+/// * `cache_ptr` - The cache to read from
+/// * `archive` - The archive to read from
+/// * `group` - The name of the group to read from
+/// * `file` - The name of the file to read
+/// * `xtea_keys` - The optional XTEA keys to use for decryption
+/// * `out_len` - The length of the returned buffer
+///
+/// # Returns
+///
+/// The function returns a pointer to the buffer containing the file data, where the length is stored in the `out_len` variable.
+/// The caller is responsible for freeing the buffer using the function `cache_free`.
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn cache_read_named(
+    cache_ptr: *mut Cache,
+    archive: u8,
+    group: *const c_char,
+    file: *const c_char,
+    xtea_keys_arg: *const [u32; 4],
+    out_len: *mut u32,
+) -> *mut u8 {
+    // Dereference the cache
+    let cache = &mut *cache_ptr;
+
+    // Dereference the xtea keys if not null
+    let mut xtea_keys = None;
+    if !xtea_keys_arg.is_null() {
+        xtea_keys = Some(*xtea_keys_arg);
+    }
+
+    let group_str = CStr::from_ptr(group)
+        .to_str()
+        .expect("failed to convert group to str");
+    let file_str = CStr::from_ptr(file)
+        .to_str()
+        .expect("failed to convert file to str");
+
+    // Call the read function
+    let mut buf = cache
+        .read_named(archive, group_str, file_str, xtea_keys)
+        .expect("failed reading named file");
+
+    let data = buf.as_mut_ptr();
+    *out_len = buf.len() as u32;
+    mem::forget(buf);
+    data
+}
+
 /// Free a buffer returned by cache read functions
 ///
 /// # Arguments
@@ -0,0 +1,521 @@
+use crate::{
+    buffer::{char_to_cp1252, cp1252_to_char},
+    sprite::{SpriteFrame, SpriteSheet},
+};
+use osrs_bytes::ReadExt;
+use std::io;
+use thiserror::Error;
+
+/// The archive holding the metrics of each font.
+pub const FONTS_ARCHIVE: u8 = 13;
+/// The plain 11px font.
+pub const FONT_PLAIN_11: &str = "p11_full";
+/// The plain 12px font.
+pub const FONT_PLAIN_12: &str = "p12_full";
+/// The bold 12px font.
+pub const FONT_BOLD_12: &str = "b12_full";
+/// The quill 8px font.
+pub const FONT_QUILL_8: &str = "q8_full";
+
+const GLYPHS: usize = 256;
+const NBSP: u8 = 0xa0;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum FontError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("expected {GLYPHS} glyphs, got {0}")]
+    InvalidGlyphCount(usize),
+}
+
+/// The advances and kerning of a font, from file 0 of a fonts group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontMetrics {
+    /// The advance of each Windows-1252 character
+    pub advances: Vec<u8>,
+    pub ascent: u8,
+    /// The kerning of each pair of characters, indexed by
+    /// `(first << 8) | second`
+    pub kerning: Option<Vec<i8>>,
+}
+
+impl FontMetrics {
+    /// Decode font metrics. The short form is the advances followed by the
+    /// ascent; the long form adds each glyph's vertical extent and left and
+    /// right profiles, from which the client derives the kerning.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The contents of file 0 of a fonts group
+    pub fn decode(mut buf: &[u8]) -> Result<FontMetrics, FontError> {
+        let read_table = |buf: &mut &[u8]| -> io::Result<Vec<u8>> {
+            let mut table = vec![0; GLYPHS];
+            for value in table.iter_mut() {
+                *value = buf.read_u8()?;
+            }
+            Ok(table)
+        };
+
+        let advances = read_table(&mut buf)?;
+        if buf.len() == 1 {
+            return Ok(FontMetrics {
+                advances,
+                ascent: buf[0],
+                kerning: None,
+            });
+        }
+
+        let heights = read_table(&mut buf)?;
+        let tops = read_table(&mut buf)?;
+        let read_profiles = |buf: &mut &[u8]| -> io::Result<Vec<Vec<i8>>> {
+            heights
+                .iter()
+                .map(|height| {
+                    let mut value = 0i8;
+                    (0..*height)
+                        .map(|_| {
+                            value = value.wrapping_add(buf.read_i8()?);
+                            Ok(value)
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        let lefts = read_profiles(&mut buf)?;
+        let rights = read_profiles(&mut buf)?;
+
+        let kern = |first: usize, second: usize| {
+            let (first_top, second_top) = (tops[first] as usize, tops[second] as usize);
+            let start = first_top.max(second_top);
+            let end =
+                (first_top + heights[first] as usize).min(second_top + heights[second] as usize);
+
+            let gap = (start..end)
+                .map(|y| rights[first][y - first_top] as i32 + lefts[second][y - second_top] as i32)
+                .fold(advances[first].min(advances[second]) as i32, i32::min);
+            -gap as i8
+        };
+
+        let mut kerning = vec![0; GLYPHS * GLYPHS];
+        let spaces = [b' ' as usize, NBSP as usize];
+        for first in (0..GLYPHS).filter(|c| !spaces.contains(c)) {
+            for second in (0..GLYPHS).filter(|c| !spaces.contains(c)) {
+                kerning[(first << 8) | second] = kern(first, second);
+            }
+        }
+
+        Ok(FontMetrics {
+            advances,
+            ascent: tops[b' ' as usize] + heights[b' ' as usize],
+            kerning: Some(kerning),
+        })
+    }
+}
+
+/// A character placed on a line by [`Font::layout`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlacedGlyph {
+    /// The Windows-1252 character
+    pub character: u8,
+    /// The pen position before the glyph's own offset is applied
+    pub x: i32,
+    /// The colour set by the last `<col>` tag, or `None` for the default
+    pub colour: Option<u32>,
+}
+
+/// A line of text laid out by [`Font::layout`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextLine {
+    /// The line with its tags, as the client would draw it
+    pub text: String,
+    pub width: i32,
+    pub glyphs: Vec<PlacedGlyph>,
+}
+
+/// A font made of a glyph sprite sheet and its metrics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Font {
+    pub glyphs: SpriteSheet,
+    pub metrics: FontMetrics,
+}
+
+impl Font {
+    /// Pair the glyphs of a font with its metrics
+    ///
+    /// # Arguments
+    ///
+    /// * `glyphs` - The sprite sheet with one frame per Windows-1252 character
+    /// * `metrics` - The metrics of the font
+    pub fn new(glyphs: SpriteSheet, metrics: FontMetrics) -> Result<Font, FontError> {
+        if glyphs.frames.len() != GLYPHS {
+            return Err(FontError::InvalidGlyphCount(glyphs.frames.len()));
+        }
+        Ok(Font { glyphs, metrics })
+    }
+
+    /// Get the glyph of a character
+    pub fn glyph(&self, c: char) -> &SpriteFrame {
+        &self.glyphs.frames[char_to_cp1252(c) as usize]
+    }
+
+    /// Get the advance of a character
+    pub fn char_width(&self, c: char) -> i32 {
+        self.metrics.advances[char_to_cp1252(c) as usize] as i32
+    }
+
+    /// Get the distance from the top of the tallest glyph to the baseline
+    pub fn max_ascent(&self) -> i32 {
+        let top = self
+            .glyphs
+            .frames
+            .iter()
+            .filter(|glyph| glyph.height != 0)
+            .map(|glyph| glyph.offset_y as i32)
+            .min()
+            .unwrap_or(0);
+        self.metrics.ascent as i32 - top
+    }
+
+    /// Get the distance from the baseline to the bottom of the lowest glyph
+    pub fn max_descent(&self) -> i32 {
+        let bottom = self
+            .glyphs
+            .frames
+            .iter()
+            .map(|glyph| glyph.offset_y as i32 + glyph.height as i32)
+            .max()
+            .unwrap_or(0);
+        bottom - self.metrics.ascent as i32
+    }
+
+    /// Measure text the way the client does. `<img=N>` tags add the width
+    /// of their icon and other tags besides `<lt>` and `<gt>` are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to measure
+    /// * `icons` - The width of each icon, indexed by the `N` of `<img=N>`
+    pub fn text_width(&self, text: &str, icons: &[i32]) -> i32 {
+        let mut width = 0;
+        let mut previous = None;
+        for token in tokens(text) {
+            match token {
+                Token::Char(c) => {
+                    width += self.advance(c) + self.kerning(previous, c);
+                    previous = Some(c);
+                }
+                Token::Tag(tag) => {
+                    if let Some(icon_width) = icon_width(tag, icons) {
+                        width += icon_width;
+                        previous = None;
+                    }
+                }
+            }
+        }
+        width
+    }
+
+    /// Split text into lines at `<br>` tags and, if a width is given, at the
+    /// last space or hyphen before the line gets too wide. Lines keep their
+    /// tags and match the client's line breaking exactly. Non-breaking spaces
+    /// stay part of their word.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to split
+    /// * `max_width` - The widest a line may be before it wraps
+    /// * `icons` - The width of each icon, indexed by the `N` of `<img=N>`
+    pub fn break_lines(&self, text: &str, max_width: Option<i32>, icons: &[i32]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut out: Vec<char> = Vec::new();
+        let mut line_start = 0;
+        let mut width = 0;
+        // The end of the last space or hyphen, the width up to it and
+        // whether the break consumes it
+        let mut break_at: Option<(usize, i32, usize)> = None;
+        // The client kerns the first character of a line against byte 0
+        let mut previous = Some(0);
+
+        for token in tokens(text) {
+            let c = match token {
+                Token::Tag(tag) => {
+                    out.push('<');
+                    out.extend(tag.chars());
+                    out.push('>');
+                    if tag == "br" {
+                        lines.push(out[line_start..].iter().collect());
+                        line_start = out.len();
+                        width = 0;
+                        break_at = None;
+                        previous = Some(0);
+                    } else if let Some(icon_width) = icon_width(tag, icons) {
+                        width += icon_width;
+                        previous = None;
+                    }
+                    None
+                }
+                Token::Char(c) if c == b'<' || c == b'>' => {
+                    let tag = if c == b'<' { "lt" } else { "gt" };
+                    out.push('<');
+                    out.extend(tag.chars());
+                    out.push('>');
+                    width += self.advance(c) + self.kerning(previous, c);
+                    previous = Some(c);
+                    None
+                }
+                Token::Char(c) => {
+                    out.push(cp1252_to_char(c));
+                    width += self.advance(c) + self.kerning(previous, c);
+                    previous = Some(c);
+                    Some(c)
+                }
+            };
+
+            if c == Some(b' ') {
+                break_at = Some((out.len(), width, 1));
+            }
+            if let (Some(max_width), Some((end, break_width, consumed))) = (max_width, break_at) {
+                if width > max_width {
+                    lines.push(out[line_start..end - consumed].iter().collect());
+                    line_start = end;
+                    width -= break_width;
+                    break_at = None;
+                    previous = Some(0);
+                }
+            }
+            if c == Some(b'-') {
+                break_at = Some((out.len(), width, 0));
+            }
+        }
+
+        if out.len() > line_start {
+            lines.push(out[line_start..].iter().collect());
+        }
+        lines
+    }
+
+    /// Break text into lines and place each character, following `<col=>`
+    /// and `</col>` tags and leaving room for `<img=N>` icons. Colours carry
+    /// over from one line to the next.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to lay out
+    /// * `max_width` - The widest a line may be before it wraps
+    /// * `icons` - The width of each icon, indexed by the `N` of `<img=N>`
+    pub fn layout(&self, text: &str, max_width: Option<i32>, icons: &[i32]) -> Vec<TextLine> {
+        let mut colour = None;
+        self.break_lines(text, max_width, icons)
+            .into_iter()
+            .map(|text| {
+                let mut x = 0;
+                let mut previous = None;
+                let mut glyphs = Vec::new();
+                for token in tokens(&text) {
+                    match token {
+                        Token::Tag("/col") => colour = None,
+                        Token::Tag(tag) => {
+                            if let Some(hex) = tag.strip_prefix("col=") {
+                                colour = u32::from_str_radix(hex, 16).ok().or(colour);
+                            } else if let Some(icon_width) = icon_width(tag, icons) {
+                                x += icon_width;
+                                previous = None;
+                            }
+                        }
+                        Token::Char(c) => {
+                            x += self.kerning(previous, c);
+                            glyphs.push(PlacedGlyph {
+                                character: c,
+                                x,
+                                colour,
+                            });
+                            x += self.advance(c);
+                            previous = Some(c);
+                        }
+                    }
+                }
+                TextLine {
+                    text,
+                    width: x,
+                    glyphs,
+                }
+            })
+            .collect()
+    }
+
+    /// Get the advance of a Windows-1252 character. Non-breaking spaces are
+    /// measured as spaces.
+    fn advance(&self, c: u8) -> i32 {
+        let c = if c == NBSP { b' ' } else { c };
+        self.metrics.advances[c as usize] as i32
+    }
+
+    fn kerning(&self, previous: Option<u8>, c: u8) -> i32 {
+        match (&self.metrics.kerning, previous) {
+            (Some(kerning), Some(previous)) => {
+                kerning[((previous as usize) << 8) | c as usize] as i32
+            }
+            _ => 0,
+        }
+    }
+}
+
+enum Token<'a> {
+    Char(u8),
+    /// A tag other than `<lt>` and `<gt>`, without its brackets
+    Tag(&'a str),
+}
+
+/// Split text into Windows-1252 characters and tags. An unclosed tag is
+/// dropped, as in the client.
+fn tokens(text: &str) -> impl Iterator<Item = Token<'_>> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let c = rest.chars().next()?;
+        if c == '<' {
+            let end = rest.find('>')?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+            return Some(match tag {
+                "lt" => Token::Char(b'<'),
+                "gt" => Token::Char(b'>'),
+                _ => Token::Tag(tag),
+            });
+        }
+        rest = &rest[c.len_utf8()..];
+        Some(Token::Char(char_to_cp1252(c)))
+    })
+}
+
+/// Get the width of the icon drawn by an `<img=N>` tag, or `None` if the tag
+/// isn't one. Icons that aren't given have no width.
+fn icon_width(tag: &str, icons: &[i32]) -> Option<i32> {
+    let icon: usize = tag.strip_prefix("img=")?.parse().ok()?;
+    Some(icons.get(icon).copied().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    fn open(name: &str) -> Font {
+        let mut cache = Cache::open("tests/data/cache/cache-fonts").unwrap();
+        cache.font(name).unwrap()
+    }
+
+    #[test]
+    fn test_decode() {
+        let font = open(FONT_PLAIN_12);
+        assert_eq!(10, font.metrics.ascent);
+        assert_eq!(None, font.metrics.kerning);
+        assert_eq!(7, font.char_width('W'));
+        assert_eq!(6, font.glyph('W').width);
+        assert_eq!(9, font.max_ascent());
+        assert_eq!(-7, font.max_descent());
+
+        let font = open(FONT_QUILL_8);
+        assert_eq!(2, font.metrics.ascent);
+        let kerning = font.metrics.kerning.as_ref().unwrap();
+        assert_eq!(-2, kerning[((b'A' as usize) << 8) | b'V' as usize]);
+        assert_eq!(0, kerning[((b'V' as usize) << 8) | b'A' as usize]);
+        assert_eq!(0, kerning[((b'A' as usize) << 8) | b' ' as usize]);
+
+        assert!(matches!(
+            Font::new(SpriteSheet::default(), font.metrics.clone()),
+            Err(FontError::InvalidGlyphCount(0))
+        ));
+    }
+
+    #[test]
+    fn test_text_width() {
+        let font = open(FONT_PLAIN_12);
+        assert_eq!(0, font.text_width("", &[]));
+        assert_eq!(4 + 3 + 7 + 2, font.text_width("a Wi", &[]));
+        assert_eq!(
+            font.text_width("a Wi", &[]),
+            font.text_width("<col=ff0000>a\u{a0}W</col><u>i", &[])
+        );
+        assert_eq!(8, font.text_width("<lt><gt>", &[]));
+        assert_eq!(4, font.text_width("a<col=ff", &[]));
+        assert_eq!(4 + 13 + 4, font.text_width("a<img=1>a<img=2>", &[5, 13]));
+
+        let font = open(FONT_QUILL_8);
+        assert_eq!(8, font.text_width("VA", &[]));
+        assert_eq!(6, font.text_width("AV", &[]));
+    }
+
+    #[test]
+    fn test_break_lines() {
+        let font = open(FONT_PLAIN_12);
+        assert_eq!(
+            vec!["one two<br>", "three"],
+            font.break_lines("one two<br>three", None, &[])
+        );
+        // "aaa bbb" is 27 wide
+        assert_eq!(
+            vec!["aaa bbb", "ccc"],
+            font.break_lines("aaa bbb ccc", Some(27), &[])
+        );
+        assert_eq!(
+            vec!["aaa", "bbb", "ccc"],
+            font.break_lines("aaa bbb ccc", Some(26), &[])
+        );
+        assert_eq!(
+            vec!["aaa-", "bbb"],
+            font.break_lines("aaa-bbb", Some(20), &[])
+        );
+        assert_eq!(
+            vec!["aaaaaaaa"],
+            font.break_lines("aaaaaaaa", Some(10), &[])
+        );
+        assert_eq!(vec!["<lt>a b"], font.break_lines("<lt>a b", Some(20), &[]));
+        assert_eq!(
+            vec!["aaa", "bbb\u{a0}ccc"],
+            font.break_lines("aaa bbb\u{a0}ccc", Some(30), &[])
+        );
+        assert_eq!(
+            vec!["a<img=1>", "b"],
+            font.break_lines("a<img=1> b", Some(10), &[0, 10])
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let font = open(FONT_PLAIN_12);
+        let lines = font.layout("a <col=ff0000>Wi bb</col> a", Some(20), &[]);
+        assert_eq!(2, lines.len());
+        assert_eq!("a <col=ff0000>Wi", lines[0].text);
+        assert_eq!(16, lines[0].width);
+        assert_eq!(
+            vec![
+                PlacedGlyph {
+                    character: b'a',
+                    x: 0,
+                    colour: None
+                },
+                PlacedGlyph {
+                    character: b' ',
+                    x: 4,
+                    colour: None
+                },
+                PlacedGlyph {
+                    character: b'W',
+                    x: 7,
+                    colour: Some(0xff0000)
+                },
+                PlacedGlyph {
+                    character: b'i',
+                    x: 14,
+                    colour: Some(0xff0000)
+                },
+            ],
+            lines[0].glyphs
+        );
+        assert_eq!("bb</col> a", lines[1].text);
+        assert_eq!(Some(0xff0000), lines[1].glyphs[0].colour);
+        assert_eq!(None, lines[1].glyphs[2].colour);
+        assert_eq!(15, lines[1].width);
+    }
+}
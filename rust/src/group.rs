@@ -4,6 +4,7 @@ use std::{collections::BTreeMap, io::Cursor};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum GroupError {
     #[error("group is empty")]
     Empty,
@@ -17,9 +18,17 @@ pub enum GroupError {
     File,
 }
 
+/// Splits the contents of a group into its files.
+#[non_exhaustive]
 pub struct Group {}
 
 impl Group {
+    /// Unpack an uncompressed group into its files
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The uncompressed group
+    /// * `group` - The files of the group, as listed in its index entry
     pub fn unpack(
         input: Vec<u8>,
         group: &BTreeMap<u32, Js5IndexFile>,
@@ -0,0 +1,254 @@
+/// The brightness of the brightest client setting.
+pub const BRIGHTNESS_MAX: f64 = 0.6;
+pub const BRIGHTNESS_HIGH: f64 = 0.7;
+pub const BRIGHTNESS_LOW: f64 = 0.8;
+/// The brightness of the darkest client setting.
+pub const BRIGHTNESS_MIN: f64 = 0.9;
+
+/// Get the hue of a packed HSL colour, from 0 to 63
+pub fn hue(hsl: u16) -> u8 {
+    ((hsl >> 10) & 0x3f) as u8
+}
+
+/// Get the saturation of a packed HSL colour, from 0 to 7
+pub fn saturation(hsl: u16) -> u8 {
+    ((hsl >> 7) & 0x7) as u8
+}
+
+/// Get the luminance of a packed HSL colour, from 0 to 127
+pub fn luminance(hsl: u16) -> u8 {
+    (hsl & 0x7f) as u8
+}
+
+/// Pack a hue, saturation and luminance into an HSL colour
+///
+/// # Arguments
+///
+/// * `hue` - The hue, from 0 to 63
+/// * `saturation` - The saturation, from 0 to 7
+/// * `luminance` - The luminance, from 0 to 127
+pub fn pack(hue: u8, saturation: u8, luminance: u8) -> u16 {
+    ((hue as u16 & 0x3f) << 10) | ((saturation as u16 & 0x7) << 7) | (luminance as u16 & 0x7f)
+}
+
+/// Convert a packed HSL colour to RGB the way the client builds its palette.
+/// Black becomes 1 as the client reserves 0 for transparency.
+///
+/// # Arguments
+///
+/// * `hsl` - The packed HSL colour
+/// * `brightness` - The brightness, from [`BRIGHTNESS_MAX`] to [`BRIGHTNESS_MIN`]
+pub fn hsl_to_rgb(hsl: u16, brightness: f64) -> u32 {
+    let hue = hue(hsl) as f64 / 64.0 + 0.0078125;
+    let saturation = saturation(hsl) as f64 / 8.0 + 0.0625;
+    let luminance = luminance(hsl) as f64 / 128.0;
+
+    let chroma = (1.0 - (2.0 * luminance - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue * 6.0) % 2.0 - 1.0).abs());
+    let lightness = luminance - chroma / 2.0;
+
+    let (r, g, b) = match (hue * 6.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    let channel = |value: f64| {
+        let value = ((value + lightness) * 256.0) as u32 as f64 / 256.0;
+        ((value.powf(brightness) * 256.0) as u32).min(0xff)
+    };
+    match (channel(r) << 16) | (channel(g) << 8) | channel(b) {
+        0 => 1,
+        rgb => rgb,
+    }
+}
+
+/// Build the client's palette of every packed HSL colour
+///
+/// # Arguments
+///
+/// * `brightness` - The brightness, from [`BRIGHTNESS_MAX`] to [`BRIGHTNESS_MIN`]
+pub fn palette(brightness: f64) -> Vec<u32> {
+    (0..=u16::MAX)
+        .map(|hsl| hsl_to_rgb(hsl, brightness))
+        .collect()
+}
+
+/// An RGB floor colour split into components from 0 to 255 the way the
+/// client converts underlays and overlays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FloorColour {
+    pub hue: i32,
+    pub saturation: i32,
+    pub lightness: i32,
+    /// How strongly the hue counts when underlays are blended, at least 1
+    pub hue_multiplier: i32,
+    /// The hue weighted by the multiplier, summed when underlays are blended
+    pub blend_hue: i32,
+}
+
+impl FloorColour {
+    /// Convert an RGB colour
+    ///
+    /// # Arguments
+    ///
+    /// * `rgb` - The colour as `0xRRGGBB`
+    pub fn from_rgb(rgb: u32) -> FloorColour {
+        let r = ((rgb >> 16) & 0xff) as f64 / 256.0;
+        let g = ((rgb >> 8) & 0xff) as f64 / 256.0;
+        let b = (rgb & 0xff) as f64 / 256.0;
+        let min = r.min(g).min(b);
+        let max = r.max(g).max(b);
+
+        let lightness = (min + max) / 2.0;
+        let mut hue = 0.0;
+        let mut saturation = 0.0;
+        if min != max {
+            saturation = if lightness < 0.5 {
+                (max - min) / (max + min)
+            } else {
+                (max - min) / (2.0 - max - min)
+            };
+            hue = if r == max {
+                (g - b) / (max - min)
+            } else if g == max {
+                2.0 + (b - r) / (max - min)
+            } else {
+                4.0 + (r - g) / (max - min)
+            };
+        }
+        hue /= 6.0;
+
+        let hue_multiplier = if lightness > 0.5 {
+            (1.0 - lightness) * saturation * 512.0
+        } else {
+            lightness * saturation * 512.0
+        } as i32;
+        let hue_multiplier = hue_multiplier.max(1);
+
+        FloorColour {
+            hue: ((hue * 256.0) as i32).clamp(0, 255),
+            saturation: ((saturation * 256.0) as i32).clamp(0, 255),
+            lightness: ((lightness * 256.0) as i32).clamp(0, 255),
+            hue_multiplier,
+            blend_hue: (hue * hue_multiplier as f64) as i32,
+        }
+    }
+
+    /// Pack the colour into an HSL colour
+    pub fn to_hsl(&self) -> u16 {
+        pack_floor(self.hue, self.saturation, self.lightness)
+    }
+}
+
+/// Pack floor colour components into an HSL colour. As in the client,
+/// light colours lose saturation.
+///
+/// # Arguments
+///
+/// * `hue` - The hue, from 0 to 255
+/// * `saturation` - The saturation, from 0 to 255
+/// * `lightness` - The lightness, from 0 to 255
+pub fn pack_floor(hue: i32, mut saturation: i32, lightness: i32) -> u16 {
+    for threshold in [179, 192, 217, 243] {
+        if lightness > threshold {
+            saturation /= 2;
+        }
+    }
+    pack(
+        (hue / 4) as u8,
+        (saturation / 32) as u8,
+        (lightness / 2) as u8,
+    )
+}
+
+/// Scale the luminance of an HSL colour the way the client lights flat
+/// tiles, keeping it between 2 and 126
+///
+/// # Arguments
+///
+/// * `hsl` - The packed HSL colour
+/// * `light` - The light, 128 leaving the luminance unchanged
+pub fn adjust_luminance(hsl: u16, light: i32) -> u16 {
+    let luminance = (luminance(hsl) as i32 * light / 128).clamp(2, 126);
+    (hsl & 0xff80) | luminance as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack() {
+        let hsl = pack(33, 5, 100);
+        assert_eq!((33, 5, 100), (hue(hsl), saturation(hsl), luminance(hsl)));
+    }
+
+    #[test]
+    fn test_hsl_to_rgb() {
+        assert_eq!(1, hsl_to_rgb(0, BRIGHTNESS_MIN));
+
+        // Entries of the client's palette at its darkest and brightest settings
+        for (hsl, min, max) in [
+            (0, 0x000001, 0x000001),
+            (127, 0xfefdfd, 0xfefefe),
+            (960, 0xf8180b, 0xfb3520),
+            (43968, 0x0b0ff8, 0x2026fb),
+            (65535, 0xfffcfc, 0xfffdfd),
+        ] {
+            assert_eq!(min, hsl_to_rgb(hsl, BRIGHTNESS_MIN));
+            assert_eq!(max, hsl_to_rgb(hsl, BRIGHTNESS_MAX));
+        }
+
+        // Luminance and a brighter setting both lighten a colour
+        let channels = |rgb: u32| [(rgb >> 16) & 0xff, (rgb >> 8) & 0xff, rgb & 0xff];
+        let dark = channels(hsl_to_rgb(pack(0, 7, 40), BRIGHTNESS_MIN));
+        let light = channels(hsl_to_rgb(pack(0, 7, 80), BRIGHTNESS_MIN));
+        let bright = channels(hsl_to_rgb(pack(0, 7, 40), BRIGHTNESS_MAX));
+        for i in 0..3 {
+            assert!(dark[i] <= light[i]);
+            assert!(dark[i] <= bright[i]);
+        }
+
+        // A saturated hue of 0 is red
+        let [r, g, b] = channels(hsl_to_rgb(pack(0, 7, 64), BRIGHTNESS_MIN));
+        assert!(r > g && r > b);
+    }
+
+    #[test]
+    fn test_floor_colour() {
+        let grey = FloorColour::from_rgb(0x808080);
+        assert_eq!(
+            (0, 0, 128, 1),
+            (
+                grey.hue,
+                grey.saturation,
+                grey.lightness,
+                grey.hue_multiplier
+            )
+        );
+
+        let green = FloorColour::from_rgb(0x00ff00);
+        assert_eq!(
+            (85, 255, 127),
+            (green.hue, green.saturation, green.lightness)
+        );
+        assert_eq!(pack(21, 7, 63), green.to_hsl());
+
+        // Light colours lose saturation
+        assert_eq!(pack(0, 1, 100), pack_floor(0, 255, 200));
+
+        assert_eq!(pack(3, 4, 75), adjust_luminance(pack(3, 4, 100), 96));
+        assert_eq!(pack(3, 4, 2), adjust_luminance(pack(3, 4, 1), 96));
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette = palette(BRIGHTNESS_LOW);
+        assert_eq!(65536, palette.len());
+        assert!(!palette.contains(&0));
+    }
+}
@@ -0,0 +1,281 @@
+use crate::buffer::{char_to_cp1252, cp1252_to_char};
+use osrs_bytes::{ReadExt, WriteExt};
+use std::io;
+use thiserror::Error;
+
+/// The archive holding binary files such as the Huffman table.
+pub const BINARY_ARCHIVE: u8 = 10;
+/// The name of the group holding the Huffman table.
+pub const HUFFMAN_GROUP: &str = "huffman";
+/// The longest chat message the client sends.
+pub const MAX_MESSAGE_LEN: usize = 80;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum HuffmanError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid huffman table: {0}")]
+    InvalidTable(String),
+    #[error("no code for byte {0}")]
+    UnencodableByte(u8),
+    #[error("compressed data ended before {0} bytes were decoded")]
+    UnexpectedEnd(usize),
+}
+
+/// The Huffman code used to compress chat messages.
+///
+/// The table stores the code length of every byte. Codes are assigned in
+/// byte order the way the client does, so the result is only compatible
+/// with the client when built from the table in the cache.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Huffman {
+    /// The code length of each byte, 0 for bytes without a code
+    sizes: Vec<u8>,
+    /// The code of each byte, aligned to the most significant bit
+    masks: Vec<u32>,
+    /// The decoding tree. Following a 0 bit moves to the next node, a 1 bit
+    /// jumps to the node stored at the current one, and negative nodes are
+    /// the complement of a decoded byte.
+    keys: Vec<i32>,
+}
+
+impl Huffman {
+    /// Build the code from the code length of every byte
+    ///
+    /// # Arguments
+    ///
+    /// * `sizes` - The contents of the Huffman table, one code length per byte
+    pub fn new(sizes: &[u8]) -> Result<Huffman, HuffmanError> {
+        let mut masks = vec![0; sizes.len()];
+        let mut next_codes = [0u32; 33];
+        let mut keys = vec![0i32; 8];
+        let mut next_node = 0;
+
+        for (symbol, &size) in sizes.iter().enumerate() {
+            if size == 0 {
+                continue;
+            }
+            if size > 32 {
+                return Err(HuffmanError::InvalidTable(format!(
+                    "code length {size} of byte {symbol} is over 32"
+                )));
+            }
+            let size = size as usize;
+
+            let bit = 1u32.checked_shl(32 - size as u32).unwrap_or(0);
+            let mask = next_codes[size];
+            masks[symbol] = mask;
+
+            let next = if mask & bit != 0 {
+                next_codes[size - 1]
+            } else {
+                for length in (1..size).rev() {
+                    let code = next_codes[length];
+                    if code != mask {
+                        break;
+                    }
+                    let length_bit = 1u32 << (32 - length);
+                    if code & length_bit != 0 {
+                        next_codes[length] = next_codes[length - 1];
+                        break;
+                    }
+                    next_codes[length] = code | length_bit;
+                }
+                mask | bit
+            };
+            next_codes[size] = next;
+            for code in next_codes.iter_mut().skip(size + 1) {
+                if *code == mask {
+                    *code = next;
+                }
+            }
+
+            let mut node = 0;
+            for i in 0..size {
+                if mask & (0x8000_0000 >> i) != 0 {
+                    if keys[node] == 0 {
+                        keys[node] = next_node as i32;
+                    }
+                    node = keys[node] as usize;
+                } else {
+                    node += 1;
+                }
+                if node >= keys.len() {
+                    keys.resize(keys.len() * 2, 0);
+                }
+            }
+            keys[node] = !(symbol as i32);
+            next_node = next_node.max(node + 1);
+        }
+
+        Ok(Huffman {
+            sizes: sizes.to_vec(),
+            masks,
+            keys,
+        })
+    }
+
+    /// Compress bytes
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The bytes to compress
+    pub fn compress(&self, input: &[u8]) -> Result<Vec<u8>, HuffmanError> {
+        let mut output = Vec::with_capacity(input.len());
+        let mut bit_position = 0;
+
+        for &byte in input {
+            let size = self.sizes.get(byte as usize).copied().unwrap_or(0) as usize;
+            if size == 0 {
+                return Err(HuffmanError::UnencodableByte(byte));
+            }
+            let mask = self.masks[byte as usize];
+
+            for i in 0..size {
+                if bit_position % 8 == 0 {
+                    output.push(0);
+                }
+                if mask & (0x8000_0000 >> i) != 0 {
+                    *output.last_mut().unwrap() |= 0x80 >> (bit_position % 8);
+                }
+                bit_position += 1;
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Decompress a known number of bytes
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The compressed bytes
+    /// * `len` - The number of bytes to decompress
+    pub fn decompress(&self, input: &[u8], len: usize) -> Result<Vec<u8>, HuffmanError> {
+        let mut output = Vec::with_capacity(len);
+        if len == 0 {
+            return Ok(output);
+        }
+
+        let mut node = 0;
+        for byte in input {
+            for i in 0..8 {
+                node = if byte & (0x80 >> i) != 0 {
+                    self.keys[node] as usize
+                } else {
+                    node + 1
+                };
+                let key = *self.keys.get(node).ok_or_else(|| {
+                    HuffmanError::InvalidTable(format!("node {node} out of bounds"))
+                })?;
+                if key < 0 {
+                    output.push(!key as u8);
+                    if output.len() == len {
+                        return Ok(output);
+                    }
+                    node = 0;
+                }
+            }
+        }
+
+        Err(HuffmanError::UnexpectedEnd(len))
+    }
+
+    /// Encode a chat message as the client does: the message length as a
+    /// smart followed by the compressed Windows-1252 text. Messages longer
+    /// than [`MAX_MESSAGE_LEN`] are truncated.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to encode
+    pub fn encode_message(&self, message: &str) -> Result<Vec<u8>, HuffmanError> {
+        let text: Vec<u8> = message
+            .chars()
+            .take(MAX_MESSAGE_LEN)
+            .map(char_to_cp1252)
+            .collect();
+
+        let mut buf = Vec::new();
+        buf.write_u16_smart(text.len() as u16)?;
+        buf.extend(self.compress(&text)?);
+        Ok(buf)
+    }
+
+    /// Decode a chat message written by [`Huffman::encode_message`]
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The encoded message
+    pub fn decode_message(&self, mut buf: &[u8]) -> Result<String, HuffmanError> {
+        let len = (buf.read_u16_smart()? as usize).min(MAX_MESSAGE_LEN);
+        let text = self.decompress(buf, len)?;
+        Ok(text.into_iter().map(cp1252_to_char).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    #[test]
+    fn test_code_assignment() {
+        let mut sizes = vec![0; 256];
+        sizes[b'a' as usize] = 1;
+        sizes[b'b' as usize] = 2;
+        sizes[b'c' as usize] = 2;
+        let huffman = Huffman::new(&sizes).unwrap();
+
+        // a = 0, b = 10, c = 11
+        assert_eq!(vec![0b0101_1000], huffman.compress(b"abc").unwrap());
+        assert_eq!(
+            b"abc".to_vec(),
+            huffman.decompress(&[0b0101_1000], 3).unwrap()
+        );
+        assert!(matches!(
+            huffman.compress(b"d"),
+            Err(HuffmanError::UnencodableByte(b'd'))
+        ));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut cache = Cache::open("tests/data/cache/cache-binary").unwrap();
+        let huffman = cache.huffman().unwrap();
+
+        let text = b"Selling lobsters 250gp ea! \xa3\x80";
+        let compressed = huffman.compress(text).unwrap();
+        assert!(compressed.len() < text.len());
+        assert_eq!(
+            text.to_vec(),
+            huffman.decompress(&compressed, text.len()).unwrap()
+        );
+
+        let all: Vec<u8> = (0..=255).collect();
+        let compressed = huffman.compress(&all).unwrap();
+        assert_eq!(all, huffman.decompress(&compressed, all.len()).unwrap());
+
+        assert!(matches!(
+            huffman.decompress(&compressed[..4], all.len()),
+            Err(HuffmanError::UnexpectedEnd(256))
+        ));
+    }
+
+    #[test]
+    fn test_message() {
+        let mut cache = Cache::open("tests/data/cache/cache-binary").unwrap();
+        let huffman = cache.huffman().unwrap();
+
+        let buf = huffman.encode_message("Hello w\u{f6}rld \u{20ac}").unwrap();
+        assert_eq!(13, buf[0]);
+        assert_eq!(
+            "Hello w\u{f6}rld \u{20ac}",
+            huffman.decode_message(&buf).unwrap()
+        );
+
+        let long = "a".repeat(100);
+        let buf = huffman.encode_message(&long).unwrap();
+        assert_eq!("a".repeat(80), huffman.decode_message(&buf).unwrap());
+    }
+}
@@ -0,0 +1,802 @@
+use crate::{
+    buffer::CacheReadExt,
+    definitions::{read_optional_u16, DefinitionError},
+};
+use osrs_bytes::ReadExt;
+use std::{collections::BTreeMap, io};
+use thiserror::Error;
+
+/// The archive holding interfaces, one group per interface and one file per component.
+pub const INTERFACES_ARCHIVE: u8 = 3;
+
+pub const TYPE_LAYER: u8 = 0;
+pub const TYPE_INVENTORY: u8 = 2;
+pub const TYPE_RECTANGLE: u8 = 3;
+pub const TYPE_TEXT: u8 = 4;
+pub const TYPE_GRAPHIC: u8 = 5;
+pub const TYPE_MODEL: u8 = 6;
+pub const TYPE_TEXT_INVENTORY: u8 = 7;
+pub const TYPE_TOOLTIP: u8 = 8;
+pub const TYPE_LINE: u8 = 9;
+
+/// The number of sprite slots of an if1 inventory.
+const INVENTORY_SPRITES: usize = 20;
+/// The number of actions of an if1 inventory.
+const INVENTORY_ACTIONS: usize = 5;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum InterfaceError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("definition error: {0}")]
+    Definition(#[from] DefinitionError),
+    #[error("unknown listener argument type {0}")]
+    UnknownArgumentType(u8),
+}
+
+/// An argument of a listener. Integers may be magic values the client
+/// substitutes when the listener fires, such as the mouse position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListenerArgument {
+    Int(i32),
+    String(String),
+}
+
+/// The scripts an if3 component runs on events. Each listener is the id of
+/// a clientscript followed by its arguments.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Listeners {
+    pub on_load: Option<Vec<ListenerArgument>>,
+    pub on_mouse_over: Option<Vec<ListenerArgument>>,
+    pub on_mouse_leave: Option<Vec<ListenerArgument>>,
+    pub on_target_leave: Option<Vec<ListenerArgument>>,
+    pub on_target_enter: Option<Vec<ListenerArgument>>,
+    pub on_var_transmit: Option<Vec<ListenerArgument>>,
+    pub on_inv_transmit: Option<Vec<ListenerArgument>>,
+    pub on_stat_transmit: Option<Vec<ListenerArgument>>,
+    pub on_timer: Option<Vec<ListenerArgument>>,
+    pub on_op: Option<Vec<ListenerArgument>>,
+    pub on_mouse_repeat: Option<Vec<ListenerArgument>>,
+    pub on_click: Option<Vec<ListenerArgument>>,
+    pub on_click_repeat: Option<Vec<ListenerArgument>>,
+    pub on_release: Option<Vec<ListenerArgument>>,
+    pub on_hold: Option<Vec<ListenerArgument>>,
+    pub on_drag: Option<Vec<ListenerArgument>>,
+    pub on_drag_complete: Option<Vec<ListenerArgument>>,
+    pub on_scroll_wheel: Option<Vec<ListenerArgument>>,
+    /// The varps whose changes fire `on_var_transmit`
+    pub var_transmit_triggers: Vec<i32>,
+    /// The inventories whose changes fire `on_inv_transmit`
+    pub inv_transmit_triggers: Vec<i32>,
+    /// The skills whose changes fire `on_stat_transmit`
+    pub stat_transmit_triggers: Vec<i32>,
+}
+
+/// A sprite drawn in an if1 inventory slot.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SlotSprite {
+    pub offset_x: i16,
+    pub offset_y: i16,
+    pub sprite: i32,
+}
+
+/// An interface component (widget) in either the legacy if1 format or the
+/// if3 format. Which fields are meaningful depends on `component_type`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Component {
+    /// The id of the component, `(interface << 16) | file`
+    pub id: u32,
+    pub if3: bool,
+    pub component_type: u8,
+    /// The if1 behaviour when clicked, such as 1 for "Ok" and 6 for "Continue"
+    pub menu_type: u8,
+    pub content_type: u16,
+    pub x: i16,
+    pub y: i16,
+    pub width: i32,
+    /// The height, which may be negative for lines
+    pub height: i32,
+    pub width_mode: i8,
+    pub height_mode: i8,
+    pub x_mode: i8,
+    pub y_mode: i8,
+    /// The id of the parent component, or `None` for a root
+    pub parent: Option<u32>,
+    pub hidden: bool,
+    /// The if1 component highlighted while this one is hovered
+    pub hovered_sibling: Option<u16>,
+    pub opacity: u8,
+
+    /// The if1 conditions that select the alternate text, colour, sprite or
+    /// model, as `(operator, value)` pairs compared against `if1_scripts`
+    pub conditions: Vec<(u8, u16)>,
+    /// The if1 (cs1) scripts, as raw instruction words
+    pub if1_scripts: Vec<Vec<Option<u16>>>,
+
+    pub scroll_width: u16,
+    pub scroll_height: u16,
+    pub no_click_through: bool,
+
+    pub font: Option<u16>,
+    pub text: String,
+    pub alternate_text: String,
+    pub line_height: u8,
+    pub x_text_alignment: u8,
+    pub y_text_alignment: u8,
+    pub text_shadowed: bool,
+    pub colour: u32,
+    pub alternate_colour: u32,
+    pub hovered_colour: u32,
+    pub alternate_hovered_colour: u32,
+    pub filled: bool,
+
+    pub sprite: i32,
+    pub alternate_sprite: i32,
+    /// The angle the sprite is drawn rotated by
+    pub angle: u16,
+    pub sprite_tiling: bool,
+    pub border_type: u8,
+    pub shadow_colour: u32,
+    pub flipped_vertically: bool,
+    pub flipped_horizontally: bool,
+
+    pub model_type: u8,
+    pub model: Option<u16>,
+    pub alternate_model_type: u8,
+    pub alternate_model: Option<u16>,
+    pub animation: Option<u16>,
+    pub alternate_animation: Option<u16>,
+    pub model_offset_x: i16,
+    pub model_offset_y: i16,
+    pub rotation_x: u16,
+    pub rotation_y: u16,
+    pub rotation_z: u16,
+    pub model_zoom: u16,
+    pub orthographic: bool,
+    pub model_height_override: u16,
+
+    pub line_width: u8,
+    pub line_direction: bool,
+
+    pub x_pitch: i16,
+    pub y_pitch: i16,
+    /// The sprites of an if1 inventory's first slots
+    pub slot_sprites: Vec<Option<SlotSprite>>,
+    pub inventory_actions: Vec<Option<String>>,
+
+    /// The packed flags of which ops, targets and drags are enabled
+    pub click_mask: u32,
+    pub name: String,
+    pub actions: Vec<String>,
+    pub target_verb: String,
+    pub spell_name: String,
+    pub tooltip: String,
+    pub drag_dead_zone: u8,
+    pub drag_dead_time: u8,
+    pub drag_render_behaviour: bool,
+    pub listeners: Listeners,
+}
+
+impl Default for Component {
+    fn default() -> Self {
+        Component {
+            id: 0,
+            if3: false,
+            component_type: 0,
+            menu_type: 0,
+            content_type: 0,
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            width_mode: 0,
+            height_mode: 0,
+            x_mode: 0,
+            y_mode: 0,
+            parent: None,
+            hidden: false,
+            hovered_sibling: None,
+            opacity: 0,
+            conditions: Vec::new(),
+            if1_scripts: Vec::new(),
+            scroll_width: 0,
+            scroll_height: 0,
+            no_click_through: false,
+            font: None,
+            text: String::new(),
+            alternate_text: String::new(),
+            line_height: 0,
+            x_text_alignment: 0,
+            y_text_alignment: 0,
+            text_shadowed: false,
+            colour: 0,
+            alternate_colour: 0,
+            hovered_colour: 0,
+            alternate_hovered_colour: 0,
+            filled: false,
+            sprite: -1,
+            alternate_sprite: -1,
+            angle: 0,
+            sprite_tiling: false,
+            border_type: 0,
+            shadow_colour: 0,
+            flipped_vertically: false,
+            flipped_horizontally: false,
+            model_type: 1,
+            model: None,
+            alternate_model_type: 1,
+            alternate_model: None,
+            animation: None,
+            alternate_animation: None,
+            model_offset_x: 0,
+            model_offset_y: 0,
+            rotation_x: 0,
+            rotation_y: 0,
+            rotation_z: 0,
+            model_zoom: 100,
+            orthographic: false,
+            model_height_override: 0,
+            line_width: 1,
+            line_direction: false,
+            x_pitch: 0,
+            y_pitch: 0,
+            slot_sprites: Vec::new(),
+            inventory_actions: Vec::new(),
+            click_mask: 0,
+            name: String::new(),
+            actions: Vec::new(),
+            target_verb: String::new(),
+            spell_name: String::new(),
+            tooltip: "Ok".to_string(),
+            drag_dead_zone: 0,
+            drag_dead_time: 0,
+            drag_render_behaviour: false,
+            listeners: Listeners::default(),
+        }
+    }
+}
+
+impl Component {
+    /// Decode a component, detecting its format from the first byte
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the component, `(interface << 16) | file`
+    /// * `buf` - The contents of the component's file
+    pub fn decode(id: u32, buf: &[u8]) -> Result<Component, InterfaceError> {
+        let mut component = Component {
+            id,
+            ..Default::default()
+        };
+        match buf.first() {
+            Some(0xff) => component.decode_if3(&mut &buf[1..])?,
+            _ => component.decode_if1(&mut &buf[..])?,
+        }
+        Ok(component)
+    }
+
+    /// Get the file of the component within its interface
+    pub fn file(&self) -> u16 {
+        (self.id & 0xffff) as u16
+    }
+
+    fn read_parent(&self, buf: &mut &[u8]) -> Result<Option<u32>, InterfaceError> {
+        Ok(read_optional_u16(buf)?.map(|file| (self.id & !0xffff) | file as u32))
+    }
+
+    fn decode_if1(&mut self, buf: &mut &[u8]) -> Result<(), InterfaceError> {
+        self.component_type = buf.read_u8()?;
+        self.menu_type = buf.read_u8()?;
+        self.content_type = buf.read_u16()?;
+        self.x = buf.read_i16()?;
+        self.y = buf.read_i16()?;
+        self.width = buf.read_u16()? as i32;
+        self.height = buf.read_u16()? as i32;
+        self.opacity = buf.read_u8()?;
+        self.parent = self.read_parent(buf)?;
+        self.hovered_sibling = read_optional_u16(buf)?;
+
+        let len = buf.read_u8()?;
+        for _ in 0..len {
+            self.conditions.push((buf.read_u8()?, buf.read_u16()?));
+        }
+
+        let len = buf.read_u8()?;
+        for _ in 0..len {
+            let script_len = buf.read_u16()?;
+            let script = (0..script_len)
+                .map(|_| read_optional_u16(buf))
+                .collect::<Result<_, _>>()?;
+            self.if1_scripts.push(script);
+        }
+
+        let component_type = self.component_type;
+        if component_type == TYPE_LAYER {
+            self.scroll_height = buf.read_u16()?;
+            self.hidden = buf.read_u8()? == 1;
+        }
+
+        if component_type == 1 {
+            buf.read_u16()?;
+            buf.read_u8()?;
+        }
+
+        if component_type == TYPE_INVENTORY {
+            // Swappable, usable, usable with and replaceable
+            for flag in [1 << 28, 1 << 30, 1 << 31, 1 << 29] {
+                if buf.read_u8()? == 1 {
+                    self.click_mask |= flag;
+                }
+            }
+            self.x_pitch = buf.read_u8()? as i16;
+            self.y_pitch = buf.read_u8()? as i16;
+
+            for _ in 0..INVENTORY_SPRITES {
+                let slot = match buf.read_u8()? {
+                    1 => Some(SlotSprite {
+                        offset_x: buf.read_i16()?,
+                        offset_y: buf.read_i16()?,
+                        sprite: buf.read_i32()?,
+                    }),
+                    _ => None,
+                };
+                self.slot_sprites.push(slot);
+            }
+            self.read_inventory_actions(buf)?;
+        }
+
+        if component_type == TYPE_RECTANGLE {
+            self.filled = buf.read_u8()? == 1;
+        }
+
+        if component_type == TYPE_TEXT || component_type == 1 {
+            self.x_text_alignment = buf.read_u8()?;
+            self.y_text_alignment = buf.read_u8()?;
+            self.line_height = buf.read_u8()?;
+            self.font = read_optional_u16(buf)?;
+            self.text_shadowed = buf.read_u8()? == 1;
+        }
+
+        if component_type == TYPE_TEXT {
+            self.text = buf.read_cp1252_string()?;
+            self.alternate_text = buf.read_cp1252_string()?;
+        }
+
+        if component_type == 1 || component_type == TYPE_RECTANGLE || component_type == TYPE_TEXT {
+            self.colour = buf.read_u32()?;
+        }
+
+        if component_type == TYPE_RECTANGLE || component_type == TYPE_TEXT {
+            self.alternate_colour = buf.read_u32()?;
+            self.hovered_colour = buf.read_u32()?;
+            self.alternate_hovered_colour = buf.read_u32()?;
+        }
+
+        if component_type == TYPE_GRAPHIC {
+            self.sprite = buf.read_i32()?;
+            self.alternate_sprite = buf.read_i32()?;
+        }
+
+        if component_type == TYPE_MODEL {
+            self.model = read_optional_u16(buf)?;
+            self.alternate_model = read_optional_u16(buf)?;
+            self.animation = read_optional_u16(buf)?;
+            self.alternate_animation = read_optional_u16(buf)?;
+            self.model_zoom = buf.read_u16()?;
+            self.rotation_x = buf.read_u16()?;
+            self.rotation_z = buf.read_u16()?;
+        }
+
+        if component_type == TYPE_TEXT_INVENTORY {
+            self.x_text_alignment = buf.read_u8()?;
+            self.font = read_optional_u16(buf)?;
+            self.text_shadowed = buf.read_u8()? == 1;
+            self.colour = buf.read_u32()?;
+            self.x_pitch = buf.read_i16()?;
+            self.y_pitch = buf.read_i16()?;
+            if buf.read_u8()? == 1 {
+                self.click_mask |= 1 << 30;
+            }
+            self.read_inventory_actions(buf)?;
+        }
+
+        if component_type == TYPE_TOOLTIP {
+            self.text = buf.read_cp1252_string()?;
+        }
+
+        if self.menu_type == 2 || component_type == TYPE_INVENTORY {
+            self.target_verb = buf.read_cp1252_string()?;
+            self.spell_name = buf.read_cp1252_string()?;
+            self.click_mask |= (buf.read_u16()? as u32 & 0x3f) << 11;
+        }
+
+        if matches!(self.menu_type, 1 | 4 | 5 | 6) {
+            self.tooltip = buf.read_cp1252_string()?;
+            if self.tooltip.is_empty() {
+                self.tooltip = match self.menu_type {
+                    1 => "Ok",
+                    6 => "Continue",
+                    _ => "Select",
+                }
+                .to_string();
+            }
+        }
+
+        match self.menu_type {
+            1 | 4 | 5 => self.click_mask |= 1 << 22,
+            6 => self.click_mask |= 1,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn read_inventory_actions(&mut self, buf: &mut &[u8]) -> io::Result<()> {
+        for i in 0..INVENTORY_ACTIONS {
+            let action = buf.read_cp1252_string()?;
+            if action.is_empty() {
+                self.inventory_actions.push(None);
+            } else {
+                self.inventory_actions.push(Some(action));
+                self.click_mask |= 1 << (i + 23);
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_if3(&mut self, buf: &mut &[u8]) -> Result<(), InterfaceError> {
+        self.if3 = true;
+        self.component_type = buf.read_u8()?;
+        self.content_type = buf.read_u16()?;
+        self.x = buf.read_i16()?;
+        self.y = buf.read_i16()?;
+        self.width = buf.read_u16()? as i32;
+        self.height = match self.component_type {
+            TYPE_LINE => buf.read_i16()? as i32,
+            _ => buf.read_u16()? as i32,
+        };
+        self.width_mode = buf.read_i8()?;
+        self.height_mode = buf.read_i8()?;
+        self.x_mode = buf.read_i8()?;
+        self.y_mode = buf.read_i8()?;
+        self.parent = self.read_parent(buf)?;
+        self.hidden = buf.read_u8()? == 1;
+
+        match self.component_type {
+            TYPE_LAYER => {
+                self.scroll_width = buf.read_u16()?;
+                self.scroll_height = buf.read_u16()?;
+                self.no_click_through = buf.read_u8()? == 1;
+            }
+            TYPE_GRAPHIC => {
+                self.sprite = buf.read_i32()?;
+                self.angle = buf.read_u16()?;
+                self.sprite_tiling = buf.read_u8()? == 1;
+                self.opacity = buf.read_u8()?;
+                self.border_type = buf.read_u8()?;
+                self.shadow_colour = buf.read_u32()?;
+                self.flipped_vertically = buf.read_u8()? == 1;
+                self.flipped_horizontally = buf.read_u8()? == 1;
+            }
+            TYPE_MODEL => {
+                self.model = read_optional_u16(buf)?;
+                self.model_offset_x = buf.read_i16()?;
+                self.model_offset_y = buf.read_i16()?;
+                self.rotation_x = buf.read_u16()?;
+                self.rotation_z = buf.read_u16()?;
+                self.rotation_y = buf.read_u16()?;
+                self.model_zoom = buf.read_u16()?;
+                self.animation = read_optional_u16(buf)?;
+                self.orthographic = buf.read_u8()? == 1;
+                // Unused by the client
+                buf.read_u16()?;
+                if self.width_mode != 0 {
+                    self.model_height_override = buf.read_u16()?;
+                }
+                if self.height_mode != 0 {
+                    buf.read_u16()?;
+                }
+            }
+            TYPE_TEXT => {
+                self.font = read_optional_u16(buf)?;
+                self.text = buf.read_cp1252_string()?;
+                self.line_height = buf.read_u8()?;
+                self.x_text_alignment = buf.read_u8()?;
+                self.y_text_alignment = buf.read_u8()?;
+                self.text_shadowed = buf.read_u8()? == 1;
+                self.colour = buf.read_u32()?;
+            }
+            TYPE_RECTANGLE => {
+                self.colour = buf.read_u32()?;
+                self.filled = buf.read_u8()? == 1;
+                self.opacity = buf.read_u8()?;
+            }
+            TYPE_LINE => {
+                self.line_width = buf.read_u8()?;
+                self.colour = buf.read_u32()?;
+                self.line_direction = buf.read_u8()? == 1;
+            }
+            _ => {}
+        }
+
+        self.click_mask = buf.read_u24()?;
+        self.name = buf.read_cp1252_string()?;
+        let len = buf.read_u8()?;
+        for _ in 0..len {
+            self.actions.push(buf.read_cp1252_string()?);
+        }
+        self.drag_dead_zone = buf.read_u8()?;
+        self.drag_dead_time = buf.read_u8()?;
+        self.drag_render_behaviour = buf.read_u8()? == 1;
+        self.target_verb = buf.read_cp1252_string()?;
+
+        let listeners = &mut self.listeners;
+        for listener in [
+            &mut listeners.on_load,
+            &mut listeners.on_mouse_over,
+            &mut listeners.on_mouse_leave,
+            &mut listeners.on_target_leave,
+            &mut listeners.on_target_enter,
+            &mut listeners.on_var_transmit,
+            &mut listeners.on_inv_transmit,
+            &mut listeners.on_stat_transmit,
+            &mut listeners.on_timer,
+            &mut listeners.on_op,
+            &mut listeners.on_mouse_repeat,
+            &mut listeners.on_click,
+            &mut listeners.on_click_repeat,
+            &mut listeners.on_release,
+            &mut listeners.on_hold,
+            &mut listeners.on_drag,
+            &mut listeners.on_drag_complete,
+            &mut listeners.on_scroll_wheel,
+        ] {
+            *listener = read_listener(buf)?;
+        }
+        for triggers in [
+            &mut listeners.var_transmit_triggers,
+            &mut listeners.inv_transmit_triggers,
+            &mut listeners.stat_transmit_triggers,
+        ] {
+            let len = buf.read_u8()?;
+            for _ in 0..len {
+                triggers.push(buf.read_i32()?);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The components of an interface, keyed by file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Interface {
+    pub id: u16,
+    pub components: BTreeMap<u16, Component>,
+}
+
+impl Interface {
+    /// Get the components without a parent, which are attached to whatever
+    /// the interface is opened in
+    pub fn roots(&self) -> impl Iterator<Item = &Component> {
+        self.components
+            .values()
+            .filter(|component| component.parent.is_none())
+    }
+
+    /// Get the direct children of a component
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file of the parent component
+    pub fn children(&self, file: u16) -> impl Iterator<Item = &Component> {
+        let id = ((self.id as u32) << 16) | file as u32;
+        self.components
+            .values()
+            .filter(move |component| component.parent == Some(id))
+    }
+}
+
+fn read_listener(buf: &mut &[u8]) -> Result<Option<Vec<ListenerArgument>>, InterfaceError> {
+    let len = buf.read_u8()?;
+    if len == 0 {
+        return Ok(None);
+    }
+
+    let mut arguments = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        arguments.push(match buf.read_u8()? {
+            0 => ListenerArgument::Int(buf.read_i32()?),
+            1 => ListenerArgument::String(buf.read_cp1252_string()?),
+            argument_type => return Err(InterfaceError::UnknownArgumentType(argument_type)),
+        });
+    }
+    Ok(Some(arguments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    fn open(id: u16) -> Interface {
+        let mut cache = Cache::open("tests/data/cache/cache-interfaces").unwrap();
+        cache.interface(id).unwrap()
+    }
+
+    #[test]
+    fn test_decode_if1() {
+        let interface = open(0);
+        assert_eq!(4, interface.components.len());
+
+        let layer = &interface.components[&0];
+        assert!(!layer.if3);
+        assert_eq!(
+            (TYPE_LAYER, None, 200),
+            (layer.component_type, layer.parent, layer.scroll_height)
+        );
+
+        let text = &interface.components[&1];
+        assert_eq!(TYPE_TEXT, text.component_type);
+        assert_eq!(Some(0), text.parent);
+        assert_eq!(vec![(1, 5)], text.conditions);
+        assert_eq!(vec![vec![Some(5), Some(300), None]], text.if1_scripts);
+        assert_eq!("Hello", text.text);
+        assert_eq!("Goodbye", text.alternate_text);
+        assert_eq!(Some(495), text.font);
+        assert_eq!((0xffff00, 0x00ff00), (text.colour, text.alternate_colour));
+        assert_eq!("Continue", text.tooltip);
+        assert_eq!(1, text.click_mask);
+
+        let inventory = &interface.components[&2];
+        assert_eq!(TYPE_INVENTORY, inventory.component_type);
+        assert_eq!(
+            (1 << 28) | (1 << 23) | (1 << 25) | (3 << 11),
+            inventory.click_mask
+        );
+        assert_eq!((10, 4), (inventory.x_pitch, inventory.y_pitch));
+        assert_eq!(20, inventory.slot_sprites.len());
+        assert_eq!(
+            Some(SlotSprite {
+                offset_x: -2,
+                offset_y: 3,
+                sprite: 156
+            }),
+            inventory.slot_sprites[1]
+        );
+        assert_eq!(
+            vec![
+                Some("Eat".to_string()),
+                None,
+                Some("Drop".to_string()),
+                None,
+                None
+            ],
+            inventory.inventory_actions
+        );
+        assert_eq!(
+            ("Use".to_string(), "Spell".to_string()),
+            (inventory.target_verb.clone(), inventory.spell_name.clone())
+        );
+
+        let model = &interface.components[&3];
+        assert_eq!(
+            (Some(2000), None, Some(808)),
+            (model.model, model.alternate_model, model.animation)
+        );
+        assert_eq!(
+            (1000, 512, 128),
+            (model.model_zoom, model.rotation_x, model.rotation_z)
+        );
+        assert_eq!("Select", model.tooltip);
+        assert_eq!(1 << 22, model.click_mask);
+    }
+
+    #[test]
+    fn test_decode_if3() {
+        let interface = open(1);
+        assert_eq!(4, interface.components.len());
+        assert_eq!(
+            vec![0],
+            interface.roots().map(Component::file).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![1, 2],
+            interface
+                .children(0)
+                .map(Component::file)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![3],
+            interface
+                .children(2)
+                .map(Component::file)
+                .collect::<Vec<_>>()
+        );
+
+        let layer = &interface.components[&0];
+        assert!(layer.if3);
+        assert_eq!((1 << 16, 512, 334), (layer.id, layer.width, layer.height));
+        assert_eq!((1, 1), (layer.width_mode, layer.height_mode));
+        assert_eq!(
+            (0, 800, true),
+            (
+                layer.scroll_width,
+                layer.scroll_height,
+                layer.no_click_through
+            )
+        );
+        assert_eq!(
+            Some(vec![
+                ListenerArgument::Int(3000),
+                ListenerArgument::Int(-2147483645),
+                ListenerArgument::String("x".to_string())
+            ]),
+            layer.listeners.on_load
+        );
+        assert_eq!(vec![281, 1055], layer.listeners.var_transmit_triggers);
+        assert_eq!(None, layer.listeners.on_op);
+
+        let graphic = &interface.components[&1];
+        assert_eq!(
+            (TYPE_GRAPHIC, 1200, 40),
+            (graphic.component_type, graphic.sprite, graphic.opacity)
+        );
+        assert!(
+            graphic.sprite_tiling && graphic.flipped_horizontally && !graphic.flipped_vertically
+        );
+        assert_eq!("Bank", graphic.name);
+        assert_eq!(vec!["Withdraw-1", "", "Examine"], graphic.actions);
+        assert_eq!(0x3e, graphic.click_mask);
+        assert_eq!(
+            Some(vec![ListenerArgument::Int(4000)]),
+            graphic.listeners.on_op
+        );
+
+        let model = &interface.components[&2];
+        assert_eq!(
+            (Some(5000), -4, 7),
+            (model.model, model.model_offset_x, model.model_offset_y)
+        );
+        assert_eq!(
+            (100, 200, 300, 900),
+            (
+                model.rotation_x,
+                model.rotation_z,
+                model.rotation_y,
+                model.model_zoom
+            )
+        );
+        assert!(model.orthographic);
+        assert_eq!(60, model.model_height_override);
+
+        let line = &interface.components[&3];
+        assert_eq!(
+            (TYPE_LINE, -20, 3, true),
+            (
+                line.component_type,
+                line.height,
+                line.line_width,
+                line.line_direction
+            )
+        );
+    }
+
+    #[test]
+    fn test_unknown_argument_type() {
+        let mut buf = vec![0xff, TYPE_LAYER];
+        buf.extend([0; 14]);
+        buf.extend([0xff, 0xff, 0]);
+        buf.extend([0; 5]);
+        buf.extend([0; 9]);
+        buf.extend([1, 2]);
+        assert!(matches!(
+            Component::decode(0, &buf),
+            Err(InterfaceError::UnknownArgumentType(2))
+        ));
+    }
+}
@@ -1,12 +1,17 @@
-use crate::xtea::xtea_decipher;
+use crate::{xtea::xtea_decipher, MAX_GROUP_SIZE};
 use bzip2::read::BzDecoder;
 use flate2::bufread::GzDecoder;
 use lzma_rs::{decompress, lzma_decompress_with_options};
 use osrs_bytes::ReadExt;
-use std::io::{Cursor, Read};
+use rayon::prelude::*;
+use std::{
+    cmp,
+    io::{Cursor, Read},
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Js5CompressionError {
     #[error("missing header")]
     MissingHeader,
@@ -28,9 +33,28 @@ const COMPRESSION_TYPE_NONE: u8 = 0;
 const COMPRESSION_TYPE_BZIP: u8 = 1;
 const COMPRESSION_TYPE_GZIP: u8 = 2;
 const COMPRESSION_TYPE_LZMA: u8 = 3;
+
+const ZERO_KEY: [u32; 4] = [0; 4];
+const XTEA_BLOCK_SIZE: usize = 8;
+
+const BZIP2_BLOCK_MAGIC: [u8; 6] = [0x31, 0x41, 0x59, 0x26, 0x53, 0x59];
+const BZIP2_END_OF_STREAM_MAGIC: [u8; 6] = [0x17, 0x72, 0x45, 0x38, 0x50, 0x90];
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const GZIP_METHOD_DEFLATE: u8 = 8;
+const GZIP_RESERVED_FLAGS: u8 = 0xE0;
+const LZMA_MAX_PROPERTIES: u8 = 9 * 5 * 5;
+
+/// Decompresses (and optionally decrypts) JS5 containers.
+#[non_exhaustive]
 pub struct Js5Compression {}
 
 impl Js5Compression {
+    /// Uncompress a container
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The compressed container, including its header
+    /// * `xtea_keys` - The XTEA keys to use for decryption. If None, the container will not be decrypted
     pub fn uncompress<T: AsRef<[u8]>>(
         input: T,
         xtea_keys: Option<[u32; 4]>,
@@ -54,11 +78,7 @@ impl Js5Compression {
                 return Err(Js5CompressionError::DataTruncated);
             }
 
-            if let Some(xtea_keys) = xtea_keys {
-                return Ok(xtea_decipher(input_ref, &xtea_keys));
-            }
-
-            return Ok(input_ref[..len as usize].to_vec());
+            return Ok(Self::decrypt(input_ref, len, xtea_keys));
         }
 
         let len_with_uncompressed_len = len + 4;
@@ -97,15 +117,170 @@ impl Js5Compression {
         Ok(decomp)
     }
 
+    /// Check whether a key is likely to be the one a container was encrypted with
+    ///
+    /// The decrypted header is checked for a sane uncompressed length, the magic
+    /// of the compression format and, where the format stores it, the decoded
+    /// length. The container is never decompressed. The zero key means no
+    /// encryption, and uncompressed containers carry no structure to check, so
+    /// every key is reported as valid for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The compressed container, including its header
+    /// * `key` - The XTEA key to check
+    pub fn is_key_valid<T: AsRef<[u8]>>(
+        input: T,
+        key: [u32; 4],
+    ) -> Result<bool, Js5CompressionError> {
+        let header = ContainerHeader::read(input.as_ref())?;
+        Ok(header.is_key_valid(input.as_ref(), key))
+    }
+
+    /// Find the key a container was encrypted with among a list of candidates
+    ///
+    /// The candidates are checked in parallel with [`Js5Compression::is_key_valid`],
+    /// and the first valid one in list order is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The compressed container, including its header
+    /// * `candidates` - The XTEA keys to try
+    pub fn find_key<T: AsRef<[u8]>>(
+        input: T,
+        candidates: &[[u32; 4]],
+    ) -> Result<Option<[u32; 4]>, Js5CompressionError> {
+        let input = input.as_ref();
+        let header = ContainerHeader::read(input)?;
+
+        Ok(candidates
+            .par_iter()
+            .find_first(|key| header.is_key_valid(input, **key))
+            .copied())
+    }
+
     fn decrypt<T: AsRef<[u8]>>(input: T, len: i32, xtea_keys: Option<[u32; 4]>) -> Vec<u8> {
-        if let Some(xtea_keys) = xtea_keys {
-            xtea_decipher(input.as_ref(), &xtea_keys)
+        let input = &input.as_ref()[..len as usize];
+        match xtea_keys {
+            Some(xtea_keys) if xtea_keys != ZERO_KEY => xtea_decipher(input, &xtea_keys),
+            _ => input.to_vec(),
+        }
+    }
+}
+
+/// The unencrypted part of a container's header.
+struct ContainerHeader {
+    type_id: u8,
+    len: usize,
+}
+
+impl ContainerHeader {
+    fn read(mut input: &[u8]) -> Result<ContainerHeader, Js5CompressionError> {
+        if input.len() < 5 {
+            return Err(Js5CompressionError::MissingHeader);
+        }
+
+        let type_id = input.read_u8()?;
+        if type_id > COMPRESSION_TYPE_LZMA {
+            return Err(Js5CompressionError::UnknownCompressionType(type_id));
+        }
+
+        let len = input.read_i32()?;
+        if len < 0 {
+            return Err(Js5CompressionError::NegativeLength(len));
+        }
+
+        let len = if type_id == COMPRESSION_TYPE_NONE {
+            len as usize
         } else {
-            input.as_ref().to_vec()[..len as usize].to_vec()
+            len as usize + 4
+        };
+        if input.len() < len {
+            return Err(Js5CompressionError::DataTruncated);
+        }
+
+        Ok(ContainerHeader { type_id, len })
+    }
+
+    fn is_key_valid(&self, input: &[u8], key: [u32; 4]) -> bool {
+        if self.type_id == COMPRESSION_TYPE_NONE {
+            return true;
+        }
+
+        let payload = &input[5..5 + self.len];
+        let compressed_len = self.len - 4;
+
+        let head = decrypt_window(payload, 0, cmp::min(self.len, 16), key);
+        let mut head_ref = head.as_slice();
+        let uncompressed_len = match head_ref.read_i32() {
+            Ok(len) => len,
+            Err(_) => return false,
+        };
+        if uncompressed_len < 0 || uncompressed_len as usize > MAX_GROUP_SIZE {
+            return false;
+        }
+
+        match self.type_id {
+            COMPRESSION_TYPE_BZIP => {
+                head_ref.starts_with(&BZIP2_BLOCK_MAGIC)
+                    || (uncompressed_len == 0 && head_ref.starts_with(&BZIP2_END_OF_STREAM_MAGIC))
+            }
+            COMPRESSION_TYPE_GZIP => {
+                if head_ref.len() < 4
+                    || head_ref[..2] != GZIP_MAGIC
+                    || head_ref[2] != GZIP_METHOD_DEFLATE
+                    || head_ref[3] & GZIP_RESERVED_FLAGS != 0
+                    || compressed_len < 8
+                {
+                    return false;
+                }
+
+                // The trailer of a gzip stream ends with the decoded length
+                let trailer = decrypt_window(payload, self.len - 4, self.len, key);
+                let decoded_len =
+                    u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+                decoded_len == uncompressed_len as u32
+            }
+            COMPRESSION_TYPE_LZMA => {
+                if head_ref.len() < 6 || head_ref[0] >= LZMA_MAX_PROPERTIES {
+                    return false;
+                }
+
+                // The range coder's initial byte, following the properties, is always zero
+                let dict_size =
+                    i32::from_le_bytes([head_ref[1], head_ref[2], head_ref[3], head_ref[4]]);
+                dict_size >= 0 && head_ref[5] == 0
+            }
+            _ => false,
         }
     }
 }
 
+/// Decrypt the bytes between `start` and `end` of an encrypted payload,
+/// deciphering only the blocks covering them. Trailing bytes that do not
+/// fill a whole block are not encrypted.
+fn decrypt_window(payload: &[u8], start: usize, end: usize, key: [u32; 4]) -> Vec<u8> {
+    if key == ZERO_KEY {
+        return payload[start..end].to_vec();
+    }
+
+    let encrypted_end = payload.len() / XTEA_BLOCK_SIZE * XTEA_BLOCK_SIZE;
+    let block_start = start / XTEA_BLOCK_SIZE * XTEA_BLOCK_SIZE;
+    let block_end = cmp::min(
+        end.div_ceil(XTEA_BLOCK_SIZE) * XTEA_BLOCK_SIZE,
+        encrypted_end,
+    );
+
+    let mut window = if block_start < block_end {
+        xtea_decipher(&payload[block_start..block_end], &key)
+    } else {
+        Vec::new()
+    };
+    window.extend_from_slice(&payload[block_start + window.len()..cmp::max(end, block_start)]);
+
+    window[start - block_start..end - block_start].to_vec()
+}
+
 // Decompress using bzip2
 fn decompress_archive_bzip2<T: AsRef<[u8]>>(
     archive_data: T,
@@ -369,6 +544,104 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_key_valid_encrypted() {
+        for name in [
+            "gzip-encrypted.dat",
+            "bzip2-encrypted.dat",
+            "lzma-encrypted.dat",
+        ] {
+            read(name, |data| {
+                assert!(Js5Compression::is_key_valid(&data, KEY).unwrap(), "{name}");
+                assert!(
+                    !Js5Compression::is_key_valid(&data, INVALID_KEY).unwrap(),
+                    "{name}"
+                );
+                assert!(
+                    !Js5Compression::is_key_valid(&data, ZERO_KEY).unwrap(),
+                    "{name}"
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn test_key_valid_unencrypted() {
+        for name in ["gzip.dat", "bzip2.dat", "lzma.dat"] {
+            read(name, |data| {
+                assert!(
+                    Js5Compression::is_key_valid(&data, ZERO_KEY).unwrap(),
+                    "{name}"
+                );
+                assert!(!Js5Compression::is_key_valid(&data, KEY).unwrap(), "{name}");
+            });
+        }
+    }
+
+    #[test]
+    fn test_key_valid_uncompressed() {
+        read("none-encrypted.dat", |data| {
+            assert!(Js5Compression::is_key_valid(&data, KEY).unwrap());
+            assert!(Js5Compression::is_key_valid(&data, ZERO_KEY).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_key_valid_invalid_header() {
+        read("invalid-type.dat", |data| {
+            assert!(matches!(
+                Js5Compression::is_key_valid(data, KEY),
+                Err(Js5CompressionError::UnknownCompressionType(4))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_find_key() {
+        let mut candidates: Vec<[u32; 4]> = (0..5000_u32)
+            .map(|i| [i, i.wrapping_mul(0x9E3779B9), !i, i << 16])
+            .collect();
+        candidates.push(ZERO_KEY);
+        candidates.push(KEY);
+
+        for name in [
+            "gzip-encrypted.dat",
+            "bzip2-encrypted.dat",
+            "lzma-encrypted.dat",
+        ] {
+            read(name, |data| {
+                assert_eq!(
+                    Some(KEY),
+                    Js5Compression::find_key(&data, &candidates).unwrap(),
+                    "{name}"
+                );
+            });
+        }
+
+        read("gzip-encrypted.dat", |data| {
+            assert_eq!(
+                None,
+                Js5Compression::find_key(&data, &[ZERO_KEY, INVALID_KEY]).unwrap()
+            );
+        });
+        read("gzip.dat", |data| {
+            assert_eq!(
+                Some(ZERO_KEY),
+                Js5Compression::find_key(&data, &[KEY, ZERO_KEY]).unwrap()
+            );
+        });
+    }
+
+    #[test]
+    fn test_uncompress_zero_key() {
+        read("gzip.dat", |data| {
+            assert_eq!(
+                "OpenRS2".as_bytes(),
+                Js5Compression::uncompress(data, Some(ZERO_KEY)).unwrap()
+            );
+        });
+    }
+
     fn read<P, F>(p: P, f: F)
     where
         P: AsRef<Path>,
@@ -381,5 +654,5 @@ mod tests {
     }
 
     const KEY: [u32; 4] = [0x00112233, 0x44556677, 0x8899AABB, 0xCCDDEEFF];
-    //const INVALID_KEY: [u32; 4] = [0x01234567, 0x89ABCDEF, 0x01234567, 0x89ABCDEF];
+    const INVALID_KEY: [u32; 4] = [0x01234567, 0x89ABCDEF, 0x01234567, 0x89ABCDEF];
 }
@@ -1,11 +1,12 @@
 use osrs_bytes::ReadExt;
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     io::Read,
 };
 use thiserror::Error;
 
-#[allow(dead_code)]
+/// The protocol (format) of a [`Js5Index`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Js5Protocol {
     Original = 5,
     Versioned = 6,
@@ -19,12 +20,16 @@ enum Js5IndexFlags {
     UncompressedChecksums = 0x8,
 }
 
+/// A file within a [`Js5IndexEntry`].
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub struct Js5IndexFile {
     pub name_hash: i32,
 }
 
+/// A group within a [`Js5Index`].
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub struct Js5IndexEntry {
     pub name_hash: i32,
     pub version: u32,
@@ -35,17 +40,76 @@ pub struct Js5IndexEntry {
     pub digest: Vec<u8>,
     pub capacity: u32,
     pub files: BTreeMap<u32, Js5IndexFile>,
+    pub name_hash_table: NameHashTable,
 }
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Js5IndexError {
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     #[error("failed getting named hash table entry")]
     NamedHashTableEntry,
+    #[error("name hash {0} is shared by more than one entry")]
+    NameHashCollision(u32),
 }
 
+/// Maps djb2 name hashes to the ids of the groups or files carrying them.
+///
+/// Hashes shared by more than one id are remembered so that looking them up
+/// fails instead of silently returning whichever id was inserted last.
+#[derive(Debug, Default, PartialEq)]
+pub struct NameHashTable {
+    ids: HashMap<u32, u32>,
+    collisions: HashSet<u32>,
+}
+
+impl NameHashTable {
+    /// Register the id carrying the given name hash
+    pub fn insert(&mut self, name_hash: u32, id: u32) {
+        if let Some(existing) = self.ids.insert(name_hash, id) {
+            if existing != id {
+                self.collisions.insert(name_hash);
+            }
+        }
+    }
+
+    /// Get the id carrying the given name hash
+    pub fn get(&self, name_hash: u32) -> Result<u32, Js5IndexError> {
+        if self.collisions.contains(&name_hash) {
+            return Err(Js5IndexError::NameHashCollision(name_hash));
+        }
+
+        self.ids
+            .get(&name_hash)
+            .ok_or(Js5IndexError::NamedHashTableEntry)
+            .copied()
+    }
+
+    /// Get the number of distinct name hashes in the table
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Check whether the table is empty
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+impl<const N: usize> From<[(u32, u32); N]> for NameHashTable {
+    fn from(entries: [(u32, u32); N]) -> Self {
+        let mut table = NameHashTable::default();
+        for (name_hash, id) in entries {
+            table.insert(name_hash, id);
+        }
+        table
+    }
+}
+
+/// The index of an archive, describing its groups and the files within them.
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub struct Js5Index {
     pub protocol: u8,
     pub version: i32,
@@ -54,10 +118,164 @@ pub struct Js5Index {
     pub has_lengths: bool,
     pub has_uncompressed_checksums: bool,
     pub groups: BTreeMap<u32, Js5IndexEntry>,
-    pub name_hash_table: HashMap<u32, u32>,
+    pub name_hash_table: NameHashTable,
+}
+
+impl Js5IndexFile {
+    /// Create a file with the given name hash
+    ///
+    /// # Arguments
+    ///
+    /// * `name_hash` - The djb2 hash of the file name, or -1 if unnamed
+    pub fn new(name_hash: i32) -> Js5IndexFile {
+        Js5IndexFile { name_hash }
+    }
+}
+
+impl Default for Js5IndexEntry {
+    fn default() -> Self {
+        Js5IndexEntry {
+            name_hash: -1,
+            version: 0,
+            checksum: 0,
+            uncompressed_checksum: 0,
+            length: 0,
+            uncompressed_length: 0,
+            digest: Vec::new(),
+            capacity: 0,
+            files: BTreeMap::new(),
+            name_hash_table: NameHashTable::default(),
+        }
+    }
+}
+
+impl Js5IndexEntry {
+    /// Create an empty, unnamed group
+    pub fn new() -> Js5IndexEntry {
+        Self::default()
+    }
+
+    /// Set the djb2 hash of the group name
+    pub fn with_name_hash(mut self, name_hash: i32) -> Self {
+        self.name_hash = name_hash;
+        self
+    }
+
+    /// Set the version of the group
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the CRC-32 checksum of the compressed group
+    pub fn with_checksum(mut self, checksum: u32) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Set the CRC-32 checksum of the uncompressed group
+    pub fn with_uncompressed_checksum(mut self, uncompressed_checksum: u32) -> Self {
+        self.uncompressed_checksum = uncompressed_checksum;
+        self
+    }
+
+    /// Set the compressed and uncompressed lengths of the group
+    pub fn with_lengths(mut self, length: u32, uncompressed_length: u32) -> Self {
+        self.length = length;
+        self.uncompressed_length = uncompressed_length;
+        self
+    }
+
+    /// Set the digest of the group
+    pub fn with_digest(mut self, digest: Vec<u8>) -> Self {
+        self.digest = digest;
+        self
+    }
+
+    /// Add a file to the group
+    pub fn with_file(mut self, id: u32, file: Js5IndexFile) -> Self {
+        self.files.insert(id, file);
+        self
+    }
+
+    /// Get the id of the file with the given name hash
+    pub fn get_named(&self, name_hash: u32) -> Result<u32, Js5IndexError> {
+        self.name_hash_table.get(name_hash)
+    }
+
+    fn build_name_hash_table(&mut self) {
+        self.name_hash_table = NameHashTable::default();
+        for (id, file) in &self.files {
+            self.name_hash_table.insert(file.name_hash as u32, *id);
+        }
+    }
 }
 
 impl Js5Index {
+    /// Create an empty index using the given protocol
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol` - The protocol the index is written with
+    pub fn new(protocol: Js5Protocol) -> Js5Index {
+        Js5Index {
+            protocol: protocol as u8,
+            version: 0,
+            has_names: false,
+            has_digests: false,
+            has_lengths: false,
+            has_uncompressed_checksums: false,
+            groups: BTreeMap::new(),
+            name_hash_table: NameHashTable::default(),
+        }
+    }
+
+    /// Set the version of the index
+    pub fn with_version(mut self, version: i32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set whether the index stores group and file names
+    pub fn with_names(mut self, has_names: bool) -> Self {
+        self.has_names = has_names;
+        self
+    }
+
+    /// Set whether the index stores group digests
+    pub fn with_digests(mut self, has_digests: bool) -> Self {
+        self.has_digests = has_digests;
+        self
+    }
+
+    /// Set whether the index stores group lengths
+    pub fn with_lengths(mut self, has_lengths: bool) -> Self {
+        self.has_lengths = has_lengths;
+        self
+    }
+
+    /// Set whether the index stores uncompressed group checksums
+    pub fn with_uncompressed_checksums(mut self, has_uncompressed_checksums: bool) -> Self {
+        self.has_uncompressed_checksums = has_uncompressed_checksums;
+        self
+    }
+
+    /// Add a group to the index, registering its name hash and those of its
+    /// files if the index is named
+    pub fn with_group(mut self, id: u32, mut group: Js5IndexEntry) -> Self {
+        if self.has_names {
+            self.name_hash_table.insert(group.name_hash as u32, id);
+            group.build_name_hash_table();
+        }
+        self.groups.insert(id, group);
+        self
+    }
+
+    /// Read an index from its uncompressed representation
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The uncompressed index data
     pub fn read<T: AsRef<[u8]>>(buf: T) -> Result<Js5Index, Js5IndexError> {
         let mut buf_ref = buf.as_ref();
 
@@ -86,27 +304,14 @@ impl Js5Index {
             has_lengths: (flags & Js5IndexFlags::Lengths as u8) != 0,
             has_uncompressed_checksums: (flags & Js5IndexFlags::UncompressedChecksums as u8) != 0,
             groups: BTreeMap::new(),
-            name_hash_table: HashMap::new(),
+            name_hash_table: NameHashTable::default(),
         };
 
         // Begin creating the groups
         let mut prev_group_id = 0;
         for _ in 0..size {
             prev_group_id += read_func(&mut buf_ref)?;
-            index.groups.insert(
-                prev_group_id,
-                Js5IndexEntry {
-                    name_hash: -1,
-                    version: 0,
-                    checksum: 0,
-                    uncompressed_checksum: 0,
-                    length: 0,
-                    uncompressed_length: 0,
-                    digest: Vec::new(),
-                    capacity: 0,
-                    files: BTreeMap::new(),
-                },
-            );
+            index.groups.insert(prev_group_id, Js5IndexEntry::new());
         }
 
         if index.has_names {
@@ -158,9 +363,7 @@ impl Js5Index {
             let mut prev_file_id = 0;
             for _ in 0..group_size {
                 prev_file_id += read_func(&mut buf_ref)?;
-                group
-                    .files
-                    .insert(prev_file_id, Js5IndexFile { name_hash: -1 });
+                group.files.insert(prev_file_id, Js5IndexFile::new(-1));
             }
         }
 
@@ -169,17 +372,16 @@ impl Js5Index {
                 for file in group.files.values_mut() {
                     file.name_hash = buf_ref.read_i32()?;
                 }
+                group.build_name_hash_table();
             }
         }
 
         Ok(index)
     }
 
+    /// Get the id of the group with the given name hash
     pub fn get_named(&self, name_hash: u32) -> Result<u32, Js5IndexError> {
-        self.name_hash_table
-            .get(&name_hash)
-            .ok_or(Js5IndexError::NamedHashTableEntry)
-            .copied()
+        self.name_hash_table.get(name_hash)
     }
 }
 
@@ -204,7 +406,7 @@ mod tests {
                 has_lengths: false,
                 has_uncompressed_checksums: false,
                 groups: BTreeMap::new(),
-                name_hash_table: HashMap::new(),
+                name_hash_table: NameHashTable::default(),
             };
 
             assert_eq!(empty_index, index);
@@ -224,7 +426,7 @@ mod tests {
                 has_lengths: false,
                 has_uncompressed_checksums: false,
                 groups: BTreeMap::new(),
-                name_hash_table: HashMap::new(),
+                name_hash_table: NameHashTable::default(),
             };
 
             assert_eq!(versioned_index, index);
@@ -260,6 +462,7 @@ mod tests {
                     digest: Vec::new(),
                     capacity: 0,
                     files: files_1,
+                    name_hash_table: NameHashTable::default(),
                 },
             );
             groups.insert(
@@ -274,6 +477,7 @@ mod tests {
                     digest: Vec::new(),
                     capacity: 0,
                     files: BTreeMap::new(),
+                    name_hash_table: NameHashTable::default(),
                 },
             );
             groups.insert(
@@ -288,6 +492,7 @@ mod tests {
                     digest: Vec::new(),
                     capacity: 0,
                     files: files_2,
+                    name_hash_table: NameHashTable::default(),
                 },
             );
 
@@ -299,7 +504,7 @@ mod tests {
                 has_lengths: false,
                 has_uncompressed_checksums: false,
                 groups,
-                name_hash_table: HashMap::new(),
+                name_hash_table: NameHashTable::default(),
             };
 
             assert_eq!(no_flags_index, index);
@@ -334,13 +539,10 @@ mod tests {
                     digest: Vec::new(),
                     capacity: 0,
                     files,
+                    name_hash_table: NameHashTable::from([(djb2_hash("world"), 0)]),
                 },
             );
-            let name_hash_table = {
-                let mut table = HashMap::new();
-                table.insert(djb2_hash("hello"), 0);
-                table
-            };
+            let name_hash_table = NameHashTable::from([(djb2_hash("hello"), 0)]);
 
             let named_index = Js5Index {
                 protocol: Js5Protocol::Original as u8,
@@ -349,7 +551,7 @@ mod tests {
                 has_digests: false,
                 has_lengths: false,
                 has_uncompressed_checksums: false,
-                groups: groups,
+                groups,
                 name_hash_table,
             };
 
@@ -382,6 +584,7 @@ mod tests {
                     digest: Vec::new(),
                     capacity: 0,
                     files,
+                    name_hash_table: NameHashTable::default(),
                 },
             );
             groups.insert(
@@ -396,6 +599,7 @@ mod tests {
                     digest: Vec::new(),
                     capacity: 0,
                     files: BTreeMap::new(),
+                    name_hash_table: NameHashTable::default(),
                 },
             );
 
@@ -407,7 +611,7 @@ mod tests {
                 has_lengths: false,
                 has_uncompressed_checksums: false,
                 groups,
-                name_hash_table: HashMap::new(),
+                name_hash_table: NameHashTable::default(),
             };
 
             assert_eq!(smart_index, index);
@@ -437,6 +641,7 @@ mod tests {
                     ],
                     capacity: 0,
                     files: BTreeMap::new(),
+                    name_hash_table: NameHashTable::default(),
                 },
             );
             let digest_index = Js5Index {
@@ -447,7 +652,7 @@ mod tests {
                 has_lengths: false,
                 has_uncompressed_checksums: false,
                 groups,
-                name_hash_table: HashMap::new(),
+                name_hash_table: NameHashTable::default(),
             };
 
             assert_eq!(digest_index, index);
@@ -472,6 +677,7 @@ mod tests {
                     digest: Vec::new(),
                     capacity: 0,
                     files: BTreeMap::new(),
+                    name_hash_table: NameHashTable::default(),
                 },
             );
             let lengths_index = Js5Index {
@@ -482,7 +688,7 @@ mod tests {
                 has_lengths: true,
                 has_uncompressed_checksums: false,
                 groups,
-                name_hash_table: HashMap::new(),
+                name_hash_table: NameHashTable::default(),
             };
 
             assert_eq!(lengths_index, index);
@@ -507,6 +713,7 @@ mod tests {
                     digest: Vec::new(),
                     capacity: 0,
                     files: BTreeMap::new(),
+                    name_hash_table: NameHashTable::default(),
                 },
             );
             let uncompressed_checksum_index = Js5Index {
@@ -517,7 +724,7 @@ mod tests {
                 has_lengths: false,
                 has_uncompressed_checksums: true,
                 groups,
-                name_hash_table: HashMap::new(),
+                name_hash_table: NameHashTable::default(),
             };
 
             assert_eq!(uncompressed_checksum_index, index);
@@ -540,11 +747,7 @@ mod tests {
                 files
             };
 
-            let name_hash_table = {
-                let mut table = HashMap::new();
-                table.insert(djb2_hash("hello"), 0);
-                table
-            };
+            let name_hash_table = NameHashTable::from([(djb2_hash("hello"), 0)]);
 
             let mut groups = BTreeMap::new();
             groups.insert(
@@ -564,6 +767,7 @@ mod tests {
                     ],
                     capacity: 0,
                     files,
+                    name_hash_table: NameHashTable::from([(djb2_hash("world"), 0)]),
                 },
             );
             let all_flags_index = Js5Index {
@@ -581,6 +785,74 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_get_named_file() {
+        read("named.dat", |data| {
+            let index = Js5Index::read(data).unwrap();
+
+            let group = index.get_named(djb2_hash("hello")).unwrap();
+            let file = index.groups[&group].get_named(djb2_hash("world")).unwrap();
+
+            assert_eq!((0, 0), (group, file));
+            assert!(matches!(
+                index.groups[&group].get_named(djb2_hash("hello")),
+                Err(Js5IndexError::NamedHashTableEntry)
+            ));
+        });
+    }
+
+    #[test]
+    fn test_get_named_collision() {
+        let index = Js5Index::new(Js5Protocol::Original)
+            .with_names(true)
+            .with_group(
+                0,
+                Js5IndexEntry::new()
+                    .with_name_hash(djb2_hash("hello") as i32)
+                    .with_file(0, Js5IndexFile::new(djb2_hash("world") as i32))
+                    .with_file(1, Js5IndexFile::new(djb2_hash("world") as i32)),
+            )
+            .with_group(
+                1,
+                Js5IndexEntry::new().with_name_hash(djb2_hash("hello") as i32),
+            );
+
+        assert!(matches!(
+            index.get_named(djb2_hash("hello")),
+            Err(Js5IndexError::NameHashCollision(hash)) if hash == djb2_hash("hello")
+        ));
+        assert!(matches!(
+            index.groups[&0].get_named(djb2_hash("world")),
+            Err(Js5IndexError::NameHashCollision(hash)) if hash == djb2_hash("world")
+        ));
+    }
+
+    #[test]
+    fn test_builder() {
+        read("all-flags.dat", |data| {
+            let index = Js5Index::read(data).unwrap();
+
+            let built = Js5Index::new(Js5Protocol::Original)
+                .with_names(true)
+                .with_digests(true)
+                .with_lengths(true)
+                .with_uncompressed_checksums(true)
+                .with_group(
+                    0,
+                    Js5IndexEntry::new()
+                        .with_name_hash(djb2_hash("hello") as i32)
+                        .with_version(0x89ABCDEF)
+                        .with_checksum(0x01234567)
+                        .with_uncompressed_checksum(0xAAAA5555)
+                        .with_lengths(1000, 2000)
+                        .with_digest(index.groups[&0].digest.clone())
+                        .with_file(0, Js5IndexFile::new(djb2_hash("world") as i32)),
+                );
+
+            assert_eq!(built, index);
+        });
+    }
+
     fn read<P, F>(p: P, f: F)
     where
         P: AsRef<Path>,
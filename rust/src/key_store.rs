@@ -0,0 +1,220 @@
+use crate::djb2::djb2_hash;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io::Read, path::Path};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum KeyStoreError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid map square id: {0}")]
+    InvalidMapSquare(String),
+    #[error("invalid key for map square {0}")]
+    InvalidKey(u32),
+}
+
+/// A single entry of a keys file. Accepts both the OpenRS2 format
+/// (`mapsquare` and `key`) and the older RuneLite format (`region` and `keys`).
+#[derive(Deserialize)]
+struct KeyEntry {
+    #[serde(alias = "region")]
+    mapsquare: u32,
+    #[serde(alias = "keys")]
+    key: [i64; 4],
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeysFile {
+    /// `[{"mapsquare": 10295, "key": [...]}, ...]`
+    List(Vec<KeyEntry>),
+    /// `{"10295": [...], ...}`
+    Map(HashMap<String, [i64; 4]>),
+}
+
+/// XTEA keys for the encrypted location groups of map squares.
+///
+/// Map squares are identified by their id, `(x << 8) | y`, and their location
+/// groups are named `l{x}_{y}`.
+#[derive(Debug, Default)]
+pub struct KeyStore {
+    keys: HashMap<u32, [u32; 4]>,
+    name_hashes: HashMap<u32, u32>,
+}
+
+impl KeyStore {
+    /// Create an empty key store
+    pub fn new() -> KeyStore {
+        Self::default()
+    }
+
+    /// Load keys from a path. Directories are expected to contain one
+    /// `{mapsquare}.txt` file per map square with a key word on each line,
+    /// anything else is read as a JSON keys file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the keys file or directory
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<KeyStore, KeyStoreError> {
+        let mut store = Self::new();
+
+        if path.as_ref().is_dir() {
+            store.read_text_dir(path)?;
+        } else {
+            store.read_json(fs::File::open(path)?)?;
+        }
+
+        Ok(store)
+    }
+
+    /// Add the keys from a JSON keys file, either in the OpenRS2 `keys.json`
+    /// format, the RuneLite `region`/`keys` format or a map of map square ids
+    /// to keys
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader to read the JSON from
+    pub fn read_json<R: Read>(&mut self, reader: R) -> Result<(), KeyStoreError> {
+        match serde_json::from_reader(reader)? {
+            KeysFile::List(entries) => {
+                for entry in entries {
+                    self.insert(entry.mapsquare, key_from_words(entry.key));
+                }
+            }
+            KeysFile::Map(entries) => {
+                for (mapsquare, key) in entries {
+                    let mapsquare = mapsquare
+                        .parse()
+                        .map_err(|_| KeyStoreError::InvalidMapSquare(mapsquare))?;
+                    self.insert(mapsquare, key_from_words(key));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_text_dir<P: AsRef<Path>>(&mut self, path: P) -> Result<(), KeyStoreError> {
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("txt") {
+                continue;
+            }
+
+            let stem = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_string();
+            let mapsquare = stem
+                .parse()
+                .map_err(|_| KeyStoreError::InvalidMapSquare(stem))?;
+
+            let words = fs::read_to_string(&path)?
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.trim().parse::<i64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| KeyStoreError::InvalidKey(mapsquare))?;
+            let words: [i64; 4] = words
+                .try_into()
+                .map_err(|_| KeyStoreError::InvalidKey(mapsquare))?;
+
+            self.insert(mapsquare, key_from_words(words));
+        }
+
+        Ok(())
+    }
+
+    /// Add the key for a map square
+    ///
+    /// # Arguments
+    ///
+    /// * `mapsquare` - The id of the map square, `(x << 8) | y`
+    /// * `key` - The XTEA key of the map square's location group
+    pub fn insert(&mut self, mapsquare: u32, key: [u32; 4]) {
+        let name = format!("l{}_{}", mapsquare >> 8, mapsquare & 0xFF);
+        self.name_hashes.insert(djb2_hash(name), mapsquare);
+        self.keys.insert(mapsquare, key);
+    }
+
+    /// Get the key for a map square
+    pub fn get(&self, mapsquare: u32) -> Option<[u32; 4]> {
+        self.keys.get(&mapsquare).copied()
+    }
+
+    /// Get the key for a location group by its name, e.g. `l50_50`
+    pub fn get_named(&self, group: &str) -> Option<[u32; 4]> {
+        self.get_by_name_hash(djb2_hash(group))
+    }
+
+    /// Get the key for a location group by the djb2 hash of its name
+    pub fn get_by_name_hash(&self, name_hash: u32) -> Option<[u32; 4]> {
+        self.name_hashes
+            .get(&name_hash)
+            .and_then(|mapsquare| self.get(*mapsquare))
+    }
+
+    /// Get the number of map squares with a key
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Check whether the key store is empty
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+fn key_from_words(words: [i64; 4]) -> [u32; 4] {
+    words.map(|word| word as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_openrs2() {
+        read_test("keys.json", |store| {
+            assert_eq!(1, store.len());
+            assert_eq!(Some(KEY), store.get(10295));
+            assert_eq!(Some(KEY), store.get_named("l40_55"));
+            assert_eq!(None, store.get_named("m40_55"));
+        });
+    }
+
+    #[test]
+    fn test_open_region() {
+        read_test("xteas-region.json", |store| {
+            assert_eq!(Some(KEY), store.get(10295));
+        });
+    }
+
+    #[test]
+    fn test_open_map() {
+        read_test("xteas-map.json", |store| {
+            assert_eq!(Some(KEY), store.get(10295));
+        });
+    }
+
+    #[test]
+    fn test_open_text() {
+        read_test("txt", |store| {
+            assert_eq!(Some(KEY), store.get(10295));
+        });
+    }
+
+    fn read_test<P, F>(p: P, f: F)
+    where
+        P: AsRef<Path>,
+        F: FnOnce(KeyStore),
+    {
+        f(KeyStore::open(Path::new("tests/data/keys").join(p)).unwrap())
+    }
+
+    const KEY: [u32; 4] = [0x00112233, 0x44556677, 0x8899AABB, 0xCCDDEEFF];
+}
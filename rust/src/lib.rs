@@ -1,21 +1,44 @@
 use archive::cache_archive::CacheArchive;
 use group::GroupError;
+use key_store::KeyStore;
+use revision::Revision;
 use std::collections::HashMap;
 use store::Store;
 
-mod archive;
+pub mod anim;
+pub mod archive;
+mod buffer;
 pub mod cache;
 pub mod checksumtable;
-mod djb2;
+pub mod clientscript;
+pub mod collision;
+pub mod definitions;
+pub mod djb2;
 mod ffi;
-mod group;
-mod js5_compression;
-mod js5_index;
+pub mod font;
+pub mod group;
+pub mod hsl;
+pub mod huffman;
+pub mod interface;
+pub mod js5_compression;
+pub mod js5_index;
 pub mod js5_masterindex;
+pub mod key_store;
+pub mod map;
+pub mod minimap;
+pub mod model;
+pub mod music;
+pub mod name_dictionary;
+pub mod revision;
+pub mod sound;
+pub mod sprite;
 pub mod store;
-mod xtea;
+pub mod texture;
+mod trig;
+pub mod vars;
+pub mod xtea;
 
-const _MAX_GROUP_SIZE: usize = (1 << 24) - 1;
+const MAX_GROUP_SIZE: usize = (1 << 24) - 1;
 
 pub struct Cache {
     /// Store
@@ -24,6 +47,12 @@ pub struct Cache {
     /// Archives
     archives: HashMap<u8, CacheArchive>,
 
+    /// XTEA keys used when reading map squares without explicit keys
+    keys: KeyStore,
+
+    /// Revision used when decoding configs and maps
+    revision: Revision,
+
     /// Unpacked cache size
     _unpacked_cache_size: usize,
 }
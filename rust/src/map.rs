@@ -0,0 +1,540 @@
+use crate::{
+    buffer::{CacheReadExt, CacheWriteExt},
+    revision::Revision,
+    trig::cosine,
+};
+use osrs_bytes::{ReadExt, WriteExt};
+use std::io;
+use thiserror::Error;
+
+/// The archive holding the map squares.
+pub const MAPS_ARCHIVE: u8 = 5;
+/// The width and length of a map square in tiles.
+pub const MAP_SIZE: usize = 64;
+/// The number of planes in a map square.
+pub const MAP_PLANES: usize = 4;
+
+/// The tile setting blocking movement onto the tile.
+pub const SETTING_BLOCKED: u8 = 0x1;
+/// The tile setting marking a bridge, moving the plane above down to this one.
+pub const SETTING_BRIDGE: u8 = 0x2;
+/// The tile setting hiding roofs above the tile.
+pub const SETTING_ROOF: u8 = 0x4;
+/// The tile setting drawing the tile below the current plane.
+pub const SETTING_VISIBLE_BELOW: u8 = 0x8;
+
+/// The height difference between two planes.
+const PLANE_HEIGHT: i32 = 240;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum MapError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("terrain value {value} can't be encoded in revision {}", revision.0)]
+    Unencodable { value: u16, revision: Revision },
+}
+
+/// A single tile of the terrain as stored in the cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tile {
+    /// The explicit height of the tile, `None` if the height is derived from
+    /// the noise function or the plane below
+    pub height: Option<u8>,
+    /// The overlay id plus one, 0 for no overlay
+    pub overlay_id: u16,
+    pub overlay_path: u8,
+    pub overlay_rotation: u8,
+    /// The `SETTING_*` flags of the tile
+    pub settings: u8,
+    /// The underlay id plus one, 0 for no underlay
+    pub underlay_id: u16,
+}
+
+/// The terrain of a map square, decoded from an `m{x}_{y}` group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Terrain {
+    tiles: Vec<Tile>,
+}
+
+impl Default for Terrain {
+    fn default() -> Self {
+        Terrain {
+            tiles: vec![Tile::default(); MAP_PLANES * MAP_SIZE * MAP_SIZE],
+        }
+    }
+}
+
+impl Terrain {
+    /// Decode the terrain of a map square from the latest caches
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The contents of file 0 of the `m{x}_{y}` group
+    pub fn decode(buf: &[u8]) -> Result<Terrain, MapError> {
+        Self::decode_revision(buf, Revision::LATEST)
+    }
+
+    /// Decode the terrain of a map square from a cache of a revision. Caches
+    /// before [`Revision::WIDE_TERRAIN`] store opcodes and overlay ids as
+    /// bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The contents of file 0 of the `m{x}_{y}` group
+    /// * `revision` - The revision of the cache
+    pub fn decode_revision(mut buf: &[u8], revision: Revision) -> Result<Terrain, MapError> {
+        let wide = revision >= Revision::WIDE_TERRAIN;
+        let mut terrain = Terrain::default();
+
+        for tile in terrain.tiles.iter_mut() {
+            loop {
+                let opcode = if wide {
+                    buf.read_u16()?
+                } else {
+                    buf.read_u8()? as u16
+                };
+                match opcode {
+                    0 => break,
+                    1 => {
+                        tile.height = Some(buf.read_u8()?);
+                        break;
+                    }
+                    2..=49 => {
+                        tile.overlay_id = if wide {
+                            buf.read_i16()? as u16
+                        } else {
+                            buf.read_u8()? as u16
+                        };
+                        tile.overlay_path = ((opcode - 2) / 4) as u8;
+                        tile.overlay_rotation = ((opcode - 2) & 0x3) as u8;
+                    }
+                    50..=81 => tile.settings = (opcode - 49) as u8,
+                    _ => tile.underlay_id = opcode - 81,
+                }
+            }
+        }
+
+        Ok(terrain)
+    }
+
+    /// Encode the terrain of a map square for the latest caches
+    pub fn encode(&self) -> Result<Vec<u8>, MapError> {
+        self.encode_revision(Revision::LATEST)
+    }
+
+    /// Encode the terrain of a map square for a cache of a revision
+    ///
+    /// # Arguments
+    ///
+    /// * `revision` - The revision of the cache
+    pub fn encode_revision(&self, revision: Revision) -> Result<Vec<u8>, MapError> {
+        let mut buf = Vec::new();
+        let write = |buf: &mut Vec<u8>, value: u16| -> Result<(), MapError> {
+            if revision >= Revision::WIDE_TERRAIN {
+                buf.write_u16(value)?;
+            } else {
+                let value =
+                    u8::try_from(value).map_err(|_| MapError::Unencodable { value, revision })?;
+                buf.write_u8(value)?;
+            }
+            Ok(())
+        };
+
+        for tile in &self.tiles {
+            if tile.overlay_id != 0 {
+                write(
+                    &mut buf,
+                    2 + ((tile.overlay_path as u16) << 2) + tile.overlay_rotation as u16,
+                )?;
+                write(&mut buf, tile.overlay_id)?;
+            }
+            if tile.settings != 0 {
+                write(&mut buf, 49 + tile.settings as u16)?;
+            }
+            if tile.underlay_id != 0 {
+                write(&mut buf, 81 + tile.underlay_id)?;
+            }
+            match tile.height {
+                Some(height) => {
+                    write(&mut buf, 1)?;
+                    buf.write_u8(height)?;
+                }
+                None => write(&mut buf, 0)?,
+            }
+        }
+
+        Ok(buf)
+    }
+
+    /// Get a tile
+    ///
+    /// # Arguments
+    ///
+    /// * `plane` - The plane of the tile, from 0 to 3
+    /// * `x` - The x coordinate of the tile within the map square
+    /// * `y` - The y coordinate of the tile within the map square
+    pub fn tile(&self, plane: usize, x: usize, y: usize) -> &Tile {
+        &self.tiles[Self::offset(plane, x, y)]
+    }
+
+    /// Get a mutable reference to a tile
+    ///
+    /// # Arguments
+    ///
+    /// * `plane` - The plane of the tile, from 0 to 3
+    /// * `x` - The x coordinate of the tile within the map square
+    /// * `y` - The y coordinate of the tile within the map square
+    pub fn tile_mut(&mut self, plane: usize, x: usize, y: usize) -> &mut Tile {
+        &mut self.tiles[Self::offset(plane, x, y)]
+    }
+
+    /// Calculate the height of every tile the way the client does, filling
+    /// in tiles without an explicit height from the noise function on plane
+    /// 0 and from the plane below otherwise. Heights grow more negative
+    /// going up, indexed by plane, then x, then y.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_x` - The absolute x coordinate of the south west tile of the map square
+    /// * `base_y` - The absolute y coordinate of the south west tile of the map square
+    pub fn heights(&self, base_x: u32, base_y: u32) -> Vec<[[i32; MAP_SIZE]; MAP_SIZE]> {
+        let mut heights = vec![[[0; MAP_SIZE]; MAP_SIZE]; MAP_PLANES];
+
+        for plane in 0..MAP_PLANES {
+            let below = if plane == 0 {
+                [[0; MAP_SIZE]; MAP_SIZE]
+            } else {
+                heights[plane - 1]
+            };
+            for (x, column) in heights[plane].iter_mut().enumerate() {
+                for (y, height) in column.iter_mut().enumerate() {
+                    let below = below[x][y];
+                    *height = match self.tile(plane, x, y).height {
+                        Some(height) => {
+                            let height = if height == 1 { 0 } else { height as i32 };
+                            below - height * 8
+                        }
+                        None if plane == 0 => {
+                            -noise_height(
+                                base_x as i32 + x as i32 + 0xe3b7b,
+                                base_y as i32 + y as i32 + 0x87cce,
+                            ) * 8
+                        }
+                        None => below - PLANE_HEIGHT,
+                    };
+                }
+            }
+        }
+
+        heights
+    }
+
+    fn offset(plane: usize, x: usize, y: usize) -> usize {
+        (plane * MAP_SIZE + x) * MAP_SIZE + y
+    }
+}
+
+/// A loc placed in a map square.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub id: u32,
+    /// The x coordinate within the map square
+    pub x: u8,
+    /// The y coordinate within the map square
+    pub y: u8,
+    /// The plane as stored, before bridges are applied
+    pub plane: u8,
+    /// The loc type (shape), such as a wall, wall decoration or scenery
+    pub shape: u8,
+    /// The rotation, from 0 to 3 clockwise starting at west
+    pub rotation: u8,
+}
+
+/// Decode the locations of a map square
+///
+/// Locations are grouped by ascending id, each id stored as a delta from the
+/// previous id followed by the delta encoded positions of its locations.
+///
+/// # Arguments
+///
+/// * `buf` - The decrypted contents of file 0 of the `l{x}_{y}` group
+pub fn decode_locations(mut buf: &[u8]) -> Result<Vec<Location>, MapError> {
+    let mut locations = Vec::new();
+
+    let mut id: i64 = -1;
+    loop {
+        let id_delta = buf.read_extended_smart()?;
+        if id_delta == 0 {
+            break;
+        }
+        id += id_delta as i64;
+
+        let mut position = 0;
+        loop {
+            let position_delta = buf.read_u16_smart()? as u32;
+            if position_delta == 0 {
+                break;
+            }
+            position += position_delta - 1;
+
+            let attributes = buf.read_u8()?;
+            locations.push(Location {
+                id: id as u32,
+                x: ((position >> 6) & 0x3f) as u8,
+                y: (position & 0x3f) as u8,
+                plane: ((position >> 12) & 0x3) as u8,
+                shape: attributes >> 2,
+                rotation: attributes & 0x3,
+            });
+        }
+    }
+
+    Ok(locations)
+}
+
+/// Encode the locations of a map square, sorting them by id and position
+///
+/// # Arguments
+///
+/// * `locations` - The locations to encode
+pub fn encode_locations(locations: &[Location]) -> Result<Vec<u8>, MapError> {
+    let position = |location: &Location| {
+        ((location.plane as u32) << 12) | ((location.x as u32) << 6) | location.y as u32
+    };
+
+    let mut sorted = locations.to_vec();
+    sorted.sort_by_key(|location| (location.id, position(location)));
+
+    let mut buf = Vec::new();
+    let mut previous_id: i64 = -1;
+    let mut iter = sorted.iter().peekable();
+    while let Some(first) = iter.peek() {
+        let id = first.id;
+        buf.write_extended_smart((id as i64 - previous_id) as u32)?;
+        previous_id = id as i64;
+
+        let mut previous_position = 0;
+        while let Some(location) = iter.next_if(|location| location.id == id) {
+            let position = position(location);
+            buf.write_u16_smart((position - previous_position + 1) as u16)?;
+            buf.write_u8((location.shape << 2) | (location.rotation & 0x3))?;
+            previous_position = position;
+        }
+        buf.write_u16_smart(0)?;
+    }
+    buf.write_u16_smart(0)?;
+
+    Ok(buf)
+}
+
+/// A map square with its terrain and locations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MapSquare {
+    pub x: u8,
+    pub y: u8,
+    pub terrain: Terrain,
+    pub locations: Vec<Location>,
+}
+
+impl MapSquare {
+    /// Get the id of the map square, as used by XTEA key lists
+    pub fn id(&self) -> u32 {
+        ((self.x as u32) << 8) | self.y as u32
+    }
+
+    /// Get the absolute x coordinate of the south west tile
+    pub fn base_x(&self) -> u32 {
+        self.x as u32 * MAP_SIZE as u32
+    }
+
+    /// Get the absolute y coordinate of the south west tile
+    pub fn base_y(&self) -> u32 {
+        self.y as u32 * MAP_SIZE as u32
+    }
+
+    /// Calculate the height of every tile, see [`Terrain::heights`]
+    pub fn heights(&self) -> Vec<[[i32; MAP_SIZE]; MAP_SIZE]> {
+        self.terrain.heights(self.base_x(), self.base_y())
+    }
+}
+
+/// Get the name of the terrain group of a map square
+pub fn terrain_name(x: u8, y: u8) -> String {
+    format!("m{x}_{y}")
+}
+
+/// Get the name of the locations group of a map square
+pub fn locations_name(x: u8, y: u8) -> String {
+    format!("l{x}_{y}")
+}
+
+/// The procedural height the client gives tiles on plane 0 without one.
+fn noise_height(x: i32, y: i32) -> i32 {
+    let height = interpolated_noise(x + 45365, y + 91923, 4) - 128
+        + ((interpolated_noise(x + 10294, y + 37821, 2) - 128) >> 1)
+        + ((interpolated_noise(x, y, 1) - 128) >> 2);
+    ((height as f64 * 0.3) as i32 + 35).clamp(10, 60)
+}
+
+fn interpolated_noise(x: i32, y: i32, frequency: i32) -> i32 {
+    let (int_x, frac_x) = (x / frequency, x & (frequency - 1));
+    let (int_y, frac_y) = (y / frequency, y & (frequency - 1));
+    let south = interpolate(
+        smooth_noise(int_x, int_y),
+        smooth_noise(int_x + 1, int_y),
+        frac_x,
+        frequency,
+    );
+    let north = interpolate(
+        smooth_noise(int_x, int_y + 1),
+        smooth_noise(int_x + 1, int_y + 1),
+        frac_x,
+        frequency,
+    );
+    interpolate(south, north, frac_y, frequency)
+}
+
+fn interpolate(a: i32, b: i32, fraction: i32, frequency: i32) -> i32 {
+    let f = (65536 - cosine()[(fraction * 1024 / frequency) as usize]) >> 1;
+    ((a * (65536 - f)) >> 16) + ((b * f) >> 16)
+}
+
+fn smooth_noise(x: i32, y: i32) -> i32 {
+    let corners =
+        noise(x - 1, y - 1) + noise(x + 1, y - 1) + noise(x - 1, y + 1) + noise(x + 1, y + 1);
+    let sides = noise(x - 1, y) + noise(x + 1, y) + noise(x, y - 1) + noise(x, y + 1);
+    noise(x, y) / 4 + sides / 8 + corners / 16
+}
+
+fn noise(x: i32, y: i32) -> i32 {
+    let mut n = x.wrapping_add(y.wrapping_mul(57));
+    n ^= n << 13;
+    let n = n
+        .wrapping_mul(n.wrapping_mul(n).wrapping_mul(15731).wrapping_add(789221))
+        .wrapping_add(1376312589)
+        & 0x7fffffff;
+    (n >> 19) & 0xff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    const KEY: [u32; 4] = [0x1234_5678, 0x9abc_def0, 0x0fed_cba9, 0x8765_4321];
+
+    #[test]
+    fn test_map_square() {
+        let mut cache = Cache::open("tests/data/cache/cache-maps").unwrap();
+        let square = cache.map_square(50, 50, Some(KEY)).unwrap();
+
+        assert_eq!(12850, square.id());
+        assert_eq!((3200, 3200), (square.base_x(), square.base_y()));
+
+        let tile = square.terrain.tile(0, 0, 0);
+        assert_eq!(Some(10), tile.height);
+        assert_eq!(5, tile.underlay_id);
+        assert_eq!(SETTING_BLOCKED, tile.settings);
+
+        let bridge = square.terrain.tile(1, 1, 2);
+        assert_eq!(7, bridge.overlay_id);
+        assert_eq!((1, 3), (bridge.overlay_path, bridge.overlay_rotation));
+        assert_eq!(SETTING_BRIDGE, bridge.settings);
+
+        assert_eq!(
+            Location {
+                id: 1276,
+                x: 20,
+                y: 20,
+                plane: 0,
+                shape: 10,
+                rotation: 0
+            },
+            square.locations[0]
+        );
+        assert_eq!(4, square.locations.len());
+        assert_eq!(40000, square.locations[3].id);
+        assert_eq!((63, 63, 3), {
+            let l = square.locations[3];
+            (l.x, l.y, l.plane)
+        });
+    }
+
+    #[test]
+    fn test_map_square_without_locations() {
+        let mut cache = Cache::open("tests/data/cache/cache-maps").unwrap();
+        let square = cache.map_square(50, 51, None).unwrap();
+
+        assert!(square.locations.is_empty());
+        assert_eq!(Some(0), square.terrain.tile(0, 63, 63).height);
+    }
+
+    #[test]
+    fn test_heights() {
+        let mut terrain = Terrain::default();
+        terrain.tile_mut(0, 0, 0).height = Some(10);
+        terrain.tile_mut(1, 0, 0).height = Some(1);
+
+        let heights = terrain.heights(3200, 3200);
+        assert_eq!(-80, heights[0][0][0]);
+        assert_eq!(-80, heights[1][0][0]);
+        assert_eq!(-320, heights[2][0][0]);
+        for (ground, first) in heights[0][1..].iter().zip(&heights[1][1..]) {
+            assert!((-480..=-80).contains(&ground[0]));
+            assert_eq!(ground[0] - PLANE_HEIGHT, first[0]);
+        }
+        assert_eq!(heights, terrain.heights(3200, 3200));
+    }
+
+    #[test]
+    fn test_terrain_round_trip() {
+        let mut cache = Cache::open("tests/data/cache/cache-maps").unwrap();
+        let buf = cache
+            .read_named_group(MAPS_ARCHIVE, &terrain_name(50, 50), 0, None)
+            .unwrap();
+
+        assert_eq!(buf, Terrain::decode(&buf).unwrap().encode().unwrap());
+    }
+
+    #[test]
+    fn test_legacy_terrain() {
+        let mut terrain = Terrain::default();
+        *terrain.tile_mut(0, 0, 0) = Tile {
+            height: Some(10),
+            overlay_id: 7,
+            overlay_path: 1,
+            overlay_rotation: 3,
+            settings: SETTING_BLOCKED,
+            underlay_id: 5,
+        };
+
+        let buf = terrain.encode_revision(Revision::LEGACY).unwrap();
+        assert_eq!([9, 7, 50, 86, 1, 10, 0], buf[..7]);
+        assert_eq!(MAP_PLANES * MAP_SIZE * MAP_SIZE + 5, buf.len());
+        assert_eq!(
+            terrain,
+            Terrain::decode_revision(&buf, Revision::LEGACY).unwrap()
+        );
+
+        terrain.tile_mut(0, 0, 0).underlay_id = 200;
+        assert!(matches!(
+            terrain.encode_revision(Revision::LEGACY),
+            Err(MapError::Unencodable { value: 281, .. })
+        ));
+        assert!(terrain.encode_revision(Revision::WIDE_TERRAIN).is_ok());
+    }
+
+    #[test]
+    fn test_locations_round_trip() {
+        let mut cache = Cache::open("tests/data/cache/cache-maps").unwrap();
+        let buf = cache
+            .read_named_group(MAPS_ARCHIVE, &locations_name(50, 50), 0, Some(KEY))
+            .unwrap();
+
+        assert_eq!(
+            buf,
+            encode_locations(&decode_locations(&buf).unwrap()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    cache::CacheError,
+    hsl::{self, BRIGHTNESS_LOW},
+    map::{self, MapError, Terrain, MAPS_ARCHIVE, MAP_PLANES, MAP_SIZE, SETTING_BRIDGE},
+    sprite::{SpriteError, SpriteFrame},
+    Cache,
+};
+use std::collections::{hash_map::Entry, HashMap};
+use thiserror::Error;
+
+/// The width and height of a tile on the minimap, in pixels.
+pub const TILE_SIZE: usize = 4;
+
+/// How many tiles away underlays are blended with, as in the client.
+const BLEND_RADIUS: usize = 5;
+/// The light the client draws the minimap's tiles with.
+const MINIMAP_LIGHT: i32 = 96;
+
+/// The pixels of each tile shape covered by its overlay, indexed by the
+/// overlay path plus one.
+const SHAPES: [[u8; 16]; 13] = [
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1, 1, 0, 0, 1, 1, 1, 0, 1, 1, 1, 1],
+    [1, 1, 0, 0, 1, 1, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 0, 1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [1, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1],
+    [1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0],
+    [1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 0, 0, 1, 1],
+    [1, 1, 1, 1, 1, 1, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 1, 1, 1, 0],
+];
+
+/// The order a shape's pixels are read in for each overlay rotation.
+const ROTATIONS: [[usize; 16]; 4] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [12, 8, 4, 0, 13, 9, 5, 1, 14, 10, 6, 2, 15, 11, 7, 3],
+    [15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+    [3, 7, 11, 15, 2, 6, 10, 14, 1, 5, 9, 13, 0, 4, 8, 12],
+];
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum MinimapError {
+    #[error("cache error: {0}")]
+    Cache(#[from] CacheError),
+    #[error("map error: {0}")]
+    Map(#[from] MapError),
+    #[error("sprite error: {0}")]
+    Sprite(#[from] SpriteError),
+}
+
+/// Render the floor of a map square as the client draws it on the minimap,
+/// [`TILE_SIZE`] pixels per tile with north at the top. Underlays are
+/// blended with their neighbours within the square, and tiles without an
+/// underlay or overlay are transparent.
+///
+/// # Arguments
+///
+/// * `cache` - The cache to read the terrain and floor definitions from
+/// * `x` - The x coordinate of the map square
+/// * `y` - The y coordinate of the map square
+/// * `plane` - The plane to render. Tiles below bridges show the bridge.
+pub fn render_region(
+    cache: &mut Cache,
+    x: u8,
+    y: u8,
+    plane: usize,
+) -> Result<SpriteFrame, MinimapError> {
+    let buf = cache.read_named_group(MAPS_ARCHIVE, &map::terrain_name(x, y), 0, None)?;
+    let terrain = Terrain::decode_revision(&buf, cache.revision())?;
+
+    let tiles: Vec<_> = (0..MAP_SIZE * MAP_SIZE)
+        .map(|i| {
+            let (x, y) = (i / MAP_SIZE, i % MAP_SIZE);
+            let bridge = terrain.tile(1, x, y).settings & SETTING_BRIDGE != 0;
+            if plane + 1 < MAP_PLANES && bridge {
+                *terrain.tile(plane + 1, x, y)
+            } else {
+                *terrain.tile(plane, x, y)
+            }
+        })
+        .collect();
+
+    let mut underlays = HashMap::new();
+    let mut colours = Vec::with_capacity(tiles.len());
+    for tile in &tiles {
+        let colour = match tile.underlay_id {
+            0 => None,
+            id => Some(match underlays.entry(id) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => *entry.insert(cache.underlay(id as u32 - 1)?.colour()),
+            }),
+        };
+        colours.push(colour);
+    }
+
+    let mut overlays = HashMap::new();
+    let size = MAP_SIZE * TILE_SIZE;
+    let mut pixels = vec![0; size * size];
+    for (i, tile) in tiles.iter().enumerate() {
+        let (x, y) = (i / MAP_SIZE, i % MAP_SIZE);
+        let underlay = match colours[i] {
+            Some(_) => argb(blend(&colours, x, y)),
+            None => 0,
+        };
+        let overlay = match tile.overlay_id {
+            0 => None,
+            id => Some(match overlays.entry(id) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => {
+                    *entry.insert(overlay_hsl(cache, id as u32 - 1)?.map_or(0, argb))
+                }
+            }),
+        };
+
+        let shape = match overlay {
+            Some(_) => &SHAPES[tile.overlay_path as usize + 1],
+            None => &SHAPES[0],
+        };
+        let rotation = &ROTATIONS[tile.overlay_rotation as usize & 0x3];
+        let left = x * TILE_SIZE;
+        let top = (MAP_SIZE - 1 - y) * TILE_SIZE;
+        for (j, index) in rotation.iter().enumerate() {
+            let pixel = match shape[*index] {
+                0 => underlay,
+                _ => overlay.unwrap_or_default(),
+            };
+            pixels[(top + j / TILE_SIZE) * size + left + j % TILE_SIZE] = pixel;
+        }
+    }
+
+    Ok(SpriteFrame::new(size as u16, size as u16, pixels)?)
+}
+
+/// Blend the underlay of a tile with the underlays around it, returning the
+/// packed HSL colour
+fn blend(colours: &[Option<hsl::FloorColour>], x: usize, y: usize) -> u16 {
+    let (mut hue, mut multiplier, mut saturation, mut lightness, mut count) = (0, 0, 0, 0, 0);
+    let xs = x.saturating_sub(BLEND_RADIUS)..(x + BLEND_RADIUS + 1).min(MAP_SIZE);
+    for x in xs {
+        let ys = y.saturating_sub(BLEND_RADIUS)..(y + BLEND_RADIUS + 1).min(MAP_SIZE);
+        for colour in ys.filter_map(|y| colours[x * MAP_SIZE + y]) {
+            hue += colour.blend_hue;
+            multiplier += colour.hue_multiplier;
+            saturation += colour.saturation;
+            lightness += colour.lightness;
+            count += 1;
+        }
+    }
+    hsl::pack_floor(
+        hue * 256 / multiplier,
+        saturation / count,
+        lightness / count,
+    )
+}
+
+/// Get the packed HSL colour an overlay is drawn with on the minimap, or
+/// `None` if it isn't drawn
+fn overlay_hsl(cache: &mut Cache, id: u32) -> Result<Option<u16>, CacheError> {
+    let overlay = cache.overlay(id)?;
+    if let Some(hsl) = overlay.secondary_hsl() {
+        return Ok(Some(hsl));
+    }
+    match overlay.texture {
+        Some(texture) => Ok(Some(cache.texture(texture as u16)?.average_hsl)),
+        None => Ok(overlay.hsl()),
+    }
+}
+
+/// Convert a packed HSL colour to an opaque ARGB minimap pixel
+fn argb(hsl: u16) -> u32 {
+    let hsl = hsl::adjust_luminance(hsl, MINIMAP_LIGHT);
+    0xff000000 | hsl::hsl_to_rgb(hsl, BRIGHTNESS_LOW)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hsl::{adjust_luminance, hsl_to_rgb, pack};
+
+    fn tile_pixel(frame: &SpriteFrame, x: usize, y: usize, i: usize) -> u32 {
+        let px = x * TILE_SIZE + i % TILE_SIZE;
+        let py = (MAP_SIZE - 1 - y) * TILE_SIZE + i / TILE_SIZE;
+        frame.pixel(px as u16, py as u16)
+    }
+
+    fn channels(argb: u32) -> [u32; 4] {
+        argb.to_be_bytes().map(|channel| channel as u32)
+    }
+
+    #[test]
+    fn test_render_region() {
+        let mut cache = Cache::open("tests/data/cache/cache-minimap").unwrap();
+        let frame = render_region(&mut cache, 50, 50, 0).unwrap();
+        assert_eq!((256, 256), (frame.width, frame.height));
+
+        let green = tile_pixel(&frame, 0, 0, 0);
+        let [a, r, g, b] = channels(green);
+        assert!(a == 0xff && g > r && g > b);
+        assert_eq!(green, tile_pixel(&frame, 5, 5, 15));
+        assert_eq!(0, tile_pixel(&frame, 63, 63, 0));
+
+        // Underlays at the border of the grey half are blended
+        let grey = tile_pixel(&frame, 63, 0, 0);
+        let border = tile_pixel(&frame, 31, 0, 0);
+        assert!(border != green && border != grey);
+
+        let blue = tile_pixel(&frame, 10, 10, 0);
+        let [_, r, g, b] = channels(blue);
+        assert!(b > r && b > g);
+        assert!((0..16).all(|i| tile_pixel(&frame, 10, 10, i) == blue));
+        assert!((0..16).all(|i| tile_pixel(&frame, 10, 20, i) == 0));
+
+        let average = adjust_luminance(pack(10, 3, 60), 96);
+        let textured = 0xff000000 | hsl_to_rgb(average, BRIGHTNESS_LOW);
+        assert_eq!(textured, tile_pixel(&frame, 12, 10, 0));
+
+        // The secondary yellow is drawn instead of red
+        let [_, r, g, b] = channels(tile_pixel(&frame, 14, 10, 0));
+        assert!(r > b && g > b);
+
+        // Half tiles keep the underlay below the diagonal, turned by the rotation
+        assert_eq!(blue, tile_pixel(&frame, 20, 10, 0));
+        assert_eq!(green, tile_pixel(&frame, 20, 10, 3));
+        assert_eq!(blue, tile_pixel(&frame, 22, 10, 3));
+        assert_eq!(green, tile_pixel(&frame, 22, 10, 15));
+
+        // The bridge on plane 1 is drawn over plane 0
+        assert_eq!(blue, tile_pixel(&frame, 40, 40, 0));
+    }
+}
@@ -0,0 +1,988 @@
+use crate::hsl::{self, BRIGHTNESS_MIN};
+use osrs_bytes::ReadExt;
+use serde_json::json;
+use std::{collections::BTreeSet, fmt::Write as _, io};
+use thiserror::Error;
+
+/// The archive holding the models.
+pub const MODELS_ARCHIVE: u8 = 7;
+
+/// The face render type drawn with a single colour rather than shaded.
+pub const RENDER_TYPE_FLAT: u8 = 1;
+
+const TEXTURE_TYPE_PLANAR: u8 = 0;
+const TEXTURE_TYPE_CYLINDRICAL: u8 = 1;
+const TEXTURE_TYPE_CUBE: u8 = 2;
+const TEXTURE_TYPE_SPHERICAL: u8 = 3;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ModelError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("model section out of bounds at offset {0}")]
+    SectionOutOfBounds(usize),
+    #[error("unsupported texture render type: {0}")]
+    UnsupportedTextureType(u8),
+}
+
+/// The layout of an encoded model, identified by the last two bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModelFormat {
+    /// The original format with an 18 byte footer and no marker
+    Old,
+    /// The new format with texture render types and a 23 byte footer, marked by `FF FF`
+    New,
+    /// The original format with animaya groups, marked by `FF FE`
+    OldAnimaya,
+    /// The new format with animaya groups, marked by `FF FD`
+    NewAnimaya,
+}
+
+impl ModelFormat {
+    fn detect(buf: &[u8]) -> ModelFormat {
+        match buf {
+            [.., 0xff, 0xfd] => ModelFormat::NewAnimaya,
+            [.., 0xff, 0xfe] => ModelFormat::OldAnimaya,
+            [.., 0xff, 0xff] => ModelFormat::New,
+            _ => ModelFormat::Old,
+        }
+    }
+
+    fn is_new(self) -> bool {
+        matches!(self, ModelFormat::New | ModelFormat::NewAnimaya)
+    }
+
+    fn has_animaya(self) -> bool {
+        matches!(self, ModelFormat::OldAnimaya | ModelFormat::NewAnimaya)
+    }
+
+    fn footer_len(self) -> usize {
+        match self {
+            ModelFormat::Old => 18,
+            ModelFormat::New | ModelFormat::OldAnimaya => 23,
+            ModelFormat::NewAnimaya => 26,
+        }
+    }
+}
+
+/// A triangle of a model.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Face {
+    /// The indices of the three vertices
+    pub vertices: [u16; 3],
+    /// The packed HSL colour
+    pub colour: u16,
+    pub render_type: u8,
+    /// The draw priority, the model's priority unless it has one per face
+    pub priority: u8,
+    /// The transparency, 0 being opaque
+    pub alpha: u8,
+    /// The label grouping the face for animations
+    pub skin: Option<u8>,
+    /// The texture drawn on the face
+    pub texture: Option<u16>,
+    /// The texture triangle mapping the texture, or `None` to map it onto the face itself
+    pub texture_coords: Option<u8>,
+}
+
+/// How a texture triangle maps its texture onto the faces using it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextureMapping {
+    /// The texture is stretched across the triangle
+    #[default]
+    Planar,
+    /// The texture is wrapped around a cylinder
+    Cylindrical(TextureProjection),
+    /// The texture is projected onto the sides of a cube, offset by `(u, v)`
+    Cube {
+        projection: TextureProjection,
+        translation: (i8, i8),
+    },
+    /// The texture is wrapped around a sphere
+    Spherical(TextureProjection),
+}
+
+/// The shape of a texture projected by a cylindrical, cube or spherical
+/// texture triangle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextureProjection {
+    /// The size of the shape along each axis
+    pub scale: [u16; 3],
+    pub rotation: i8,
+    pub direction: i8,
+    /// How fast the texture scrolls
+    pub speed: i8,
+}
+
+/// A 3D model from the models archive. The client's y axis points down.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Model {
+    pub format: ModelFormat,
+    pub vertices: Vec<[i32; 3]>,
+    /// The label grouping each vertex for animations
+    pub vertex_skins: Option<Vec<u8>>,
+    pub faces: Vec<Face>,
+    /// The priority of every face, or 255 if each face has its own
+    pub priority: u8,
+    /// The vertices `(p, m, n)` mapping textures onto faces
+    pub texture_triangles: Vec<[u16; 3]>,
+    /// How each texture triangle maps its texture. Old models only have
+    /// planar mappings.
+    pub texture_mappings: Vec<TextureMapping>,
+    /// The `(group, scale)` pairs of each vertex for skeletal animations
+    pub animaya_groups: Option<Vec<Vec<(u8, u8)>>>,
+}
+
+/// The flags read from a model's footer.
+struct Header {
+    vertex_count: usize,
+    face_count: usize,
+    texture_count: usize,
+    /// The mapping type of each texture triangle
+    texture_types: Vec<u8>,
+    has_render_types: bool,
+    priority: u8,
+    has_alpha: bool,
+    has_face_skins: bool,
+    has_textures: bool,
+    has_vertex_skins: bool,
+    has_animaya: bool,
+    x_len: usize,
+    y_len: usize,
+    z_len: usize,
+    face_index_len: usize,
+    texture_coords_len: usize,
+}
+
+impl Header {
+    fn read(format: ModelFormat, buf: &[u8]) -> Result<Header, ModelError> {
+        let mut footer = section(buf, buf.len().saturating_sub(format.footer_len()))?;
+
+        let vertex_count = footer.read_u16()? as usize;
+        let face_count = footer.read_u16()? as usize;
+        let texture_count = footer.read_u8()? as usize;
+        let has_render_types = footer.read_u8()? == 1;
+        let priority = footer.read_u8()?;
+        let has_alpha = footer.read_u8()? == 1;
+        let has_face_skins = footer.read_u8()? == 1;
+        let has_textures = format.is_new() && footer.read_u8()? == 1;
+        let has_vertex_skins = footer.read_u8()? == 1;
+        let has_animaya = format.has_animaya() && footer.read_u8()? == 1;
+        let x_len = footer.read_u16()? as usize;
+        let y_len = footer.read_u16()? as usize;
+        let z_len = footer.read_u16()? as usize;
+        let face_index_len = footer.read_u16()? as usize;
+        let texture_coords_len = if format.is_new() {
+            footer.read_u16()? as usize
+        } else {
+            0
+        };
+        let texture_types = if format.is_new() {
+            buf.get(..texture_count)
+                .ok_or(ModelError::SectionOutOfBounds(0))?
+                .to_vec()
+        } else {
+            vec![TEXTURE_TYPE_PLANAR; texture_count]
+        };
+        if let Some(texture_type) = texture_types
+            .iter()
+            .find(|texture_type| **texture_type > TEXTURE_TYPE_SPHERICAL)
+        {
+            return Err(ModelError::UnsupportedTextureType(*texture_type));
+        }
+
+        Ok(Header {
+            vertex_count,
+            face_count,
+            texture_count,
+            texture_types,
+            has_render_types,
+            priority,
+            has_alpha,
+            has_face_skins,
+            has_textures,
+            has_vertex_skins,
+            has_animaya,
+            x_len,
+            y_len,
+            z_len,
+            face_index_len,
+            texture_coords_len,
+        })
+    }
+
+    /// Count the texture triangles of the given types
+    fn texture_count(&self, types: &[u8]) -> usize {
+        self.texture_types
+            .iter()
+            .filter(|texture_type| types.contains(texture_type))
+            .count()
+    }
+}
+
+/// The offsets of the sections of a model, which are stored one after the
+/// other in an order that depends on the format.
+#[derive(Default)]
+struct Offsets {
+    vertex_flags: usize,
+    render_types: usize,
+    face_compression: usize,
+    priorities: usize,
+    face_skins: usize,
+    vertex_skins: usize,
+    alphas: usize,
+    face_indices: usize,
+    face_textures: usize,
+    texture_coords: usize,
+    colours: usize,
+    xs: usize,
+    ys: usize,
+    zs: usize,
+    texture_triangles: usize,
+    texture_projections: usize,
+    texture_scales: usize,
+    texture_rotations: usize,
+    texture_directions: usize,
+    texture_speeds: usize,
+    animaya: usize,
+}
+
+impl Offsets {
+    fn new(format: ModelFormat, header: &Header) -> Offsets {
+        let mut offset = 0;
+        let mut next = |len: usize| {
+            let start = offset;
+            offset += len;
+            start
+        };
+        let optional = |present: bool, len: usize| if present { len } else { 0 };
+        let faces = header.face_count;
+
+        if format.is_new() {
+            let planar = header.texture_count(&[TEXTURE_TYPE_PLANAR]);
+            let projected = header.texture_count(&[
+                TEXTURE_TYPE_CYLINDRICAL,
+                TEXTURE_TYPE_CUBE,
+                TEXTURE_TYPE_SPHERICAL,
+            ]);
+            let cube = header.texture_count(&[TEXTURE_TYPE_CUBE]);
+
+            next(header.texture_count);
+            Offsets {
+                vertex_flags: next(header.vertex_count),
+                render_types: next(optional(header.has_render_types, faces)),
+                face_compression: next(faces),
+                priorities: next(optional(header.priority == 255, faces)),
+                face_skins: next(optional(header.has_face_skins, faces)),
+                vertex_skins: next(optional(header.has_vertex_skins, header.vertex_count)),
+                alphas: next(optional(header.has_alpha, faces)),
+                face_indices: next(header.face_index_len),
+                face_textures: next(optional(header.has_textures, faces * 2)),
+                texture_coords: next(header.texture_coords_len),
+                colours: next(faces * 2),
+                xs: next(header.x_len),
+                ys: next(header.y_len),
+                zs: next(header.z_len),
+                texture_triangles: next(planar * 6),
+                texture_projections: next(projected * 6),
+                texture_scales: next(projected * 6),
+                texture_rotations: next(projected),
+                texture_directions: next(projected),
+                // Cube mappings follow their speed with a translation
+                texture_speeds: next(projected + cube * 2),
+                animaya: next(0),
+            }
+        } else {
+            let vertex_flags = next(header.vertex_count);
+            let face_compression = next(faces);
+            let priorities = next(optional(header.priority == 255, faces));
+            let face_skins = next(optional(header.has_face_skins, faces));
+            // Old models pack the render type and texture flag of each face into one byte
+            let render_types = next(optional(header.has_render_types, faces));
+            Offsets {
+                vertex_flags,
+                render_types,
+                face_compression,
+                priorities,
+                face_skins,
+                vertex_skins: next(optional(header.has_vertex_skins, header.vertex_count)),
+                alphas: next(optional(header.has_alpha, faces)),
+                face_indices: next(header.face_index_len),
+                colours: next(faces * 2),
+                texture_triangles: next(header.texture_count * 6),
+                xs: next(header.x_len),
+                ys: next(header.y_len),
+                zs: next(header.z_len),
+                animaya: next(0),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+impl Model {
+    /// Decode a model, detecting its format from the last two bytes
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The contents of file 0 of a models group
+    pub fn decode(buf: &[u8]) -> Result<Model, ModelError> {
+        let format = ModelFormat::detect(buf);
+        let header = Header::read(format, buf)?;
+        let offsets = Offsets::new(format, &header);
+
+        let mut model = Model {
+            format,
+            vertices: Vec::with_capacity(header.vertex_count),
+            vertex_skins: header.has_vertex_skins.then(Vec::new),
+            faces: vec![Face::default(); header.face_count],
+            priority: header.priority,
+            texture_triangles: Vec::with_capacity(header.texture_count),
+            texture_mappings: Vec::with_capacity(header.texture_count),
+            animaya_groups: None,
+        };
+
+        model.decode_vertices(buf, &header, &offsets)?;
+        model.decode_faces(buf, &header, &offsets)?;
+        model.decode_face_indices(buf, &offsets)?;
+
+        model.decode_texture_triangles(buf, &header, &offsets)?;
+
+        if header.has_animaya {
+            let mut animaya = section(buf, offsets.animaya)?;
+            let mut groups = Vec::with_capacity(header.vertex_count);
+            for _ in 0..header.vertex_count {
+                let len = animaya.read_u8()?;
+                let mut vertex = Vec::with_capacity(len as usize);
+                for _ in 0..len {
+                    vertex.push((animaya.read_u8()?, animaya.read_u8()?));
+                }
+                groups.push(vertex);
+            }
+            model.animaya_groups = Some(groups);
+        }
+
+        Ok(model)
+    }
+
+    fn decode_vertices(
+        &mut self,
+        buf: &[u8],
+        header: &Header,
+        offsets: &Offsets,
+    ) -> Result<(), ModelError> {
+        let mut flags = section(buf, offsets.vertex_flags)?;
+        let mut xs = section(buf, offsets.xs)?;
+        let mut ys = section(buf, offsets.ys)?;
+        let mut zs = section(buf, offsets.zs)?;
+        let mut skins = section(buf, offsets.vertex_skins)?;
+
+        let mut position = [0; 3];
+        for _ in 0..header.vertex_count {
+            let flag = flags.read_u8()?;
+            for (axis, deltas) in [&mut xs, &mut ys, &mut zs].into_iter().enumerate() {
+                if flag & (1 << axis) != 0 {
+                    position[axis] += deltas.read_i16_smart()? as i32;
+                }
+            }
+            self.vertices.push(position);
+
+            if let Some(vertex_skins) = &mut self.vertex_skins {
+                vertex_skins.push(skins.read_u8()?);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decode_faces(
+        &mut self,
+        buf: &[u8],
+        header: &Header,
+        offsets: &Offsets,
+    ) -> Result<(), ModelError> {
+        let mut colours = section(buf, offsets.colours)?;
+        let mut render_types = section(buf, offsets.render_types)?;
+        let mut priorities = section(buf, offsets.priorities)?;
+        let mut alphas = section(buf, offsets.alphas)?;
+        let mut skins = section(buf, offsets.face_skins)?;
+        let mut textures = section(buf, offsets.face_textures)?;
+        let mut texture_coords = section(buf, offsets.texture_coords)?;
+
+        for face in self.faces.iter_mut() {
+            face.colour = colours.read_u16()?;
+
+            if header.has_render_types {
+                let render_type = render_types.read_u8()?;
+                if self.format.is_new() {
+                    face.render_type = render_type;
+                } else {
+                    face.render_type = render_type & 0x1;
+                    if render_type & 0x2 != 0 {
+                        face.texture = Some(face.colour);
+                        face.texture_coords = Some(render_type >> 2);
+                        face.colour = 127;
+                    }
+                }
+            }
+            face.priority = if header.priority == 255 {
+                priorities.read_u8()?
+            } else {
+                header.priority
+            };
+            if header.has_alpha {
+                face.alpha = alphas.read_u8()?;
+            }
+            if header.has_face_skins {
+                face.skin = Some(skins.read_u8()?);
+            }
+            if header.has_textures {
+                face.texture = textures.read_u16()?.checked_sub(1);
+                if face.texture.is_some() && header.texture_count > 0 {
+                    face.texture_coords = texture_coords.read_u8()?.checked_sub(1);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decode_texture_triangles(
+        &mut self,
+        buf: &[u8],
+        header: &Header,
+        offsets: &Offsets,
+    ) -> Result<(), ModelError> {
+        let mut triangles = section(buf, offsets.texture_triangles)?;
+        let mut projections = section(buf, offsets.texture_projections)?;
+        let mut scales = section(buf, offsets.texture_scales)?;
+        let mut rotations = section(buf, offsets.texture_rotations)?;
+        let mut directions = section(buf, offsets.texture_directions)?;
+        let mut speeds = section(buf, offsets.texture_speeds)?;
+
+        for &texture_type in &header.texture_types {
+            let vertices = if texture_type == TEXTURE_TYPE_PLANAR {
+                &mut triangles
+            } else {
+                &mut projections
+            };
+            self.texture_triangles.push([
+                vertices.read_u16()?,
+                vertices.read_u16()?,
+                vertices.read_u16()?,
+            ]);
+            if texture_type == TEXTURE_TYPE_PLANAR {
+                self.texture_mappings.push(TextureMapping::Planar);
+                continue;
+            }
+
+            let projection = TextureProjection {
+                scale: [scales.read_u16()?, scales.read_u16()?, scales.read_u16()?],
+                rotation: rotations.read_i8()?,
+                direction: directions.read_i8()?,
+                speed: speeds.read_i8()?,
+            };
+            self.texture_mappings.push(match texture_type {
+                TEXTURE_TYPE_CYLINDRICAL => TextureMapping::Cylindrical(projection),
+                TEXTURE_TYPE_CUBE => TextureMapping::Cube {
+                    projection,
+                    translation: (speeds.read_i8()?, speeds.read_i8()?),
+                },
+                _ => TextureMapping::Spherical(projection),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn decode_face_indices(&mut self, buf: &[u8], offsets: &Offsets) -> Result<(), ModelError> {
+        let mut compression = section(buf, offsets.face_compression)?;
+        let mut indices = section(buf, offsets.face_indices)?;
+
+        let (mut a, mut b, mut c, mut last) = (0, 0, 0, 0);
+        let mut next = |indices: &mut &[u8]| -> Result<i32, ModelError> {
+            last += indices.read_i16_smart()? as i32;
+            Ok(last)
+        };
+        for face in self.faces.iter_mut() {
+            match compression.read_u8()? {
+                1 => {
+                    a = next(&mut indices)?;
+                    b = next(&mut indices)?;
+                    c = next(&mut indices)?;
+                }
+                2 => {
+                    b = c;
+                    c = next(&mut indices)?;
+                }
+                3 => {
+                    a = c;
+                    c = next(&mut indices)?;
+                }
+                4 => {
+                    std::mem::swap(&mut a, &mut b);
+                    c = next(&mut indices)?;
+                }
+                _ => {}
+            }
+            face.vertices = [a as u16, b as u16, c as u16];
+        }
+
+        Ok(())
+    }
+
+    /// Calculate the texture coordinates of the vertices of every textured
+    /// face by projecting them onto the face's texture triangle. Cylindrical,
+    /// cube and spherical mappings are approximated by a planar projection
+    /// through their triangle.
+    pub fn texture_uvs(&self) -> Vec<Option<[[f32; 2]; 3]>> {
+        self.faces
+            .iter()
+            .map(|face| {
+                face.texture?;
+                let [p, m, n] = match face.texture_coords {
+                    Some(coords) => *self.texture_triangles.get(coords as usize)?,
+                    None => face.vertices,
+                };
+                let vertex = |index: u16| {
+                    self.vertices
+                        .get(index as usize)
+                        .map(|v| v.map(|c| c as f32))
+                };
+                let (p, m, n) = (vertex(p)?, vertex(m)?, vertex(n)?);
+
+                let pm = sub(m, p);
+                let pn = sub(n, p);
+                let normal = cross(pm, pn);
+                let u_axis = cross(pn, normal);
+                let v_axis = cross(pm, normal);
+                let u_scale = 1.0 / dot(u_axis, pm);
+                let v_scale = 1.0 / dot(v_axis, pn);
+
+                let mut uvs = [[0.0; 2]; 3];
+                for (uv, index) in uvs.iter_mut().zip(face.vertices) {
+                    let pa = sub(vertex(index)?, p);
+                    *uv = [dot(u_axis, pa) * u_scale, dot(v_axis, pa) * v_scale];
+                }
+                Some(uvs)
+            })
+            .collect()
+    }
+
+    /// Export the model as a Wavefront OBJ, flipping the y and z axes so that
+    /// up is positive y. Faces use the materials written by [`Model::to_mtl`].
+    ///
+    /// # Arguments
+    ///
+    /// * `mtl_file` - The name of the material library to reference
+    pub fn to_obj(&self, mtl_file: &str) -> String {
+        let mut obj = String::new();
+        let _ = writeln!(obj, "mtllib {mtl_file}");
+
+        for [x, y, z] in &self.vertices {
+            let _ = writeln!(obj, "v {} {} {}", x, -y, -z);
+        }
+
+        let uvs = self.texture_uvs();
+        for uv in uvs.iter().flatten().flatten() {
+            let _ = writeln!(obj, "vt {} {}", uv[0], 1.0 - uv[1]);
+        }
+
+        let mut material = None;
+        let mut next_uv = 1;
+        for (face, uv) in self.faces.iter().zip(&uvs) {
+            let name = material_name(face);
+            if material.as_ref() != Some(&name) {
+                let _ = writeln!(obj, "usemtl {name}");
+                material = Some(name);
+            }
+
+            let [a, b, c] = face.vertices.map(|v| v as usize + 1);
+            if uv.is_some() {
+                let _ = writeln!(
+                    obj,
+                    "f {a}/{} {b}/{} {c}/{}",
+                    next_uv,
+                    next_uv + 1,
+                    next_uv + 2
+                );
+                next_uv += 3;
+            } else {
+                let _ = writeln!(obj, "f {a} {b} {c}");
+            }
+        }
+
+        obj
+    }
+
+    /// Export the materials used by [`Model::to_obj`], one per colour and
+    /// alpha, and one per texture referencing `{texture}.png`
+    pub fn to_mtl(&self) -> String {
+        let mut mtl = String::new();
+        let mut written = BTreeSet::new();
+        for face in &self.faces {
+            let name = material_name(face);
+            if written.contains(&name) {
+                continue;
+            }
+            let _ = writeln!(mtl, "newmtl {name}");
+            match face.texture {
+                Some(texture) => {
+                    let _ = writeln!(mtl, "Kd 1 1 1");
+                    let _ = writeln!(mtl, "map_Kd {texture}.png");
+                }
+                None => {
+                    let [r, g, b] = rgb(face.colour);
+                    let _ = writeln!(mtl, "Kd {r} {g} {b}");
+                }
+            }
+            if face.alpha != 0 {
+                let _ = writeln!(mtl, "d {}", opacity(face.alpha));
+            }
+            written.insert(name);
+        }
+
+        mtl
+    }
+
+    /// Export the model as a binary glTF (GLB) with a colour per vertex,
+    /// flipping the y and z axes so that up is positive y
+    pub fn to_glb(&self) -> Vec<u8> {
+        let mut positions = Vec::with_capacity(self.faces.len() * 3);
+        let mut colours = Vec::with_capacity(self.faces.len() * 3);
+        for face in &self.faces {
+            let [r, g, b] = rgb(face.colour);
+            for vertex in face.vertices {
+                let [x, y, z] = self
+                    .vertices
+                    .get(vertex as usize)
+                    .copied()
+                    .unwrap_or_default();
+                positions.push([x as f32, -y as f32, -z as f32]);
+                colours.push([r, g, b, opacity(face.alpha)]);
+            }
+        }
+
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for position in &positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        if positions.is_empty() {
+            (min, max) = ([0.0; 3], [0.0; 3]);
+        }
+
+        let mut bin = Vec::with_capacity(positions.len() * 28);
+        for value in positions.iter().flatten() {
+            bin.extend_from_slice(&value.to_le_bytes());
+        }
+        let positions_len = bin.len();
+        for value in colours.iter().flatten() {
+            bin.extend_from_slice(&value.to_le_bytes());
+        }
+        let colours_len = bin.len() - positions_len;
+
+        let translucent = self.faces.iter().any(|face| face.alpha != 0);
+        let gltf = json!({
+            "asset": { "version": "2.0", "generator": "rs2-cache" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{
+                "primitives": [{
+                    "attributes": { "POSITION": 0, "COLOR_0": 1 },
+                    "material": 0,
+                }],
+            }],
+            "materials": [{
+                "pbrMetallicRoughness": { "metallicFactor": 0.0, "roughnessFactor": 1.0 },
+                "alphaMode": if translucent { "BLEND" } else { "OPAQUE" },
+                "doubleSided": true,
+            }],
+            "buffers": [{ "byteLength": bin.len() }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": positions_len },
+                { "buffer": 0, "byteOffset": positions_len, "byteLength": colours_len },
+            ],
+            "accessors": [
+                {
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": positions.len(),
+                    "type": "VEC3",
+                    "min": min,
+                    "max": max,
+                },
+                {
+                    "bufferView": 1,
+                    "componentType": 5126,
+                    "count": colours.len(),
+                    "type": "VEC4",
+                },
+            ],
+        });
+
+        let mut json = gltf.to_string().into_bytes();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        while !bin.len().is_multiple_of(4) {
+            bin.push(0);
+        }
+
+        let len = 12 + 8 + json.len() + 8 + bin.len();
+        let mut glb = Vec::with_capacity(len);
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(len as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"JSON");
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"BIN\0");
+        glb.extend_from_slice(&bin);
+        glb
+    }
+}
+
+/// Get a section of a model starting at an offset
+fn section(buf: &[u8], offset: usize) -> Result<&[u8], ModelError> {
+    buf.get(offset..)
+        .ok_or(ModelError::SectionOutOfBounds(offset))
+}
+
+fn material_name(face: &Face) -> String {
+    match (face.texture, face.alpha) {
+        (Some(texture), 0) => format!("t{texture}"),
+        (Some(texture), alpha) => format!("t{texture}_{alpha}"),
+        (None, 0) => format!("c{}", face.colour),
+        (None, alpha) => format!("c{}_{alpha}", face.colour),
+    }
+}
+
+/// Convert a packed HSL colour to sRGB components between 0 and 1
+fn rgb(colour: u16) -> [f32; 3] {
+    let rgb = hsl::hsl_to_rgb(colour, BRIGHTNESS_MIN);
+    [rgb >> 16, rgb >> 8, rgb].map(|channel| (channel & 0xff) as f32 / 255.0)
+}
+
+fn opacity(alpha: u8) -> f32 {
+    1.0 - alpha as f32 / 255.0
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    const VERTICES: [[i32; 3]; 4] = [[0, 0, 0], [100, 0, 0], [0, -100, 0], [0, 0, 100]];
+    const FACES: [[u16; 3]; 4] = [[0, 1, 2], [0, 2, 3], [3, 2, 1], [2, 3, 0]];
+
+    #[test]
+    fn test_decode_old() {
+        let mut cache = Cache::open("tests/data/cache/cache-models").unwrap();
+        let model = cache.model(0).unwrap();
+
+        assert_eq!(ModelFormat::Old, model.format);
+        assert_eq!(VERTICES.to_vec(), model.vertices);
+        assert_eq!(Some(vec![0, 1, 1, 2]), model.vertex_skins);
+        assert_eq!(FACES.to_vec(), {
+            model.faces.iter().map(|f| f.vertices).collect::<Vec<_>>()
+        });
+        assert_eq!(vec![[0, 1, 2]], model.texture_triangles);
+        assert_eq!(vec![TextureMapping::Planar], model.texture_mappings);
+        assert_eq!(10, model.priority);
+
+        let faces = &model.faces;
+        assert!(faces.iter().all(|face| face.priority == 10));
+        assert_eq!(
+            Face {
+                vertices: [0, 2, 3],
+                colour: 0x2345,
+                render_type: RENDER_TYPE_FLAT,
+                priority: 10,
+                alpha: 0,
+                skin: Some(1),
+                texture: None,
+                texture_coords: None,
+            },
+            faces[1]
+        );
+        // Textured faces of old models store the texture in place of the colour
+        assert_eq!((127, Some(7), Some(0)), {
+            (faces[2].colour, faces[2].texture, faces[2].texture_coords)
+        });
+        assert_eq!(128, faces[3].alpha);
+        assert_eq!(None, model.animaya_groups);
+    }
+
+    #[test]
+    fn test_decode_new() {
+        let mut cache = Cache::open("tests/data/cache/cache-models").unwrap();
+        let model = cache.model(1).unwrap();
+
+        assert_eq!(ModelFormat::New, model.format);
+        assert_eq!(VERTICES.to_vec(), model.vertices);
+        assert_eq!(Some(vec![3, 3, 4, 4]), model.vertex_skins);
+        assert_eq!(vec![[3, 2, 1]], model.texture_triangles);
+        assert_eq!(255, model.priority);
+
+        let faces = &model.faces;
+        assert_eq!(FACES.to_vec(), {
+            faces.iter().map(|f| f.vertices).collect::<Vec<_>>()
+        });
+        assert_eq!(vec![1, 2, 3, 4], {
+            faces.iter().map(|f| f.priority).collect::<Vec<_>>()
+        });
+        assert_eq!(vec![0, 1, 0, 2], {
+            faces.iter().map(|f| f.render_type).collect::<Vec<_>>()
+        });
+        assert_eq!((Some(5), None), (faces[0].texture, faces[0].texture_coords));
+        assert_eq!((None, None), (faces[1].texture, faces[1].texture_coords));
+        assert_eq!(
+            (Some(9), Some(0)),
+            (faces[2].texture, faces[2].texture_coords)
+        );
+        assert_eq!(
+            (0x3333, 255, None),
+            (faces[2].colour, faces[2].alpha, faces[2].skin)
+        );
+    }
+
+    #[test]
+    fn test_decode_texture_mappings() {
+        let mut buf = vec![TEXTURE_TYPE_CYLINDRICAL, TEXTURE_TYPE_CUBE];
+        // Three vertices at the origin and one face using the cube mapping
+        buf.extend([0, 0, 0, 1, 64, 65, 65, 0, 6, 2, 0x12, 0x34]);
+        buf.extend([0, 0, 0, 1, 0, 2, 0, 2, 0, 1, 0, 0]);
+        buf.extend([0, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6]);
+        buf.extend([7, 8, 9, 10, 11, 12, 13, 0xff]);
+        buf.extend([
+            0, 3, 0, 1, 2, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 1,
+        ]);
+        buf.extend([0xff, 0xff]);
+
+        let model = Model::decode(&buf).unwrap();
+        assert_eq!(vec![[0, 1, 2], [2, 1, 0]], model.texture_triangles);
+        assert_eq!(
+            vec![
+                TextureMapping::Cylindrical(TextureProjection {
+                    scale: [1, 2, 3],
+                    rotation: 7,
+                    direction: 9,
+                    speed: 11,
+                }),
+                TextureMapping::Cube {
+                    projection: TextureProjection {
+                        scale: [4, 5, 6],
+                        rotation: 8,
+                        direction: 10,
+                        speed: 12,
+                    },
+                    translation: (13, -1),
+                },
+            ],
+            model.texture_mappings
+        );
+        assert_eq!(
+            (Some(5), Some(1), 0x1234),
+            (
+                model.faces[0].texture,
+                model.faces[0].texture_coords,
+                model.faces[0].colour
+            )
+        );
+    }
+
+    #[test]
+    fn test_decode_animaya() {
+        let mut cache = Cache::open("tests/data/cache/cache-models").unwrap();
+        let expected = Some(vec![
+            vec![(1, 255)],
+            vec![],
+            vec![(1, 128), (2, 127)],
+            vec![(3, 255)],
+        ]);
+
+        let new = cache.model(2).unwrap();
+        assert_eq!(ModelFormat::NewAnimaya, new.format);
+        assert_eq!(expected, new.animaya_groups);
+        assert_eq!(cache.model(1).unwrap().faces, new.faces);
+
+        let old = cache.model(3).unwrap();
+        assert_eq!(ModelFormat::OldAnimaya, old.format);
+        assert_eq!(expected, old.animaya_groups);
+        assert_eq!(cache.model(0).unwrap().faces, old.faces);
+    }
+
+    #[test]
+    fn test_texture_uvs() {
+        let mut cache = Cache::open("tests/data/cache/cache-models").unwrap();
+        let uvs = cache.model(1).unwrap().texture_uvs();
+
+        assert_eq!(Some([[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]), uvs[0]);
+        assert_eq!(None, uvs[1]);
+        // Mapped by the texture triangle (3, 2, 1), so vertex 3 is the origin
+        assert_eq!([0.0, 1.0], uvs[2].unwrap()[2]);
+    }
+
+    #[test]
+    fn test_to_obj() {
+        let mut cache = Cache::open("tests/data/cache/cache-models").unwrap();
+        let model = cache.model(1).unwrap();
+
+        let obj = model.to_obj("model.mtl");
+        let lines: Vec<&str> = obj.lines().collect();
+        assert_eq!("mtllib model.mtl", lines[0]);
+        assert_eq!("v 0 100 0", lines[3]);
+        assert_eq!(6, lines.iter().filter(|l| l.starts_with("vt ")).count());
+        assert!(lines.contains(&"usemtl t5"));
+        assert!(lines.contains(&"f 1/1 2/2 3/3"));
+        assert!(lines.contains(&"f 1 3 4"));
+
+        let mtl = model.to_mtl();
+        assert_eq!(4, mtl.matches("newmtl").count());
+        assert!(mtl.contains("map_Kd 9.png\nd 0\n"));
+    }
+
+    #[test]
+    fn test_to_glb() {
+        let mut cache = Cache::open("tests/data/cache/cache-models").unwrap();
+        let glb = cache.model(0).unwrap().to_glb();
+
+        assert_eq!(b"glTF", &glb[..4]);
+        assert_eq!(
+            glb.len() as u32,
+            u32::from_le_bytes(glb[8..12].try_into().unwrap())
+        );
+
+        let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        let json: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+        assert_eq!(12, json["accessors"][0]["count"]);
+        assert_eq!("BLEND", json["materials"][0]["alphaMode"]);
+        assert_eq!(
+            serde_json::json!([0.0, 0.0, -100.0]),
+            json["accessors"][0]["min"]
+        );
+    }
+}
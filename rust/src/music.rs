@@ -0,0 +1,638 @@
+use osrs_bytes::{ReadExt, WriteExt};
+use std::io::{self, Write};
+use thiserror::Error;
+
+/// The archive holding the songs played by the music player.
+pub const SONGS_ARCHIVE: u8 = 6;
+/// The archive holding the jingles played on events such as levelling up.
+pub const JINGLES_ARCHIVE: u8 = 11;
+/// The archive holding the music of newer caches, stored in its own data file.
+pub const MUSIC_ARCHIVE: u8 = 40;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum MusicError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("unknown event opcode {0}")]
+    UnknownOpcode(u8),
+    #[error("expected a {0} chunk")]
+    MissingChunk(&'static str),
+    #[error("a track can't hold {0} tracks")]
+    TooManyTracks(u16),
+    #[error("event without a status byte")]
+    MissingStatus,
+    #[error("unsupported midi status {0:#04x}")]
+    UnsupportedStatus(u8),
+}
+
+const NOTE_ON: u8 = 0;
+const NOTE_OFF: u8 = 1;
+const CONTROL_CHANGE: u8 = 2;
+const PITCH_BEND: u8 = 3;
+const CHANNEL_PRESSURE: u8 = 4;
+const KEY_PRESSURE: u8 = 5;
+const PROGRAM_CHANGE: u8 = 6;
+const END_OF_TRACK: u8 = 7;
+const TEMPO: u8 = 23;
+
+/// The midi status of each channel event type, without the channel.
+const STATUSES: [u8; 7] = [0x90, 0x80, 0xb0, 0xe0, 0xd0, 0xa0, 0xc0];
+
+const META: u8 = 0xff;
+const META_END_OF_TRACK: u8 = 0x2f;
+const META_TEMPO: u8 = 0x51;
+const SYSEX: u8 = 0xf0;
+const SYSEX_ESCAPE: u8 = 0xf7;
+
+// The streams of event fields following the controllers, in stored order
+const SWITCHES: usize = 0;
+const KEY_PRESSURES: usize = 1;
+const CHANNEL_PRESSURES: usize = 2;
+const PITCH_BENDS_HIGH: usize = 3;
+const MODULATIONS: usize = 4;
+const VOLUMES: usize = 5;
+const PANS: usize = 6;
+const KEYS: usize = 7;
+const NOTE_ON_VELOCITIES: usize = 8;
+const CONTROLS: usize = 9;
+const NOTE_OFF_VELOCITIES: usize = 10;
+const MODULATIONS_LSB: usize = 11;
+const VOLUMES_LSB: usize = 12;
+const PANS_LSB: usize = 13;
+const PROGRAMS: usize = 14;
+const PITCH_BENDS_LOW: usize = 15;
+const NRPNS_MSB: usize = 16;
+const NRPNS_LSB: usize = 17;
+const RPNS_MSB: usize = 18;
+const RPNS_LSB: usize = 19;
+const TEMPOS: usize = 20;
+const STREAMS: usize = 21;
+
+/// Get the stream holding the values of a controller. Bank selects share
+/// the stream of program changes.
+fn controller_stream(controller: u8) -> usize {
+    match controller {
+        0 | 32 => PROGRAMS,
+        1 => MODULATIONS,
+        33 => MODULATIONS_LSB,
+        7 => VOLUMES,
+        39 => VOLUMES_LSB,
+        10 => PANS,
+        42 => PANS_LSB,
+        99 => NRPNS_MSB,
+        98 => NRPNS_LSB,
+        101 => RPNS_MSB,
+        100 => RPNS_LSB,
+        64 | 65 | 120 | 121 | 123 => SWITCHES,
+        _ => CONTROLS,
+    }
+}
+
+/// The running values events are stored relative to, shared by every track.
+struct State {
+    channel: u8,
+    key: u8,
+    note_on_velocity: u8,
+    note_off_velocity: u8,
+    key_pressure: u8,
+    channel_pressure: u8,
+    pitch_bend: u16,
+    controller: u8,
+    controller_values: [u8; 128],
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            channel: 0,
+            key: 0,
+            note_on_velocity: 0,
+            note_off_velocity: 0,
+            key_pressure: 0,
+            channel_pressure: 0,
+            pitch_bend: 0,
+            controller: 0,
+            controller_values: [0; 128],
+        }
+    }
+}
+
+/// A song or jingle, stored in the cache as a midi file with its events
+/// split into streams of deltas that compress well.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Track {
+    /// The track as a standard midi file
+    pub midi: Vec<u8>,
+}
+
+impl Track {
+    /// Decode a track, rebuilding its midi file
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The encoded track
+    pub fn decode(buf: &[u8]) -> Result<Track, MusicError> {
+        let trailer = buf
+            .len()
+            .checked_sub(3)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        let (mut buf, mut trailer) = buf.split_at(trailer);
+        let tracks = trailer.read_u8()? as u16;
+        let division = trailer.read_u16()?;
+
+        let mut opcodes = Vec::new();
+        let mut counts = [0; STREAMS];
+        let mut controls = 0;
+        for _ in 0..tracks {
+            loop {
+                let opcode = buf.read_u8()?;
+                opcodes.push(opcode);
+                if opcode == END_OF_TRACK {
+                    break;
+                }
+                if opcode == TEMPO {
+                    counts[TEMPOS] += 3;
+                    continue;
+                }
+                match opcode & 0xf {
+                    NOTE_ON => {
+                        counts[KEYS] += 1;
+                        counts[NOTE_ON_VELOCITIES] += 1;
+                    }
+                    NOTE_OFF => {
+                        counts[KEYS] += 1;
+                        counts[NOTE_OFF_VELOCITIES] += 1;
+                    }
+                    CONTROL_CHANGE => controls += 1,
+                    PITCH_BEND => {
+                        counts[PITCH_BENDS_LOW] += 1;
+                        counts[PITCH_BENDS_HIGH] += 1;
+                    }
+                    CHANNEL_PRESSURE => counts[CHANNEL_PRESSURES] += 1,
+                    KEY_PRESSURE => {
+                        counts[KEYS] += 1;
+                        counts[KEY_PRESSURES] += 1;
+                    }
+                    PROGRAM_CHANGE => counts[PROGRAMS] += 1,
+                    _ => return Err(MusicError::UnknownOpcode(opcode)),
+                }
+            }
+        }
+
+        let deltas = (0..opcodes.len())
+            .map(|_| read_var_int(&mut buf))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let controllers = take(&mut buf, controls)?;
+        let mut controller = 0u8;
+        for &delta in controllers {
+            controller = controller.wrapping_add(delta) & 0x7f;
+            counts[controller_stream(controller)] += 1;
+        }
+
+        let mut streams: [&[u8]; STREAMS] = [&[]; STREAMS];
+        for (stream, count) in streams.iter_mut().zip(counts) {
+            *stream = take(&mut buf, count)?;
+        }
+
+        let mut midi = Vec::new();
+        midi.write_all(b"MThd")?;
+        midi.write_u32(6)?;
+        midi.write_u16(if tracks > 1 { 1 } else { 0 })?;
+        midi.write_u16(tracks)?;
+        midi.write_u16(division)?;
+
+        let mut state = State::default();
+        let mut controllers = controllers;
+        let mut events = opcodes.into_iter().zip(deltas);
+        for _ in 0..tracks {
+            midi.write_all(b"MTrk")?;
+            let start = midi.len();
+            midi.write_u32(0)?;
+
+            let mut last_type = None;
+            for (opcode, delta) in events.by_ref() {
+                write_var_int(&mut midi, delta)?;
+                // Consecutive events of the same type and channel use the
+                // running status
+                let status = last_type != Some(opcode);
+                last_type = Some(opcode & 0xf);
+
+                if opcode == END_OF_TRACK {
+                    midi.write_all(&[META, META_END_OF_TRACK, 0])?;
+                    break;
+                }
+                if opcode == TEMPO {
+                    midi.write_all(&[META, META_TEMPO, 3])?;
+                    midi.write_all(take(&mut streams[TEMPOS], 3)?)?;
+                    continue;
+                }
+
+                state.channel ^= opcode >> 4;
+                let event = opcode & 0xf;
+                if status {
+                    midi.write_u8(STATUSES[event as usize] | state.channel)?;
+                }
+                match event {
+                    NOTE_ON => {
+                        state.key = state.key.wrapping_add(streams[KEYS].read_u8()?);
+                        state.note_on_velocity = state
+                            .note_on_velocity
+                            .wrapping_add(streams[NOTE_ON_VELOCITIES].read_u8()?);
+                        midi.write_u8(state.key & 0x7f)?;
+                        midi.write_u8(state.note_on_velocity & 0x7f)?;
+                    }
+                    NOTE_OFF => {
+                        state.key = state.key.wrapping_add(streams[KEYS].read_u8()?);
+                        state.note_off_velocity = state
+                            .note_off_velocity
+                            .wrapping_add(streams[NOTE_OFF_VELOCITIES].read_u8()?);
+                        midi.write_u8(state.key & 0x7f)?;
+                        midi.write_u8(state.note_off_velocity & 0x7f)?;
+                    }
+                    CONTROL_CHANGE => {
+                        state.controller =
+                            state.controller.wrapping_add(controllers.read_u8()?) & 0x7f;
+                        let controller = state.controller;
+                        let value = &mut state.controller_values[controller as usize];
+                        *value =
+                            value.wrapping_add(streams[controller_stream(controller)].read_u8()?);
+                        midi.write_u8(controller)?;
+                        midi.write_u8(*value & 0x7f)?;
+                    }
+                    PITCH_BEND => {
+                        let low = streams[PITCH_BENDS_LOW].read_i8()? as u16;
+                        let high = streams[PITCH_BENDS_HIGH].read_i8()? as u16;
+                        state.pitch_bend = state.pitch_bend.wrapping_add(low);
+                        state.pitch_bend = state.pitch_bend.wrapping_add(high << 7);
+                        midi.write_u8((state.pitch_bend & 0x7f) as u8)?;
+                        midi.write_u8(((state.pitch_bend >> 7) & 0x7f) as u8)?;
+                    }
+                    CHANNEL_PRESSURE => {
+                        state.channel_pressure = state
+                            .channel_pressure
+                            .wrapping_add(streams[CHANNEL_PRESSURES].read_u8()?);
+                        midi.write_u8(state.channel_pressure & 0x7f)?;
+                    }
+                    KEY_PRESSURE => {
+                        state.key = state.key.wrapping_add(streams[KEYS].read_u8()?);
+                        state.key_pressure = state
+                            .key_pressure
+                            .wrapping_add(streams[KEY_PRESSURES].read_u8()?);
+                        midi.write_u8(state.key & 0x7f)?;
+                        midi.write_u8(state.key_pressure & 0x7f)?;
+                    }
+                    _ => midi.write_u8(streams[PROGRAMS].read_u8()?)?,
+                }
+            }
+
+            let len = (midi.len() - start - 4) as u32;
+            midi[start..start + 4].copy_from_slice(&len.to_be_bytes());
+        }
+
+        Ok(Track { midi })
+    }
+
+    /// Encode the track's midi file into the cache format. Events the cache
+    /// can't hold, such as text and system exclusive events, are dropped with
+    /// their time carried over to the next event.
+    pub fn encode(&self) -> Result<Vec<u8>, MusicError> {
+        let mut buf = self.midi.as_slice();
+        let mut header = read_chunk(&mut buf, "MThd")?;
+        let _format = header.read_u16()?;
+        let tracks = header.read_u16()?;
+        let division = header.read_u16()?;
+        if tracks > u8::MAX as u16 {
+            return Err(MusicError::TooManyTracks(tracks));
+        }
+
+        let mut opcodes = Vec::new();
+        let mut deltas = Vec::new();
+        let mut controllers = Vec::new();
+        let mut streams: [Vec<u8>; STREAMS] = Default::default();
+
+        let mut state = State::default();
+        for _ in 0..tracks {
+            let mut track = read_chunk(&mut buf, "MTrk")?;
+            let mut running_status = None;
+            let mut delta = 0;
+            loop {
+                if track.is_empty() {
+                    opcodes.push(END_OF_TRACK);
+                    deltas.push(delta);
+                    break;
+                }
+                delta += read_var_int(&mut track)?;
+
+                let status = match track.first() {
+                    Some(&status) if status & 0x80 != 0 => {
+                        track = &track[1..];
+                        status
+                    }
+                    _ => running_status.ok_or(MusicError::MissingStatus)?,
+                };
+
+                match status {
+                    META => {
+                        let kind = track.read_u8()?;
+                        let len = read_var_int(&mut track)? as usize;
+                        let data = take(&mut track, len)?;
+                        match kind {
+                            META_END_OF_TRACK => {
+                                opcodes.push(END_OF_TRACK);
+                                deltas.push(delta);
+                                break;
+                            }
+                            META_TEMPO if len == 3 => {
+                                opcodes.push(TEMPO);
+                                deltas.push(delta);
+                                streams[TEMPOS].extend_from_slice(data);
+                                delta = 0;
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    SYSEX | SYSEX_ESCAPE => {
+                        let len = read_var_int(&mut track)? as usize;
+                        take(&mut track, len)?;
+                        continue;
+                    }
+                    0x80..=0xef => running_status = Some(status),
+                    _ => return Err(MusicError::UnsupportedStatus(status)),
+                }
+
+                let event = STATUSES
+                    .iter()
+                    .position(|&s| s == status & 0xf0)
+                    .ok_or(MusicError::UnsupportedStatus(status))?
+                    as u8;
+                let channel = status & 0xf;
+                opcodes.push(((channel ^ state.channel) << 4) | event);
+                deltas.push(delta);
+                state.channel = channel;
+                delta = 0;
+
+                match event {
+                    NOTE_ON => {
+                        let key = track.read_u8()?;
+                        let velocity = track.read_u8()?;
+                        streams[KEYS].push(key.wrapping_sub(state.key));
+                        streams[NOTE_ON_VELOCITIES]
+                            .push(velocity.wrapping_sub(state.note_on_velocity));
+                        state.key = key;
+                        state.note_on_velocity = velocity;
+                    }
+                    NOTE_OFF => {
+                        let key = track.read_u8()?;
+                        let velocity = track.read_u8()?;
+                        streams[KEYS].push(key.wrapping_sub(state.key));
+                        streams[NOTE_OFF_VELOCITIES]
+                            .push(velocity.wrapping_sub(state.note_off_velocity));
+                        state.key = key;
+                        state.note_off_velocity = velocity;
+                    }
+                    CONTROL_CHANGE => {
+                        let controller = track.read_u8()? & 0x7f;
+                        let value = track.read_u8()?;
+                        controllers.push(controller.wrapping_sub(state.controller) & 0x7f);
+                        let last = &mut state.controller_values[controller as usize];
+                        streams[controller_stream(controller)].push(value.wrapping_sub(*last));
+                        *last = value;
+                        state.controller = controller;
+                    }
+                    PITCH_BEND => {
+                        let low = track.read_u8()? as u16;
+                        let high = track.read_u8()? as u16;
+                        let value = (low & 0x7f) | ((high & 0x7f) << 7);
+                        let delta = value.wrapping_sub(state.pitch_bend) & 0x3fff;
+                        streams[PITCH_BENDS_LOW].push((delta & 0x7f) as u8);
+                        streams[PITCH_BENDS_HIGH].push((delta >> 7) as u8);
+                        state.pitch_bend = value;
+                    }
+                    CHANNEL_PRESSURE => {
+                        let pressure = track.read_u8()?;
+                        streams[CHANNEL_PRESSURES]
+                            .push(pressure.wrapping_sub(state.channel_pressure));
+                        state.channel_pressure = pressure;
+                    }
+                    KEY_PRESSURE => {
+                        let key = track.read_u8()?;
+                        let pressure = track.read_u8()?;
+                        streams[KEYS].push(key.wrapping_sub(state.key));
+                        streams[KEY_PRESSURES].push(pressure.wrapping_sub(state.key_pressure));
+                        state.key = key;
+                        state.key_pressure = pressure;
+                    }
+                    _ => streams[PROGRAMS].push(track.read_u8()?),
+                }
+            }
+        }
+
+        let mut encoded = opcodes;
+        for delta in deltas {
+            write_var_int(&mut encoded, delta)?;
+        }
+        encoded.extend_from_slice(&controllers);
+        for stream in &streams {
+            encoded.extend_from_slice(stream);
+        }
+        encoded.write_u8(tracks as u8)?;
+        encoded.write_u16(division)?;
+        Ok(encoded)
+    }
+}
+
+/// Split the next `len` bytes off a buffer
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if buf.len() < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let (head, tail) = buf.split_at(len);
+    *buf = tail;
+    Ok(head)
+}
+
+/// Read the next midi chunk with the given id, skipping chunks of other types
+fn read_chunk<'a>(buf: &mut &'a [u8], id: &'static str) -> Result<&'a [u8], MusicError> {
+    loop {
+        if buf.is_empty() {
+            return Err(MusicError::MissingChunk(id));
+        }
+        let chunk_id = take(buf, 4)?;
+        let len = buf.read_u32()? as usize;
+        let chunk = take(buf, len)?;
+        if chunk_id == id.as_bytes() {
+            return Ok(chunk);
+        }
+    }
+}
+
+/// Read a midi variable length quantity, 7 bits per byte with the high bit
+/// set on every byte but the last
+fn read_var_int(buf: &mut &[u8]) -> io::Result<u32> {
+    let mut value = 0u32;
+    loop {
+        let byte = buf.read_u8()?;
+        value = (value << 7) | (byte & 0x7f) as u32;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+/// Write a midi variable length quantity
+fn write_var_int(buf: &mut Vec<u8>, value: u32) -> io::Result<()> {
+    let mut shift = 28;
+    while shift > 0 && value >> shift == 0 {
+        shift -= 7;
+    }
+    while shift > 0 {
+        buf.write_u8(((value >> shift) & 0x7f) as u8 | 0x80)?;
+        shift -= 7;
+    }
+    buf.write_u8((value & 0x7f) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    /// A tempo, program change and note, with the note lasting one beat
+    #[rustfmt::skip]
+    const TRACK: [u8; 21] = [
+        23, 6, 0, 1, 7, // opcodes
+        0, 0, 0, 0x60, 0, // delta times
+        60, 0, // keys
+        100, // note on velocities
+        64,  // note off velocities
+        5,   // programs
+        0x07, 0xa1, 0x20, // tempos
+        1, 0, 96, // tracks and division
+    ];
+
+    #[rustfmt::skip]
+    const MIDI: [u8; 44] = [
+        b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+        b'M', b'T', b'r', b'k', 0, 0, 0, 22,
+        0, 0xff, 0x51, 3, 0x07, 0xa1, 0x20,
+        0, 0xc0, 5,
+        0, 0x90, 60, 100,
+        0x60, 0x80, 60, 64,
+        0, 0xff, 0x2f, 0,
+    ];
+
+    fn midi(tracks: &[&[u8]]) -> Vec<u8> {
+        let mut midi = b"MThd".to_vec();
+        let format = if tracks.len() > 1 { 1 } else { 0 };
+        midi.extend_from_slice(&[0, 0, 0, 6, 0, format, 0, tracks.len() as u8, 1, 0xe0]);
+        for track in tracks {
+            midi.extend_from_slice(b"MTrk");
+            midi.extend_from_slice(&(track.len() as u32).to_be_bytes());
+            midi.extend_from_slice(track);
+        }
+        midi
+    }
+
+    #[test]
+    fn test_decode() {
+        let track = Track::decode(&TRACK).unwrap();
+        assert_eq!(MIDI.to_vec(), track.midi);
+        assert_eq!(TRACK.to_vec(), track.encode().unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        #[rustfmt::skip]
+        let midi = midi(&[
+            &[
+                0, 0xff, 0x51, 3, 0x0f, 0x42, 0x40,
+                0, 0xb3, 0, 1, 0, 32, 2, 0, 7, 100, 0, 74, 30, 0, 64, 127,
+                0, 0xc3, 48,
+                0, 0x93, 60, 90, 0x81, 0x00, 64, 80,
+                0x10, 0xa3, 64, 20,
+                0x10, 0xe3, 0x00, 0x40, 8, 0x7f, 0x7f, 8, 0, 0,
+                0, 0xd3, 50,
+                0x20, 0x83, 60, 0, 0, 64, 10,
+                0, 0xb3, 64, 0, 0, 99, 1, 0, 98, 2, 0, 101, 3, 0, 100, 4,
+                0, 0xff, 0x2f, 0,
+            ],
+            &[
+                0, 0xc9, 0,
+                0, 0x99, 36, 127, 0x83, 0x60, 0x89, 36, 0,
+                0, 0xb9, 10, 20, 0, 42, 5, 0, 33, 6, 0, 39, 7, 0, 1, 8,
+                0, 0xff, 0x2f, 0,
+            ],
+        ]);
+
+        let encoded = Track { midi: midi.clone() }.encode().unwrap();
+        let track = Track::decode(&encoded).unwrap();
+        assert_eq!(midi, track.midi);
+        assert_eq!(encoded, track.encode().unwrap());
+    }
+
+    #[test]
+    fn test_dropped_events() {
+        #[rustfmt::skip]
+        let track = Track {
+            midi: midi(&[&[
+                0, 0xff, 0x03, 4, b'S', b'o', b'n', b'g',
+                0x10, 0xf0, 2, 0x7e, 0xf7,
+                0x10, 0x90, 60, 100,
+                0x10, 0x3c, 0,
+            ]]),
+        };
+
+        let decoded = Track::decode(&track.encode().unwrap()).unwrap();
+        #[rustfmt::skip]
+        let expected = midi(&[&[
+            0x20, 0x90, 60, 100,
+            0x10, 60, 0,
+            0, 0xff, 0x2f, 0,
+        ]]);
+        assert_eq!(expected, decoded.midi);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            Track::decode(&[8, 1, 0, 96]),
+            Err(MusicError::UnknownOpcode(8))
+        ));
+        assert!(matches!(
+            Track {
+                midi: b"RIFF\0\0\0\0".to_vec()
+            }
+            .encode(),
+            Err(MusicError::MissingChunk("MThd"))
+        ));
+        assert!(matches!(
+            Track {
+                midi: midi(&[&[0, 60, 100]])
+            }
+            .encode(),
+            Err(MusicError::MissingStatus)
+        ));
+    }
+
+    #[test]
+    fn test_var_int() {
+        for value in [0, 0x7f, 0x80, 0x3fff, 0x4000, 0x0fff_ffff] {
+            let mut buf = Vec::new();
+            write_var_int(&mut buf, value).unwrap();
+            assert_eq!(value, read_var_int(&mut buf.as_slice()).unwrap());
+        }
+        let mut buf = Vec::new();
+        write_var_int(&mut buf, 0x4000).unwrap();
+        assert_eq!(vec![0x81, 0x80, 0], buf);
+    }
+
+    #[test]
+    fn test_cache_track() {
+        let mut cache = Cache::open("tests/data/cache/cache-music").unwrap();
+        assert_eq!(MIDI.to_vec(), cache.track(SONGS_ARCHIVE, 0).unwrap().midi);
+        assert_eq!(MIDI.to_vec(), cache.track(JINGLES_ARCHIVE, 3).unwrap().midi);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{
+    djb2::djb2_hash,
+    js5_index::{Js5Index, Js5IndexEntry, Js5IndexFile},
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+use thiserror::Error;
+
+/// The highest map square coordinate generated for the map name patterns.
+const MAX_MAP_SQUARE_COORD: u32 = 255;
+
+/// Names of singleton groups that are known to exist in most caches.
+const KNOWN_NAMES: &[&str] = &[
+    "huffman",
+    "p11_full",
+    "p12_full",
+    "b12_full",
+    "q8_full",
+    "verdana_11pt_regular",
+    "verdana_13pt_regular",
+    "verdana_15pt_regular",
+    "verdana_11pt_bold",
+    "verdana_13pt_bold",
+    "verdana_15pt_bold",
+    "logo",
+    "titlebox",
+    "titlebutton",
+    "runes",
+    "title.jpg",
+    "scrollbar",
+    "headicons_pk",
+    "headicons_prayer",
+    "headicons_hint",
+    "mapmarker",
+    "cross",
+    "mapdots",
+    "mapscene",
+    "mapfunction",
+    "mod_icons",
+];
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum NameDictionaryError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// How many of the groups and files in an index could be named.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NameCoverage {
+    /// The number of groups in the index
+    pub groups: usize,
+    /// The number of groups whose name hash was found in the dictionary
+    pub named_groups: usize,
+    /// The number of files in the index
+    pub files: usize,
+    /// The number of files whose name hash was found in the dictionary
+    pub named_files: usize,
+}
+
+/// Reverse lookup of djb2 name hashes to the strings they were created from.
+///
+/// If two candidate names share a hash, the first one added is kept.
+#[derive(Debug, Default)]
+pub struct NameDictionary {
+    names: HashMap<u32, String>,
+}
+
+impl NameDictionary {
+    /// Create an empty dictionary
+    pub fn new() -> NameDictionary {
+        Self::default()
+    }
+
+    /// Load candidate names from a text file containing one name per line
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the text file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<NameDictionary, NameDictionaryError> {
+        let mut dictionary = Self::new();
+        dictionary.read(BufReader::new(File::open(path)?))?;
+        Ok(dictionary)
+    }
+
+    /// Add the candidate names from a reader containing one name per line.
+    /// Surrounding whitespace is trimmed and blank lines are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader to read names from
+    pub fn read<R: BufRead>(&mut self, reader: R) -> Result<(), NameDictionaryError> {
+        for line in reader.lines() {
+            let line = line?;
+            let name = line.trim();
+            if !name.is_empty() {
+                self.insert(name);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the names generated from patterns known to be used by the client,
+    /// such as the `m{x}_{y}` and `l{x}_{y}` map squares and `huffman`
+    pub fn with_known_patterns(mut self) -> Self {
+        for x in 0..=MAX_MAP_SQUARE_COORD {
+            for y in 0..=MAX_MAP_SQUARE_COORD {
+                self.insert(format!("m{x}_{y}"));
+                self.insert(format!("l{x}_{y}"));
+                self.insert(format!("um{x}_{y}"));
+                self.insert(format!("ul{x}_{y}"));
+            }
+        }
+
+        for name in KNOWN_NAMES {
+            self.insert(*name);
+        }
+
+        self
+    }
+
+    /// Add a candidate name
+    pub fn insert<T: Into<String>>(&mut self, name: T) {
+        let name = name.into();
+        self.names.entry(djb2_hash(&name)).or_insert(name);
+    }
+
+    /// Get the number of names in the dictionary
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Check whether the dictionary is empty
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Get the name with the given djb2 hash
+    pub fn get(&self, name_hash: u32) -> Option<&str> {
+        self.names.get(&name_hash).map(String::as_str)
+    }
+
+    /// Get the name of a group, if it is named and the name is in the dictionary
+    pub fn group_name(&self, group: &Js5IndexEntry) -> Option<&str> {
+        self.get(group.name_hash as u32)
+    }
+
+    /// Get the name of a file, if it is named and the name is in the dictionary
+    pub fn file_name(&self, file: &Js5IndexFile) -> Option<&str> {
+        self.get(file.name_hash as u32)
+    }
+
+    /// Count how many groups and files in the index can be named by the dictionary
+    pub fn coverage(&self, index: &Js5Index) -> NameCoverage {
+        let mut coverage = NameCoverage::default();
+
+        for group in index.groups.values() {
+            coverage.groups += 1;
+            coverage.files += group.files.len();
+
+            if !index.has_names {
+                continue;
+            }
+
+            if self.group_name(group).is_some() {
+                coverage.named_groups += 1;
+            }
+            coverage.named_files += group
+                .files
+                .values()
+                .filter(|file| self.file_name(file).is_some())
+                .count();
+        }
+
+        coverage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use memmap2::Mmap;
+
+    #[test]
+    fn test_open() {
+        let dictionary = NameDictionary::open("tests/data/names/names.txt").unwrap();
+
+        assert_eq!(3, dictionary.len());
+        assert_eq!(Some("hello"), dictionary.get(djb2_hash("hello")));
+        assert_eq!(Some("OpenRS2"), dictionary.get(djb2_hash("OpenRS2")));
+        assert_eq!(None, dictionary.get(djb2_hash("")));
+    }
+
+    #[test]
+    fn test_known_patterns() {
+        let dictionary = NameDictionary::new().with_known_patterns();
+
+        assert_eq!(Some("m50_50"), dictionary.get(-1123920270_i32 as u32));
+        assert_eq!(Some("huffman"), dictionary.get(1258058669));
+        assert_eq!(Some("l12_34"), dictionary.get(djb2_hash("l12_34")));
+    }
+
+    #[test]
+    fn test_coverage() {
+        let data =
+            unsafe { Mmap::map(&File::open("tests/data/index/named.dat").unwrap()).unwrap() };
+        let index = Js5Index::read(data).unwrap();
+
+        let mut dictionary = NameDictionary::new();
+        dictionary.insert("hello");
+
+        assert_eq!(
+            NameCoverage {
+                groups: 1,
+                named_groups: 1,
+                files: 1,
+                named_files: 0,
+            },
+            dictionary.coverage(&index)
+        );
+        assert_eq!(Some("hello"), dictionary.group_name(&index.groups[&0]));
+        assert_eq!(None, dictionary.file_name(&index.groups[&0].files[&0]));
+    }
+}
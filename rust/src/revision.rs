@@ -0,0 +1,89 @@
+use crate::js5_index::{Js5Index, Js5Protocol};
+
+/// The build of the Old School client a cache is for, which selects the
+/// opcodes used to decode and encode its configs and maps.
+///
+/// Caches from before Old School use [`Revision::LEGACY`], which only selects
+/// the oldest layout of the opcodes that changed between Old School builds.
+/// Opcodes found only in those older caches aren't known, so their configs
+/// can fail to decode with an unknown opcode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Revision(pub u32);
+
+impl Revision {
+    /// Caches from before Old School, read with the oldest layout of each
+    /// changed opcode
+    pub const LEGACY: Revision = Revision(0);
+
+    /// Terrain opcodes are shorts, allowing more than 255 underlays and
+    /// overlays
+    pub const WIDE_TERRAIN: Revision = Revision(209);
+
+    /// Npc head icons (opcode 102) hold a sprite group and frame per icon
+    /// instead of a single frame
+    pub const NPC_HEAD_ICON_GROUPS: Revision = Revision(210);
+
+    /// Object ambient sounds (opcodes 78 and 79) hold whether they keep
+    /// playing once out of range
+    pub const AMBIENT_SOUND_RETAIN: Revision = Revision(220);
+
+    /// Sequence frame sounds (opcodes 13 and 15) hold each field separately
+    /// instead of packing them into 24 bits
+    pub const SEQUENCE_SOUND_FIELDS: Revision = Revision(220);
+
+    /// The latest caches, which is the revision used when none is given
+    pub const LATEST: Revision = Revision(u32::MAX);
+
+    /// Guess the revision of a cache from the index of its config archive.
+    ///
+    /// Indexes without group versions are only written by caches from before
+    /// Old School, so those are [`Revision::LEGACY`] and everything else is
+    /// [`Revision::LATEST`]. Use [`Cache::set_revision`](crate::Cache::set_revision)
+    /// when the exact revision is known.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_index` - The index of the config archive
+    pub fn detect(config_index: &Js5Index) -> Revision {
+        if config_index.protocol == Js5Protocol::Original as u8 {
+            Revision::LEGACY
+        } else {
+            Revision::LATEST
+        }
+    }
+}
+
+impl Default for Revision {
+    fn default() -> Self {
+        Revision::LATEST
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Revision::LEGACY,
+            Revision::detect(&Js5Index::new(Js5Protocol::Original))
+        );
+        assert_eq!(
+            Revision::LATEST,
+            Revision::detect(&Js5Index::new(Js5Protocol::Smart))
+        );
+        assert!(Revision::LEGACY < Revision::WIDE_TERRAIN);
+        assert!(Revision::AMBIENT_SOUND_RETAIN < Revision::LATEST);
+    }
+
+    #[test]
+    fn test_cache_revision() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+        assert_eq!(Revision::LATEST, cache.revision());
+
+        cache.set_revision(Revision(200));
+        assert_eq!(Revision(200), cache.revision());
+    }
+}
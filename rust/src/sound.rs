@@ -0,0 +1,911 @@
+use osrs_bytes::{ReadExt, WriteExt};
+use std::{
+    io::{self, Write},
+    sync::OnceLock,
+};
+use thiserror::Error;
+
+/// The archive holding the sound effects, one group each.
+pub const SOUND_EFFECTS_ARCHIVE: u8 = 4;
+/// The sample rate sound effects are rendered at.
+pub const SAMPLE_RATE: u32 = 22050;
+
+/// The number of instruments a sound effect can hold.
+const INSTRUMENTS: usize = 10;
+/// The number of oscillators an instrument can hold, of which only the first
+/// `SYNTHESIZED_OSCILLATORS` are played.
+const OSCILLATORS: usize = 10;
+const SYNTHESIZED_OSCILLATORS: usize = 5;
+/// The number of pole pairs a filter direction can hold.
+const FILTER_PAIRS: usize = 4;
+/// The number of samples filtered between updates of the filter coefficients.
+const FILTER_CHUNK: usize = 128;
+
+const FORM_SQUARE: u8 = 1;
+const FORM_SINE: u8 = 2;
+const FORM_SAW: u8 = 3;
+const FORM_NOISE: u8 = 4;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum SoundError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("a filter can't hold {0} pole pairs")]
+    TooManyPairs(usize),
+}
+
+/// The client's noise table, filled with -1 and 1 by a `java.util.Random`
+/// seeded with 0.
+fn noise() -> &'static [i32; 32768] {
+    static NOISE: OnceLock<[i32; 32768]> = OnceLock::new();
+    NOISE.get_or_init(|| {
+        let mut random = JavaRandom::new(0);
+        let mut table = [0; 32768];
+        for value in table.iter_mut() {
+            *value = (random.next_int() & 2) - 1;
+        }
+        table
+    })
+}
+
+/// The client's sine table, scaled to 14 bits with 32768 steps per turn.
+fn sine() -> &'static [i32; 32768] {
+    static SINE: OnceLock<[i32; 32768]> = OnceLock::new();
+    SINE.get_or_init(|| {
+        let mut table = [0; 32768];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = ((i as f64 / 5215.1903).sin() * 16384.0) as i32;
+        }
+        table
+    })
+}
+
+/// The linear congruential generator of `java.util.Random`.
+struct JavaRandom {
+    seed: u64,
+}
+
+impl JavaRandom {
+    const MULTIPLIER: u64 = 0x5_deec_e66d;
+    const MASK: u64 = (1 << 48) - 1;
+
+    fn new(seed: u64) -> JavaRandom {
+        JavaRandom {
+            seed: (seed ^ Self::MULTIPLIER) & Self::MASK,
+        }
+    }
+
+    fn next_int(&mut self) -> i32 {
+        self.seed = (self.seed.wrapping_mul(Self::MULTIPLIER).wrapping_add(0xb)) & Self::MASK;
+        (self.seed >> 16) as i32
+    }
+}
+
+/// A point an envelope moves to in a straight line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EnvelopeSegment {
+    /// The time the segment ends at, as a fraction of the instrument's
+    /// duration out of 65536
+    pub duration: u16,
+    /// The level at the end of the segment, out of 65536
+    pub level: u16,
+}
+
+/// A piecewise linear envelope over an instrument's duration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    /// The waveform of the oscillators or modulation the envelope drives
+    pub form: u8,
+    /// The value at level 0, such as a frequency in hertz
+    pub start: i32,
+    /// The value at the highest level
+    pub end: i32,
+    pub segments: Vec<EnvelopeSegment>,
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Envelope {
+            form: 0,
+            start: 0,
+            end: 0,
+            segments: vec![
+                EnvelopeSegment {
+                    duration: 0,
+                    level: 0,
+                },
+                EnvelopeSegment {
+                    duration: 65535,
+                    level: 65535,
+                },
+            ],
+        }
+    }
+}
+
+impl Envelope {
+    fn read(buf: &mut &[u8]) -> Result<Envelope, SoundError> {
+        let form = buf.read_u8()?;
+        let start = buf.read_i32()?;
+        let end = buf.read_i32()?;
+        let segments = Self::read_segments(buf)?;
+        Ok(Envelope {
+            form,
+            start,
+            end,
+            segments,
+        })
+    }
+
+    fn read_segments(buf: &mut &[u8]) -> Result<Vec<EnvelopeSegment>, SoundError> {
+        let len = buf.read_u8()?;
+        (0..len)
+            .map(|_| {
+                Ok(EnvelopeSegment {
+                    duration: buf.read_u16()?,
+                    level: buf.read_u16()?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The position of a synthesizer within an [`Envelope`].
+#[derive(Default)]
+struct EnvelopeState {
+    segment: usize,
+    ticks: i32,
+    position: i32,
+    amplitude: i32,
+    step: i32,
+}
+
+impl EnvelopeState {
+    /// Advance the envelope by a sample, returning its level before the step
+    ///
+    /// # Arguments
+    ///
+    /// * `envelope` - The envelope
+    /// * `period` - The number of samples the envelope spans
+    fn step(&mut self, envelope: &Envelope, period: usize) -> i32 {
+        let segments = &envelope.segments;
+        if segments.is_empty() {
+            return 0;
+        }
+
+        if self.position >= self.ticks {
+            self.amplitude = (segments[self.segment].level as i32) << 15;
+            self.segment = (self.segment + 1).min(segments.len() - 1);
+            let segment = segments[self.segment];
+            self.ticks = (segment.duration as f64 / 65536.0 * period as f64) as i32;
+            if self.ticks > self.position {
+                self.step = (((segment.level as i32) << 15) - self.amplitude)
+                    / (self.ticks - self.position);
+            }
+        }
+
+        self.amplitude = self.amplitude.wrapping_add(self.step);
+        self.position += 1;
+        self.amplitude.wrapping_sub(self.step) >> 15
+    }
+}
+
+/// A low frequency oscillation of an instrument's pitch or volume.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Modulation {
+    /// The rate of the oscillation, whose form is the waveform
+    pub rate: Envelope,
+    /// The depth of the oscillation
+    pub depth: Envelope,
+}
+
+/// Silences an instrument in alternating periods, such as for a rattle.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Gate {
+    /// The length of the silent periods, in 256ths of a sample
+    pub closed: Envelope,
+    /// The length of the audible periods, in 256ths of a sample
+    pub open: Envelope,
+}
+
+/// A tone played by an instrument relative to its pitch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Oscillator {
+    /// The volume, in percent
+    pub volume: u16,
+    /// The pitch relative to the instrument's, in tenths of a semitone
+    pub pitch: i16,
+    /// The delay before the oscillator starts, in milliseconds
+    pub delay: u16,
+}
+
+/// An infinite impulse response filter of up to four pole pairs in each
+/// direction, interpolated between two sets of poles by an envelope.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Filter {
+    /// The number of pole pairs of the feedforward and feedback directions
+    pub pairs: [usize; 2],
+    /// The attenuation of the feedforward direction at the start and end
+    pub unity: [u16; 2],
+    /// The frequency of each pole pair at the start and end, by direction
+    pub phases: [[[u16; FILTER_PAIRS]; 2]; 2],
+    /// The magnitude of each pole pair at the start and end, by direction
+    pub magnitudes: [[[u16; FILTER_PAIRS]; 2]; 2],
+}
+
+impl Filter {
+    /// Read a filter, returning whether the segments of its envelope follow
+    fn read(buf: &mut &[u8]) -> Result<(Filter, bool), SoundError> {
+        let mut filter = Filter::default();
+
+        let pairs = buf.read_u8()?;
+        filter.pairs = [(pairs >> 4) as usize, (pairs & 0xf) as usize];
+        if pairs == 0 {
+            return Ok((filter, false));
+        }
+        if let Some(&pairs) = filter.pairs.iter().find(|&&pairs| pairs > FILTER_PAIRS) {
+            return Err(SoundError::TooManyPairs(pairs));
+        }
+
+        filter.unity = [buf.read_u16()?, buf.read_u16()?];
+        let interpolated = buf.read_u8()?;
+        for direction in 0..2 {
+            for pair in 0..filter.pairs[direction] {
+                filter.phases[direction][0][pair] = buf.read_u16()?;
+                filter.magnitudes[direction][0][pair] = buf.read_u16()?;
+            }
+        }
+        for direction in 0..2 {
+            for pair in 0..filter.pairs[direction] {
+                if interpolated & (1 << (direction * 4) << pair) != 0 {
+                    filter.phases[direction][1][pair] = buf.read_u16()?;
+                    filter.magnitudes[direction][1][pair] = buf.read_u16()?;
+                } else {
+                    filter.phases[direction][1][pair] = filter.phases[direction][0][pair];
+                    filter.magnitudes[direction][1][pair] = filter.magnitudes[direction][0][pair];
+                }
+            }
+        }
+
+        let segments = interpolated != 0 || filter.unity[0] != filter.unity[1];
+        Ok((filter, segments))
+    }
+
+    fn magnitude(&self, direction: usize, pair: usize, f: f32) -> f32 {
+        let [start, end] = [0, 1].map(|i| self.magnitudes[direction][i][pair] as i32);
+        let decibels = (start as f32 + f * (end - start) as f32) * 0.0015258789;
+        1.0 - 10f64.powf((-decibels / 20.0) as f64) as f32
+    }
+
+    fn phase(&self, direction: usize, pair: usize, f: f32) -> f32 {
+        let [start, end] = [0, 1].map(|i| self.phases[direction][i][pair] as i32);
+        let octaves = (start as f32 + f * (end - start) as f32) * 1.2207031e-4;
+        let frequency = 32.703197 * 2f64.powf(octaves as f64) as f32;
+        frequency * std::f32::consts::PI / 11025.0
+    }
+
+    /// Compute the coefficients of a direction at a point of the envelope,
+    /// returning the number of coefficients
+    fn compute(&self, state: &mut FilterState, direction: usize, f: f32) -> usize {
+        if direction == 0 {
+            let unity =
+                self.unity[0] as f32 + (self.unity[1] as i32 - self.unity[0] as i32) as f32 * f;
+            let decibels = unity * 0.0030517578;
+            state.forward_multiplier = 0.1f64.powf((decibels / 20.0) as f64) as f32;
+            state.forward_multiplier_int = (state.forward_multiplier * 65536.0) as i32;
+        }
+
+        let pairs = self.pairs[direction];
+        if pairs == 0 {
+            return 0;
+        }
+
+        let coefficients = &mut state.coefficients[direction];
+        let magnitude = self.magnitude(direction, 0, f);
+        coefficients[0] = -2.0 * magnitude * (self.phase(direction, 0, f) as f64).cos() as f32;
+        coefficients[1] = magnitude * magnitude;
+        for pair in 1..pairs {
+            let magnitude = self.magnitude(direction, pair, f);
+            let a = -2.0 * magnitude * (self.phase(direction, pair, f) as f64).cos() as f32;
+            let b = magnitude * magnitude;
+            coefficients[pair * 2 + 1] = coefficients[pair * 2 - 1] * b;
+            coefficients[pair * 2] =
+                coefficients[pair * 2 - 1] * a + coefficients[pair * 2 - 2] * b;
+            for i in (2..pair * 2).rev() {
+                coefficients[i] += coefficients[i - 1] * a + coefficients[i - 2] * b;
+            }
+            coefficients[1] += coefficients[0] * a + b;
+            coefficients[0] += a;
+        }
+
+        if direction == 0 {
+            for coefficient in coefficients.iter_mut().take(pairs * 2) {
+                *coefficient *= state.forward_multiplier;
+            }
+        }
+        for (int, coefficient) in state.coefficients_int[direction]
+            .iter_mut()
+            .zip(&state.coefficients[direction])
+            .take(pairs * 2)
+        {
+            *int = (coefficient * 65536.0) as i32;
+        }
+        pairs * 2
+    }
+}
+
+/// The coefficients of a [`Filter`] at the current point of its envelope.
+#[derive(Default)]
+struct FilterState {
+    coefficients: [[f32; FILTER_PAIRS * 2]; 2],
+    coefficients_int: [[i32; FILTER_PAIRS * 2]; 2],
+    forward_multiplier: f32,
+    forward_multiplier_int: i32,
+}
+
+impl FilterState {
+    /// Filter the sample at an index in place, reading the unfiltered samples
+    /// after it and the filtered samples before it
+    fn apply(&self, samples: &[i32], i: usize, forward: usize, feedback: usize) -> i32 {
+        let len = samples.len();
+        let mut value = if i + forward < len {
+            mul_16(samples[i + forward], self.forward_multiplier_int)
+        } else {
+            0
+        };
+        for tap in (i + forward).saturating_sub(len)..forward {
+            value = value.wrapping_add(mul_16(
+                samples[i + forward - 1 - tap],
+                self.coefficients_int[0][tap],
+            ));
+        }
+        for tap in 0..feedback.min(i) {
+            value = value.wrapping_sub(mul_16(samples[i - 1 - tap], self.coefficients_int[1][tap]));
+        }
+        value
+    }
+}
+
+/// Multiply a sample by a 16.16 fixed point coefficient.
+fn mul_16(sample: i32, coefficient: i32) -> i32 {
+    ((sample as i64 * coefficient as i64) >> 16) as i32
+}
+
+/// A synthesized instrument of a sound effect.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Instrument {
+    /// The frequency of the oscillators, whose form is their waveform
+    pub pitch: Envelope,
+    pub volume: Envelope,
+    pub vibrato: Option<Modulation>,
+    pub tremolo: Option<Modulation>,
+    pub gate: Option<Gate>,
+    pub oscillators: Vec<Oscillator>,
+    /// The delay of the echo, in milliseconds
+    pub delay_time: u16,
+    /// The volume of the echo, in percent
+    pub delay_decay: u16,
+    /// The duration, in milliseconds
+    pub duration: u16,
+    /// The time the instrument starts within the sound effect, in milliseconds
+    pub offset: u16,
+    pub filter: Filter,
+    /// The envelope interpolating between the filter's start and end poles
+    pub filter_envelope: Envelope,
+}
+
+impl Instrument {
+    fn read(buf: &mut &[u8]) -> Result<Instrument, SoundError> {
+        let pitch = Envelope::read(buf)?;
+        let volume = Envelope::read(buf)?;
+        let vibrato = Self::read_optional(buf)?.map(|[rate, depth]| Modulation { rate, depth });
+        let tremolo = Self::read_optional(buf)?.map(|[rate, depth]| Modulation { rate, depth });
+        let gate = Self::read_optional(buf)?.map(|[closed, open]| Gate { closed, open });
+
+        let mut oscillators = Vec::new();
+        while oscillators.len() < OSCILLATORS {
+            let volume = buf.read_u16_smart()?;
+            if volume == 0 {
+                break;
+            }
+            oscillators.push(Oscillator {
+                volume,
+                pitch: buf.read_i16_smart()?,
+                delay: buf.read_u16_smart()?,
+            });
+        }
+
+        let delay_time = buf.read_u16_smart()?;
+        let delay_decay = buf.read_u16_smart()?;
+        let duration = buf.read_u16()?;
+        let offset = buf.read_u16()?;
+
+        let (filter, segments) = Filter::read(buf)?;
+        let mut filter_envelope = Envelope::default();
+        if segments {
+            filter_envelope.segments = Envelope::read_segments(buf)?;
+        }
+
+        Ok(Instrument {
+            pitch,
+            volume,
+            vibrato,
+            tremolo,
+            gate,
+            oscillators,
+            delay_time,
+            delay_decay,
+            duration,
+            offset,
+            filter,
+            filter_envelope,
+        })
+    }
+
+    /// Read a pair of envelopes that is absent if it starts with a 0 byte
+    fn read_optional(buf: &mut &[u8]) -> Result<Option<[Envelope; 2]>, SoundError> {
+        if buf.first() == Some(&0) {
+            *buf = &buf[1..];
+            return Ok(None);
+        }
+        Ok(Some([Envelope::read(buf)?, Envelope::read(buf)?]))
+    }
+
+    /// Evaluate a waveform at a phase, where 32768 is a full cycle
+    fn wave(phase: i32, amplitude: i32, form: u8) -> i32 {
+        match form {
+            FORM_SQUARE => {
+                if phase & 0x7fff < 0x4000 {
+                    amplitude
+                } else {
+                    amplitude.wrapping_neg()
+                }
+            }
+            FORM_SINE => sine()[(phase & 0x7fff) as usize].wrapping_mul(amplitude) >> 14,
+            FORM_SAW => (amplitude.wrapping_mul(phase & 0x7fff) >> 14).wrapping_sub(amplitude),
+            FORM_NOISE => amplitude.wrapping_mul(noise()[((phase / 2607) & 0x7fff) as usize]),
+            _ => 0,
+        }
+    }
+
+    /// Synthesize the instrument's samples, clamped to 16 bits
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The number of samples to synthesize over the duration
+    fn synthesize(&self, len: usize) -> Vec<i32> {
+        let mut samples = vec![0i32; len];
+        if self.duration < 10 {
+            return samples;
+        }
+        let samples_per_ms = len as f64 / self.duration as f64;
+
+        let mut pitch = EnvelopeState::default();
+        let mut volume = EnvelopeState::default();
+
+        let steps = |envelope: &Envelope| {
+            (
+                (envelope.end.wrapping_sub(envelope.start) as f64 * 32.768 / samples_per_ms) as i32,
+                (envelope.start as f64 * 32.768 / samples_per_ms) as i32,
+            )
+        };
+        let (vibrato_step, vibrato_base_step) =
+            self.vibrato.as_ref().map_or((0, 0), |v| steps(&v.rate));
+        let (tremolo_step, tremolo_base_step) =
+            self.tremolo.as_ref().map_or((0, 0), |t| steps(&t.rate));
+        let mut vibrato_state = [EnvelopeState::default(), EnvelopeState::default()];
+        let mut tremolo_state = [EnvelopeState::default(), EnvelopeState::default()];
+        let mut vibrato_phase = 0i32;
+        let mut tremolo_phase = 0i32;
+
+        let oscillators: Vec<_> = self
+            .oscillators
+            .iter()
+            .take(SYNTHESIZED_OSCILLATORS)
+            .map(|oscillator| {
+                let pitch_step = (self.pitch.end.wrapping_sub(self.pitch.start) as f64
+                    * 32.768
+                    * 1.0057929410678534f64.powf(oscillator.pitch as f64)
+                    / samples_per_ms) as i32;
+                (
+                    (oscillator.delay as f64 * samples_per_ms) as usize,
+                    ((oscillator.volume as i32) << 14) / 100,
+                    pitch_step,
+                    (self.pitch.start as f64 * 32.768 / samples_per_ms) as i32,
+                )
+            })
+            .collect();
+        let mut phases = vec![0i32; oscillators.len()];
+
+        for i in 0..len {
+            let mut frequency = pitch.step(&self.pitch, len);
+            let mut amplitude = volume.step(&self.volume, len);
+
+            if let Some(vibrato) = &self.vibrato {
+                let rate = vibrato_state[0].step(&vibrato.rate, len);
+                let depth = vibrato_state[1].step(&vibrato.depth, len);
+                frequency = frequency
+                    .wrapping_add(Self::wave(vibrato_phase, depth, vibrato.rate.form) >> 1);
+                vibrato_phase = vibrato_phase
+                    .wrapping_add(vibrato_base_step)
+                    .wrapping_add(rate.wrapping_mul(vibrato_step) >> 16);
+            }
+
+            if let Some(tremolo) = &self.tremolo {
+                let rate = tremolo_state[0].step(&tremolo.rate, len);
+                let depth = tremolo_state[1].step(&tremolo.depth, len);
+                amplitude = amplitude.wrapping_mul(
+                    (Self::wave(tremolo_phase, depth, tremolo.rate.form) >> 1).wrapping_add(32768),
+                ) >> 15;
+                tremolo_phase = tremolo_phase
+                    .wrapping_add(tremolo_base_step)
+                    .wrapping_add(rate.wrapping_mul(tremolo_step) >> 16);
+            }
+
+            for (phase, &(delay, volume_step, pitch_step, pitch_base_step)) in
+                phases.iter_mut().zip(&oscillators)
+            {
+                let Some(sample) = samples.get_mut(delay + i) else {
+                    continue;
+                };
+                *sample = sample.wrapping_add(Self::wave(
+                    *phase,
+                    amplitude.wrapping_mul(volume_step) >> 15,
+                    self.pitch.form,
+                ));
+                *phase = phase
+                    .wrapping_add(frequency.wrapping_mul(pitch_step) >> 16)
+                    .wrapping_add(pitch_base_step);
+            }
+        }
+
+        if let Some(gate) = &self.gate {
+            let mut closed = EnvelopeState::default();
+            let mut open = EnvelopeState::default();
+            let range = gate.closed.end.wrapping_sub(gate.closed.start);
+            let mut counter = 0;
+            let mut silent = true;
+            for sample in samples.iter_mut() {
+                let closed = closed.step(&gate.closed, len);
+                let open = open.step(&gate.open, len);
+                let level = if silent { closed } else { open };
+                let threshold = (level.wrapping_mul(range) >> 8).wrapping_add(gate.closed.start);
+                counter += 256;
+                if counter >= threshold {
+                    counter = 0;
+                    silent = !silent;
+                }
+                if silent {
+                    *sample = 0;
+                }
+            }
+        }
+
+        if self.delay_time > 0 && self.delay_decay > 0 {
+            let delay = (self.delay_time as f64 * samples_per_ms) as usize;
+            for i in delay..len {
+                let echo = samples[i - delay].wrapping_mul(self.delay_decay as i32) / 100;
+                samples[i] = samples[i].wrapping_add(echo);
+            }
+        }
+
+        if self.filter.pairs != [0, 0] {
+            self.apply_filter(&mut samples);
+        }
+
+        for sample in samples.iter_mut() {
+            *sample = (*sample).clamp(i16::MIN as i32, i16::MAX as i32);
+        }
+        samples
+    }
+
+    fn apply_filter(&self, samples: &mut [i32]) {
+        let len = samples.len();
+        let mut state = FilterState::default();
+        let mut envelope = EnvelopeState::default();
+
+        let mut level = envelope.step(&self.filter_envelope, len + 1);
+        let mut forward = self.filter.compute(&mut state, 0, level as f32 / 65536.0);
+        let mut feedback = self.filter.compute(&mut state, 1, level as f32 / 65536.0);
+        if len < forward + feedback {
+            return;
+        }
+
+        let mut i = 0;
+        let mut end = feedback.min(len - forward);
+        while i < end {
+            samples[i] = state.apply(samples, i, forward, feedback);
+            level = envelope.step(&self.filter_envelope, len + 1);
+            i += 1;
+        }
+
+        end = FILTER_CHUNK;
+        loop {
+            end = end.min(len.saturating_sub(forward));
+            while i < end {
+                samples[i] = state.apply(samples, i, forward, feedback);
+                level = envelope.step(&self.filter_envelope, len + 1);
+                i += 1;
+            }
+
+            if i >= len.saturating_sub(forward) {
+                while i < len {
+                    samples[i] = state.apply(samples, i, forward, feedback);
+                    envelope.step(&self.filter_envelope, len + 1);
+                    i += 1;
+                }
+                break;
+            }
+
+            forward = self.filter.compute(&mut state, 0, level as f32 / 65536.0);
+            feedback = self.filter.compute(&mut state, 1, level as f32 / 65536.0);
+            end += FILTER_CHUNK;
+        }
+    }
+}
+
+/// A sound effect from the sound effects archive, synthesized from up to ten
+/// instruments.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SoundEffect {
+    pub instruments: [Option<Instrument>; INSTRUMENTS],
+    /// The start of the looped section, in milliseconds
+    pub loop_start: u16,
+    /// The end of the looped section, in milliseconds
+    pub loop_end: u16,
+}
+
+impl SoundEffect {
+    /// Decode a sound effect
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The encoded sound effect
+    pub fn decode(mut buf: &[u8]) -> Result<SoundEffect, SoundError> {
+        let mut sound = SoundEffect::default();
+        for instrument in sound.instruments.iter_mut() {
+            if buf.first() == Some(&0) {
+                buf = &buf[1..];
+            } else {
+                *instrument = Some(Instrument::read(&mut buf)?);
+            }
+        }
+        sound.loop_start = buf.read_u16()?;
+        sound.loop_end = buf.read_u16()?;
+        Ok(sound)
+    }
+
+    /// Get the duration of the sound effect, in milliseconds
+    pub fn duration(&self) -> u32 {
+        self.instruments
+            .iter()
+            .flatten()
+            .map(|instrument| instrument.duration as u32 + instrument.offset as u32)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Render the sound effect as mono 16-bit PCM at [`SAMPLE_RATE`].
+    /// Rendering is deterministic, following the client's integer arithmetic.
+    /// The instruments are summed before the mix is clamped, so their order
+    /// doesn't change the result.
+    pub fn render(&self) -> Vec<i16> {
+        let mut mixed = vec![0i32; (self.duration() * SAMPLE_RATE / 1000) as usize];
+        for instrument in self.instruments.iter().flatten() {
+            let len = (instrument.duration as u32 * SAMPLE_RATE / 1000) as usize;
+            let offset = (instrument.offset as u32 * SAMPLE_RATE / 1000) as usize;
+            let samples = instrument.synthesize(len);
+            for (mixed, sample) in mixed[offset..].iter_mut().zip(samples) {
+                *mixed += sample;
+            }
+        }
+        mixed
+            .into_iter()
+            .map(|sample| sample.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+            .collect()
+    }
+
+    /// Render the sound effect as a mono 16-bit WAV file
+    pub fn to_wav(&self) -> Result<Vec<u8>, SoundError> {
+        let samples = self.render();
+        let data_len = samples.len() as u32 * 2;
+
+        let mut buf = Vec::with_capacity(44 + data_len as usize);
+        buf.write_all(b"RIFF")?;
+        buf.write_u32_le(36 + data_len)?;
+        buf.write_all(b"WAVE")?;
+        buf.write_all(b"fmt ")?;
+        buf.write_u32_le(16)?;
+        buf.write_u16_le(1)?; // PCM
+        buf.write_u16_le(1)?; // Mono
+        buf.write_u32_le(SAMPLE_RATE)?;
+        buf.write_u32_le(SAMPLE_RATE * 2)?;
+        buf.write_u16_le(2)?;
+        buf.write_u16_le(16)?;
+        buf.write_all(b"data")?;
+        buf.write_u32_le(data_len)?;
+        for sample in samples {
+            buf.write_i16_le(sample)?;
+        }
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+    use std::fs;
+
+    #[test]
+    fn test_java_random() {
+        let mut random = JavaRandom::new(0);
+        assert_eq!(-1155484576, random.next_int());
+        assert_eq!(-723955400, random.next_int());
+        assert!(noise().iter().all(|&n| n == -1 || n == 1));
+    }
+
+    #[test]
+    fn test_envelope() {
+        let envelope = Envelope::default();
+        let mut state = EnvelopeState::default();
+        let levels: Vec<_> = (0..5).map(|_| state.step(&envelope, 4)).collect();
+        assert_eq!(vec![0, 21845, 43690, 65535, 65535], levels);
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut cache = Cache::open("tests/data/cache/cache-sounds").unwrap();
+        let sound = cache.sound_effect(0).unwrap();
+        assert_eq!(400, sound.duration());
+        assert_eq!((0, 300), (sound.loop_start, sound.loop_end));
+
+        let tone = sound.instruments[0].as_ref().unwrap();
+        assert_eq!((FORM_SINE, 440, 880), {
+            let p = &tone.pitch;
+            (p.form, p.start, p.end)
+        });
+        assert_eq!(4, tone.volume.segments.len());
+        assert_eq!(
+            vec![
+                Oscillator {
+                    volume: 60,
+                    pitch: 0,
+                    delay: 0
+                },
+                Oscillator {
+                    volume: 30,
+                    pitch: -120,
+                    delay: 10
+                }
+            ],
+            tone.oscillators
+        );
+        assert!(tone.vibrato.is_some() && tone.tremolo.is_none() && tone.gate.is_none());
+        assert_eq!([2, 1], tone.filter.pairs);
+        assert_eq!(3, tone.filter_envelope.segments.len());
+
+        let noise = sound.instruments[1].as_ref().unwrap();
+        assert_eq!(FORM_NOISE, noise.pitch.form);
+        assert!(noise.gate.is_some() && noise.tremolo.is_some());
+        assert_eq!((200, 200), (noise.duration, noise.offset));
+        assert_eq!([0, 0], noise.filter.pairs);
+        assert_eq!(Envelope::default(), noise.filter_envelope);
+
+        assert!(sound.instruments[2..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_render_snapshot() {
+        // The WAVs were rendered by this synthesizer rather than the client, so
+        // they only catch changes to its output
+        let mut cache = Cache::open("tests/data/cache/cache-sounds").unwrap();
+        for id in 0..2 {
+            let sound = cache.sound_effect(id).unwrap();
+            let samples = sound.render();
+            assert_eq!(
+                (sound.duration() * SAMPLE_RATE / 1000) as usize,
+                samples.len()
+            );
+            assert!(samples.iter().any(|&sample| sample != 0));
+
+            let wav = fs::read(format!("tests/data/sound/sound-{id}.wav")).unwrap();
+            assert_eq!(wav, sound.to_wav().unwrap(), "sound {id}");
+        }
+    }
+
+    #[test]
+    fn test_render_mix() {
+        let tone = |form, volume| Instrument {
+            pitch: Envelope {
+                form,
+                start: 1000,
+                end: 1000,
+                ..Default::default()
+            },
+            volume: Envelope {
+                segments: vec![
+                    EnvelopeSegment {
+                        duration: 0,
+                        level: 65535,
+                    },
+                    EnvelopeSegment {
+                        duration: 65535,
+                        level: 65535,
+                    },
+                ],
+                ..Default::default()
+            },
+            oscillators: vec![Oscillator {
+                volume,
+                ..Default::default()
+            }],
+            duration: 10,
+            ..Default::default()
+        };
+        let mut sound = SoundEffect::default();
+        sound.instruments[0] = Some(tone(FORM_SQUARE, 60));
+        sound.instruments[1] = Some(tone(FORM_SQUARE, 60));
+        sound.instruments[2] = Some(tone(FORM_SAW, 100));
+
+        // Samples of the client's synthesizer for two squares and a saw at
+        // 1kHz, which overshoot together before the saw pulls them back
+        let samples = sound.render();
+        assert_eq!(220, samples.len());
+        assert_eq!(
+            [6551, 9528, 12506, 15484, 18462, 21440, 24418, 27396, 30374, 32767],
+            samples[..10]
+        );
+        assert_eq!((-32768, -6571), (samples[12], samples[22]));
+
+        sound.instruments.swap(0, 2);
+        assert_eq!(samples, sound.render());
+    }
+
+    #[test]
+    fn test_short_instrument() {
+        let instrument = Instrument {
+            duration: 9,
+            oscillators: vec![Oscillator {
+                volume: 100,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(instrument.synthesize(198).iter().all(|&sample| sample == 0));
+        assert!(SoundEffect::default().render().is_empty());
+    }
+
+    #[test]
+    fn test_extreme_envelopes() {
+        let extreme = Envelope {
+            start: i32::MIN,
+            end: i32::MAX,
+            ..Default::default()
+        };
+        let instrument = Instrument {
+            duration: 10,
+            pitch: extreme.clone(),
+            oscillators: vec![Oscillator {
+                volume: 100,
+                ..Default::default()
+            }],
+            vibrato: Some(Modulation {
+                rate: extreme,
+                depth: Envelope::default(),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(220, instrument.synthesize(220).len());
+    }
+
+    #[test]
+    fn test_too_many_pairs() {
+        let mut buf: &[u8] = &[0x50, 0, 0, 0, 0, 0];
+        assert!(matches!(
+            Filter::read(&mut buf),
+            Err(SoundError::TooManyPairs(5))
+        ));
+    }
+}
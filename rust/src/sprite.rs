@@ -0,0 +1,396 @@
+use osrs_bytes::{ReadExt, WriteExt};
+use std::io;
+use thiserror::Error;
+
+/// The archive holding the sprites.
+pub const SPRITES_ARCHIVE: u8 = 8;
+
+/// Pixels are stored column by column rather than row by row.
+const FLAG_VERTICAL: u8 = 0x1;
+/// An alpha value follows the palette indices of every pixel.
+const FLAG_ALPHA: u8 = 0x2;
+
+/// The most colours a palette can hold, as index 0 is reserved for transparency.
+const MAX_PALETTE_SIZE: usize = 255;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum SpriteError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("png decoding error: {0}")]
+    PngDecoding(#[from] png::DecodingError),
+    #[error("png encoding error: {0}")]
+    PngEncoding(#[from] png::EncodingError),
+    #[error("too many colours for a palette: {0}")]
+    TooManyColours(usize),
+    #[error("frame pixels don't match its size: {0}")]
+    InvalidFrameSize(usize),
+    #[error("unsupported png colour type: {0:?}")]
+    UnsupportedColorType(png::ColorType),
+}
+
+/// A single image of a sprite sheet.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpriteFrame {
+    /// The x offset of the frame within the sheet
+    pub offset_x: u16,
+    /// The y offset of the frame within the sheet
+    pub offset_y: u16,
+    pub width: u16,
+    pub height: u16,
+    /// The ARGB pixels row by row, fully transparent pixels being 0
+    pub pixels: Vec<u32>,
+}
+
+impl SpriteFrame {
+    /// Create a frame from ARGB pixels
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the frame
+    /// * `height` - The height of the frame
+    /// * `pixels` - The ARGB pixels row by row
+    pub fn new(width: u16, height: u16, pixels: Vec<u32>) -> Result<SpriteFrame, SpriteError> {
+        if pixels.len() != width as usize * height as usize {
+            return Err(SpriteError::InvalidFrameSize(pixels.len()));
+        }
+
+        Ok(SpriteFrame {
+            offset_x: 0,
+            offset_y: 0,
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Get the ARGB pixel at a position
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x coordinate within the frame
+    /// * `y` - The y coordinate within the frame
+    pub fn pixel(&self, x: u16, y: u16) -> u32 {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Encode the frame as an RGBA png
+    pub fn to_png(&self) -> Result<Vec<u8>, SpriteError> {
+        let mut rgba = Vec::with_capacity(self.pixels.len() * 4);
+        for pixel in &self.pixels {
+            let [a, r, g, b] = pixel.to_be_bytes();
+            rgba.extend_from_slice(&[r, g, b, a]);
+        }
+
+        let mut buf = Vec::new();
+        let mut encoder = png::Encoder::new(&mut buf, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&rgba)?;
+        writer.finish()?;
+
+        Ok(buf)
+    }
+
+    /// Decode a frame from a png. Pixels with an alpha of 0 become fully
+    /// transparent.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The png to decode
+    pub fn from_png(buf: &[u8]) -> Result<SpriteFrame, SpriteError> {
+        let mut decoder = png::Decoder::new(buf);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data)?;
+        let data = &data[..info.buffer_size()];
+
+        let to_argb = |r: u8, g: u8, b: u8, a: u8| match a {
+            0 => 0,
+            _ => u32::from_be_bytes([a, r, g, b]),
+        };
+        let pixels: Vec<u32> = match info.color_type {
+            png::ColorType::Rgba => data
+                .chunks_exact(4)
+                .map(|p| to_argb(p[0], p[1], p[2], p[3]))
+                .collect(),
+            png::ColorType::Rgb => data
+                .chunks_exact(3)
+                .map(|p| to_argb(p[0], p[1], p[2], 0xff))
+                .collect(),
+            png::ColorType::GrayscaleAlpha => data
+                .chunks_exact(2)
+                .map(|p| to_argb(p[0], p[0], p[0], p[1]))
+                .collect(),
+            png::ColorType::Grayscale => data.iter().map(|p| to_argb(*p, *p, *p, 0xff)).collect(),
+            color_type => return Err(SpriteError::UnsupportedColorType(color_type)),
+        };
+
+        SpriteFrame::new(info.width as u16, info.height as u16, pixels)
+    }
+}
+
+/// A group of the sprites archive, holding one or more frames that share a
+/// palette.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpriteSheet {
+    pub width: u16,
+    pub height: u16,
+    pub frames: Vec<SpriteFrame>,
+}
+
+impl SpriteSheet {
+    /// Decode a sprite sheet
+    ///
+    /// The frame headers and palette are stored at the end of the group,
+    /// preceded by the palette indices and optional alpha of every frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The contents of file 0 of a sprites group
+    pub fn decode(buf: &[u8]) -> Result<SpriteSheet, SpriteError> {
+        let trailer = |len: usize| {
+            buf.len()
+                .checked_sub(len)
+                .map(|start| &buf[start..])
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "sprite trailer out of bounds")
+                })
+        };
+
+        let len = trailer(2)?.read_u16()? as usize;
+
+        let mut header = trailer(7 + len * 8)?;
+        let width = header.read_u16()?;
+        let height = header.read_u16()?;
+        let palette_len = header.read_u8()? as usize;
+        let mut frames = vec![SpriteFrame::default(); len];
+        for frame in frames.iter_mut() {
+            frame.offset_x = header.read_u16()?;
+        }
+        for frame in frames.iter_mut() {
+            frame.offset_y = header.read_u16()?;
+        }
+        for frame in frames.iter_mut() {
+            frame.width = header.read_u16()?;
+        }
+        for frame in frames.iter_mut() {
+            frame.height = header.read_u16()?;
+        }
+
+        let mut palette_buf = trailer(7 + len * 8 + palette_len * 3)?;
+        let mut palette = vec![0; palette_len + 1];
+        for colour in palette.iter_mut().skip(1) {
+            // Black is reserved for transparency so is stored as 1
+            *colour = match palette_buf.read_u24()? {
+                0 => 1,
+                colour => colour,
+            };
+        }
+
+        let mut buf = buf;
+        for frame in frames.iter_mut() {
+            let (width, height) = (frame.width as usize, frame.height as usize);
+            let flags = buf.read_u8()?;
+
+            let read_plane = |buf: &mut &[u8]| -> Result<Vec<u8>, SpriteError> {
+                let mut plane = vec![0; width * height];
+                if flags & FLAG_VERTICAL == 0 {
+                    for value in plane.iter_mut() {
+                        *value = buf.read_u8()?;
+                    }
+                } else {
+                    for x in 0..width {
+                        for y in 0..height {
+                            plane[y * width + x] = buf.read_u8()?;
+                        }
+                    }
+                }
+                Ok(plane)
+            };
+
+            let indices = read_plane(&mut buf)?;
+            let alphas = if flags & FLAG_ALPHA != 0 {
+                read_plane(&mut buf)?
+            } else {
+                indices
+                    .iter()
+                    .map(|index| if *index == 0 { 0 } else { 0xff })
+                    .collect()
+            };
+
+            frame.pixels = indices
+                .iter()
+                .zip(&alphas)
+                .map(|(index, alpha)| match alpha {
+                    0 => 0,
+                    _ => ((*alpha as u32) << 24) | palette[*index as usize],
+                })
+                .collect();
+        }
+
+        Ok(SpriteSheet {
+            width,
+            height,
+            frames,
+        })
+    }
+
+    /// Encode the sprite sheet, building a palette from the colours of every
+    /// frame. Frames are always stored row by row, with alpha only if a pixel
+    /// is partially transparent.
+    pub fn encode(&self) -> Result<Vec<u8>, SpriteError> {
+        let mut palette: Vec<u32> = Vec::new();
+        let mut buf = Vec::new();
+
+        for frame in &self.frames {
+            if frame.pixels.len() != frame.width as usize * frame.height as usize {
+                return Err(SpriteError::InvalidFrameSize(frame.pixels.len()));
+            }
+
+            let mut indices = Vec::with_capacity(frame.pixels.len());
+            for pixel in &frame.pixels {
+                if pixel >> 24 == 0 {
+                    indices.push(0);
+                    continue;
+                }
+
+                let colour = match pixel & 0xffffff {
+                    0 => 1,
+                    colour => colour,
+                };
+                let index = match palette.iter().position(|c| *c == colour) {
+                    Some(index) => index,
+                    None if palette.len() == MAX_PALETTE_SIZE => {
+                        return Err(SpriteError::TooManyColours(palette.len() + 1));
+                    }
+                    None => {
+                        palette.push(colour);
+                        palette.len() - 1
+                    }
+                };
+                indices.push(index as u8 + 1);
+            }
+
+            let has_alpha = frame
+                .pixels
+                .iter()
+                .any(|pixel| !matches!(pixel >> 24, 0 | 0xff));
+            buf.write_u8(if has_alpha { FLAG_ALPHA } else { 0 })?;
+            buf.extend_from_slice(&indices);
+            if has_alpha {
+                buf.extend(frame.pixels.iter().map(|pixel| (pixel >> 24) as u8));
+            }
+        }
+
+        for colour in &palette {
+            buf.write_u8((colour >> 16) as u8)?;
+            buf.write_u16(*colour as u16)?;
+        }
+
+        buf.write_u16(self.width)?;
+        buf.write_u16(self.height)?;
+        buf.write_u8(palette.len() as u8)?;
+        for frame in &self.frames {
+            buf.write_u16(frame.offset_x)?;
+        }
+        for frame in &self.frames {
+            buf.write_u16(frame.offset_y)?;
+        }
+        for frame in &self.frames {
+            buf.write_u16(frame.width)?;
+        }
+        for frame in &self.frames {
+            buf.write_u16(frame.height)?;
+        }
+        buf.write_u16(self.frames.len() as u16)?;
+
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    const RED: u32 = 0xffff0000;
+    const GREEN: u32 = 0xff00ff00;
+    const BLACK: u32 = 0xff000001;
+
+    #[test]
+    fn test_decode() {
+        let mut cache = Cache::open("tests/data/cache/cache-sprites").unwrap();
+        let sheet = cache.sprite(0).unwrap();
+
+        assert_eq!((4, 3), (sheet.width, sheet.height));
+        assert_eq!(2, sheet.frames.len());
+
+        let frame = &sheet.frames[0];
+        assert_eq!((0, 0, 2, 2), {
+            (frame.offset_x, frame.offset_y, frame.width, frame.height)
+        });
+        assert_eq!(vec![RED, 0, GREEN, BLACK], frame.pixels);
+
+        // Stored column by column
+        let frame = &sheet.frames[1];
+        assert_eq!((1, 1, 3, 2), {
+            (frame.offset_x, frame.offset_y, frame.width, frame.height)
+        });
+        assert_eq!(vec![RED, GREEN, RED, 0, BLACK, 0], frame.pixels);
+    }
+
+    #[test]
+    fn test_decode_alpha() {
+        let mut cache = Cache::open("tests/data/cache/cache-sprites").unwrap();
+        let sheet = cache.sprite(1).unwrap();
+
+        assert_eq!(
+            vec![0x80ff0000, 0, 0xffff0000, 0x0100ff00],
+            sheet.frames[0].pixels
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut cache = Cache::open("tests/data/cache/cache-sprites").unwrap();
+        for group in [0, 1] {
+            let sheet = cache.sprite(group).unwrap();
+            let encoded = sheet.encode().unwrap();
+            assert_eq!(sheet, SpriteSheet::decode(&encoded).unwrap());
+        }
+
+        // Row by row sheets are encoded back to the original bytes
+        let buf = cache.read(SPRITES_ARCHIVE, 1, 0, None).unwrap();
+        assert_eq!(buf, SpriteSheet::decode(&buf).unwrap().encode().unwrap());
+    }
+
+    #[test]
+    fn test_too_many_colours() {
+        let pixels = (0..256).map(|i| 0xff000000 | (i + 1)).collect();
+        let sheet = SpriteSheet {
+            width: 16,
+            height: 16,
+            frames: vec![SpriteFrame::new(16, 16, pixels).unwrap()],
+        };
+
+        assert!(matches!(
+            sheet.encode(),
+            Err(SpriteError::TooManyColours(256))
+        ));
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let mut cache = Cache::open("tests/data/cache/cache-sprites").unwrap();
+        let sheet = cache.sprite(1).unwrap();
+        let frame = &sheet.frames[0];
+
+        let png = frame.to_png().unwrap();
+        assert_eq!(b"\x89PNG", &png[..4]);
+        assert_eq!(frame.pixels, SpriteFrame::from_png(&png).unwrap().pixels);
+    }
+}
@@ -0,0 +1,124 @@
+use osrs_bytes::{ReadExt, WriteExt};
+use std::io;
+use thiserror::Error;
+
+/// The archive holding the textures.
+pub const TEXTURES_ARCHIVE: u8 = 9;
+/// The group of the textures archive holding every texture, one file each.
+pub const TEXTURES_GROUP: u32 = 0;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum TextureError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// A texture from the textures archive, built from one or more sprites.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Texture {
+    pub id: u16,
+    /// The packed HSL colour the texture averages to, drawn on the minimap
+    /// and by clients with textures turned off
+    pub average_hsl: u16,
+    pub opaque: bool,
+    /// The groups of the sprites archive the texture is made of
+    pub sprites: Vec<u16>,
+    /// How each sprite after the first is combined with the ones before it
+    pub sprite_types: Vec<u8>,
+    pub sprite_arguments: Vec<u8>,
+    /// The colour each sprite is tinted with
+    pub colours: Vec<i32>,
+    pub animation_direction: u8,
+    pub animation_speed: u8,
+}
+
+impl Texture {
+    /// Decode a texture
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the texture, which is its file in the textures group
+    /// * `buf` - The encoded texture
+    pub fn decode(id: u16, mut buf: &[u8]) -> Result<Texture, TextureError> {
+        let average_hsl = buf.read_u16()?;
+        let opaque = buf.read_u8()? == 1;
+
+        let len = buf.read_u8()? as usize;
+        let sprites = (0..len)
+            .map(|_| buf.read_u16())
+            .collect::<io::Result<_>>()?;
+        let (sprite_types, sprite_arguments) = if len > 1 {
+            let types = (1..len).map(|_| buf.read_u8()).collect::<io::Result<_>>()?;
+            let arguments = (1..len).map(|_| buf.read_u8()).collect::<io::Result<_>>()?;
+            (types, arguments)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        let colours = (0..len)
+            .map(|_| buf.read_i32())
+            .collect::<io::Result<_>>()?;
+
+        Ok(Texture {
+            id,
+            average_hsl,
+            opaque,
+            sprites,
+            sprite_types,
+            sprite_arguments,
+            colours,
+            animation_direction: buf.read_u8()?,
+            animation_speed: buf.read_u8()?,
+        })
+    }
+
+    /// Encode the texture
+    pub fn encode(&self) -> Result<Vec<u8>, TextureError> {
+        let mut buf = Vec::new();
+
+        buf.write_u16(self.average_hsl)?;
+        buf.write_u8(self.opaque as u8)?;
+        buf.write_u8(self.sprites.len() as u8)?;
+        for sprite in &self.sprites {
+            buf.write_u16(*sprite)?;
+        }
+        if self.sprites.len() > 1 {
+            buf.extend(&self.sprite_types);
+            buf.extend(&self.sprite_arguments);
+        }
+        for colour in &self.colours {
+            buf.write_i32(*colour)?;
+        }
+        buf.write_u8(self.animation_direction)?;
+        buf.write_u8(self.animation_speed)?;
+
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    #[test]
+    fn test_round_trip() {
+        let mut cache = Cache::open("tests/data/cache/cache-minimap").unwrap();
+        let texture = cache.texture(3).unwrap();
+        assert_eq!(vec![5, 6], texture.sprites);
+        assert_eq!(
+            (vec![1], vec![2]),
+            (
+                texture.sprite_types.clone(),
+                texture.sprite_arguments.clone()
+            )
+        );
+        assert_eq!(vec![0, -1], texture.colours);
+        assert!(texture.opaque);
+
+        let buf = cache
+            .read(TEXTURES_ARCHIVE, TEXTURES_GROUP, 3, None)
+            .unwrap();
+        assert_eq!(buf, texture.encode().unwrap());
+    }
+}
@@ -0,0 +1,24 @@
+use std::sync::OnceLock;
+
+/// The client's angle step, slightly under 2π / 2048.
+const ANGLE_STEP: f64 = 0.0030679615;
+
+/// The client's sine table, scaled to 16 bits with 2048 steps per turn.
+pub(crate) fn sine() -> &'static [i32; 2048] {
+    static SINE: OnceLock<[i32; 2048]> = OnceLock::new();
+    SINE.get_or_init(|| table(f64::sin))
+}
+
+/// The client's cosine table, scaled to 16 bits with 2048 steps per turn.
+pub(crate) fn cosine() -> &'static [i32; 2048] {
+    static COSINE: OnceLock<[i32; 2048]> = OnceLock::new();
+    COSINE.get_or_init(|| table(f64::cos))
+}
+
+fn table(f: fn(f64) -> f64) -> [i32; 2048] {
+    let mut table = [0; 2048];
+    for (i, value) in table.iter_mut().enumerate() {
+        *value = (65536.0 * f(i as f64 * ANGLE_STEP)) as i32;
+    }
+    table
+}
@@ -0,0 +1,155 @@
+use crate::definitions::VarbitDefinition;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VarError {
+    #[error("varp {0} out of range")]
+    VarpOutOfRange(u16),
+    #[error("varbit {0} has an invalid bit range")]
+    InvalidBitRange(u32),
+}
+
+/// The values of a player's varps, with varbits read and written the way
+/// the client packs them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VarpState {
+    varps: Vec<i32>,
+}
+
+impl VarpState {
+    /// Create a state with every varp set to 0
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The number of varps
+    pub fn new(len: usize) -> VarpState {
+        VarpState {
+            varps: vec![0; len],
+        }
+    }
+
+    /// Get the number of varps
+    pub fn len(&self) -> usize {
+        self.varps.len()
+    }
+
+    /// Check whether there are no varps
+    pub fn is_empty(&self) -> bool {
+        self.varps.is_empty()
+    }
+
+    /// Get the value of every varp, indexed by id
+    pub fn varps(&self) -> &[i32] {
+        &self.varps
+    }
+
+    /// Get the value of a varp
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the varp
+    pub fn varp(&self, id: u16) -> Result<i32, VarError> {
+        self.varps
+            .get(id as usize)
+            .copied()
+            .ok_or(VarError::VarpOutOfRange(id))
+    }
+
+    /// Set the value of a varp
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the varp
+    /// * `value` - The new value
+    pub fn set_varp(&mut self, id: u16, value: i32) -> Result<(), VarError> {
+        *self
+            .varps
+            .get_mut(id as usize)
+            .ok_or(VarError::VarpOutOfRange(id))? = value;
+        Ok(())
+    }
+
+    /// Get the value of a varbit
+    ///
+    /// # Arguments
+    ///
+    /// * `varbit` - The definition of the varbit
+    pub fn varbit(&self, varbit: &VarbitDefinition) -> Result<i32, VarError> {
+        let mask = varbit.mask().ok_or(VarError::InvalidBitRange(varbit.id))?;
+        let varp = self.varp(varbit.base_var)? as u32;
+        Ok(((varp >> varbit.lsb) & mask) as i32)
+    }
+
+    /// Set the value of a varbit, leaving the other bits of its varp
+    /// untouched. Values that don't fit in the varbit are stored as 0, as in
+    /// the client. The client compares values against a signed mask, which is
+    /// -1 for a varbit covering all 32 bits, so such a varbit is always set
+    /// to 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `varbit` - The definition of the varbit
+    /// * `value` - The new value
+    pub fn set_varbit(&mut self, varbit: &VarbitDefinition, value: i32) -> Result<(), VarError> {
+        let mask = varbit.mask().ok_or(VarError::InvalidBitRange(varbit.id))?;
+        let value = if value < 0 || value > mask as i32 {
+            0
+        } else {
+            value as u32
+        };
+
+        let varp = self.varp(varbit.base_var)? as u32;
+        let mask = mask << varbit.lsb;
+        let varp = (varp & !mask) | ((value << varbit.lsb) & mask);
+        self.set_varp(varbit.base_var, varp as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cache;
+
+    #[test]
+    fn test_varbits() {
+        let mut cache = Cache::open("tests/data/cache/cache-config").unwrap();
+        let mut state = cache.varp_state().unwrap();
+        assert_eq!(3, state.len());
+
+        let low = cache.varbit(0).unwrap();
+        let flag = cache.varbit(1).unwrap();
+        let whole = cache.varbit(2).unwrap();
+
+        state.set_varbit(&low, 9).unwrap();
+        state.set_varbit(&flag, 1).unwrap();
+        assert_eq!(0b1_1001, state.varp(1).unwrap());
+        assert_eq!(9, state.varbit(&low).unwrap());
+        assert_eq!(1, state.varbit(&flag).unwrap());
+
+        // Out of range values clear the bits without touching the others
+        state.set_varbit(&low, 16).unwrap();
+        assert_eq!(0b1_0000, state.varp(1).unwrap());
+        state.set_varbit(&flag, -1).unwrap();
+        assert_eq!(0, state.varp(1).unwrap());
+
+        state.set_varp(2, i32::MAX).unwrap();
+        assert_eq!(i32::MAX, state.varbit(&whole).unwrap());
+        // The client's mask for all 32 bits is -1, so every value is too big
+        state.set_varbit(&whole, 1).unwrap();
+        assert_eq!(0, state.varp(2).unwrap());
+        state.set_varp(2, -1).unwrap();
+        assert_eq!(-1, state.varbit(&whole).unwrap());
+
+        assert_eq!(Err(VarError::VarpOutOfRange(3)), state.varp(3));
+        let invalid = VarbitDefinition {
+            id: 7,
+            msb: 32,
+            ..Default::default()
+        };
+        assert_eq!(
+            Err(VarError::InvalidBitRange(7)),
+            state.set_varbit(&invalid, 0)
+        );
+    }
+}
@@ -1,8 +1,10 @@
 const ROUNDS: u32 = 32;
 const RATIO: u32 = 0x9E3779B9;
 
-/// Enciphers the data with the given XTEA keys. Defaults to 32 rounds
-pub fn _xtea_encipher(data: &[u8], keys: &[u32; 4]) -> Vec<u8> {
+/// Enciphers the data with the given XTEA keys. Defaults to 32 rounds.
+///
+/// Only whole 8 byte blocks are enciphered, any trailing bytes are copied as is.
+pub fn xtea_encipher(data: &[u8], keys: &[u32; 4]) -> Vec<u8> {
     let blocks = data.len() / 8;
     let mut buf = data.to_vec();
 
@@ -22,12 +24,12 @@ pub fn _xtea_encipher(data: &[u8], keys: &[u32; 4]) -> Vec<u8> {
         ]);
         let mut sum = 0_u32;
         for _ in 0..ROUNDS {
-            v0 = v0.wrapping_sub(
+            v0 = v0.wrapping_add(
                 (((v1 << 4) ^ (v1 >> 5)).wrapping_add(v1))
                     ^ (sum.wrapping_add(keys[(sum & 3) as usize])),
             );
-            sum = sum.wrapping_sub(RATIO);
-            v1 = v1.wrapping_sub(
+            sum = sum.wrapping_add(RATIO);
+            v1 = v1.wrapping_add(
                 (((v0 << 4) ^ (v0 >> 5)).wrapping_add(v0))
                     ^ (sum.wrapping_add(keys[((sum >> 11) & 3) as usize])),
             );
@@ -42,6 +44,8 @@ pub fn _xtea_encipher(data: &[u8], keys: &[u32; 4]) -> Vec<u8> {
 }
 
 /// Deciphers the data with the given XTEA keys. Defaults to 32 rounds.
+///
+/// Only whole 8 byte blocks are deciphered, any trailing bytes are copied as is.
 pub fn xtea_decipher(data: &[u8], keys: &[u32; 4]) -> Vec<u8> {
     let blocks = data.len() / 8;
     let mut buf = data.to_vec();
@@ -76,3 +80,22 @@ pub fn xtea_decipher(data: &[u8], keys: &[u32; 4]) -> Vec<u8> {
 
     buf
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encipher_decipher_round_trip() {
+        let plain = "OpenRS2".repeat(3).into_bytes();
+
+        let cipher = xtea_encipher(&plain, &KEY);
+        assert_ne!(plain, cipher);
+        // The trailing partial block is left untouched
+        assert_eq!(plain[16..], cipher[16..]);
+
+        assert_eq!(plain, xtea_decipher(&cipher, &KEY));
+    }
+
+    const KEY: [u32; 4] = [0x00112233, 0x44556677, 0x8899AABB, 0xCCDDEEFF];
+}